@@ -91,6 +91,7 @@ pub enum ContractCommand {
     Deploy(ContractDeployArgs),
 }
 
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Clone, Subcommand, Serialize)]
 pub enum EnclaveCommand {
     /// Build the Quartz app's enclave
@@ -215,6 +216,7 @@ pub struct EnclaveBuildArgs {
     pub release: bool,
 }
 
+#[serde_as]
 #[derive(Debug, Parser, Clone, Serialize, Deserialize)]
 pub struct EnclaveStartArgs {
     /// The network chain ID
@@ -241,12 +243,31 @@ pub struct EnclaveStartArgs {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub dcap_verifier_contract: Option<AccountId>,
 
+    /// Base URL of the PCCS (Provisioning Certificate Caching Service) the enclave fetches
+    /// collateral from; defaults to a local PCCS
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    pub pccs_url: Option<Url>,
+
+    /// PEM encoded root certificate to verify the PCCS' TLS certificate against, e.g. the
+    /// self-signed certificate of a local PCCS
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pccs_ca: Option<PathBuf>,
+
+    /// Don't verify the PCCS' TLS certificate (for development only)
+    #[arg(long, conflicts_with = "pccs_ca")]
+    #[serde(skip_serializing_if = "is_false")]
+    pub pccs_insecure: bool,
+
     /// Whether to target release or dev
     #[arg(long)]
     #[serde(skip_serializing_if = "is_false")]
     pub release: bool,
 }
 
+#[serde_as]
 #[derive(Debug, Parser, Clone, Serialize, Deserialize)]
 pub struct DevArgs {
     /// Automatically deploy and instantiate new cosmwasm contract instance upon changes to source
@@ -277,6 +298,24 @@ pub struct DevArgs {
     #[arg(long)]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub dcap_verifier_contract: Option<AccountId>,
+
+    /// Base URL of the PCCS (Provisioning Certificate Caching Service) the enclave fetches
+    /// collateral from; defaults to a local PCCS
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    pub pccs_url: Option<Url>,
+
+    /// PEM encoded root certificate to verify the PCCS' TLS certificate against, e.g. the
+    /// self-signed certificate of a local PCCS
+    #[arg(long)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pccs_ca: Option<PathBuf>,
+
+    /// Don't verify the PCCS' TLS certificate (for development only)
+    #[arg(long, conflicts_with = "pccs_ca")]
+    #[serde(skip_serializing_if = "is_false")]
+    pub pccs_insecure: bool,
 }
 
 pub trait ToFigment {
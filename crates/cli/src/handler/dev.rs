@@ -164,6 +164,9 @@ fn spawn_enclave_start(args: &DevRequest, config: &Config) -> Result<()> {
         fmspc: args.fmspc.clone(),
         tcbinfo_contract: args.tcbinfo_contract.clone(),
         dcap_verifier_contract: args.dcap_verifier_contract.clone(),
+        pccs_url: args.pccs_url.clone(),
+        pccs_ca: args.pccs_ca.clone(),
+        pccs_insecure: args.pccs_insecure,
     };
 
     let config_cpy = config.clone();
@@ -87,6 +87,13 @@ impl Handler for EnclaveStartRequest {
 
             debug!("quartz_dir_canon: {:?}", quartz_dir_canon);
 
+            // the CA file is mounted into the enclave at the same (absolute) path
+            let pccs_ca = self
+                .pccs_ca
+                .map(fs::canonicalize)
+                .transpose()
+                .wrap_err("Error reading the PCCS CA certificate")?;
+
             gramine_manifest(
                 &trusted_height.to_string(),
                 &trusted_hash.to_string(),
@@ -99,6 +106,9 @@ impl Handler for EnclaveStartRequest {
                 &config.node_url,
                 &config.ws_url,
                 &config.grpc_url,
+                self.pccs_url.as_ref(),
+                pccs_ca.as_deref(),
+                self.pccs_insecure,
             )
             .await?;
 
@@ -185,6 +195,9 @@ async fn gramine_manifest(
     node_url: &Url,
     ws_url: &Url,
     grpc_url: &Url,
+    pccs_url: Option<&Url>,
+    pccs_ca: Option<&Path>,
+    pccs_insecure: bool,
 ) -> Result<()> {
     let host = target_lexicon::HOST;
     let arch_libdir = format!(
@@ -197,7 +210,18 @@ async fn gramine_manifest(
         .display()
         .to_string();
 
-    let status = Command::new("gramine-manifest")
+    let mut command = Command::new("gramine-manifest");
+    if let Some(pccs_url) = pccs_url {
+        command.arg(format!("-Dpccs_url={}", pccs_url));
+    }
+    if let Some(pccs_ca) = pccs_ca {
+        command.arg(format!("-Dpccs_ca={}", pccs_ca.display()));
+    }
+    if pccs_insecure {
+        command.arg("-Dpccs_insecure=1");
+    }
+
+    let status = command
         .arg("-Dlog_level=error")
         .arg(format!("-Dhome={}", home_dir))
         .arg(format!("-Darch_libdir={}", arch_libdir))
@@ -62,6 +62,9 @@ impl TryFrom<Command> for Request {
                     fmspc: args.fmspc,
                     tcbinfo_contract: args.tcbinfo_contract,
                     dcap_verifier_contract: args.dcap_verifier_contract,
+                    pccs_url: args.pccs_url,
+                    pccs_ca: args.pccs_ca,
+                    pccs_insecure: args.pccs_insecure,
                 }
                 .into())
             }
@@ -118,6 +121,9 @@ impl TryFrom<EnclaveCommand> for Request {
                 fmspc: args.fmspc,
                 tcbinfo_contract: args.tcbinfo_contract,
                 dcap_verifier_contract: args.dcap_verifier_contract,
+                pccs_url: args.pccs_url,
+                pccs_ca: args.pccs_ca,
+                pccs_insecure: args.pccs_insecure,
             }
             .into()),
         }
@@ -2,6 +2,7 @@ use std::path::PathBuf;
 
 use cosmrs::AccountId;
 use quartz_common::enclave::types::Fmspc;
+use reqwest::Url;
 
 use crate::request::Request;
 
@@ -16,6 +17,9 @@ pub struct DevRequest {
     pub fmspc: Option<Fmspc>,
     pub tcbinfo_contract: Option<AccountId>,
     pub dcap_verifier_contract: Option<AccountId>,
+    pub pccs_url: Option<Url>,
+    pub pccs_ca: Option<PathBuf>,
+    pub pccs_insecure: bool,
 }
 
 impl From<DevRequest> for Request {
@@ -1,6 +1,9 @@
+use std::path::PathBuf;
+
 use color_eyre::Result;
 use cosmrs::AccountId;
 use quartz_common::enclave::types::Fmspc;
+use reqwest::Url;
 use tendermint::{block::Height, Hash};
 use tracing::debug;
 
@@ -12,6 +15,9 @@ pub struct EnclaveStartRequest {
     pub fmspc: Option<Fmspc>,
     pub tcbinfo_contract: Option<AccountId>,
     pub dcap_verifier_contract: Option<AccountId>,
+    pub pccs_url: Option<Url>,
+    pub pccs_ca: Option<PathBuf>,
+    pub pccs_insecure: bool,
 }
 
 impl From<EnclaveStartRequest> for Request {
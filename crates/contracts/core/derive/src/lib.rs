@@ -1,27 +1,145 @@
 use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
 use quote::quote;
-use syn::{parse_macro_input, DeriveInput};
+use syn::{
+    parse_macro_input, Attribute, Data, DeriveInput, Error, Fields, Ident, Lit, Meta, NestedMeta,
+};
 
-#[proc_macro_derive(UserData)]
+/// Derives `HasUserData` for a message.
+///
+/// By default, the user data is the SHA-256 hash of the message's JSON serialization. The
+/// following attributes opt into canonical (i.e. sorted-key) JSON and domain separation (see
+/// `quartz_contract_core::msg::execute::attested::canonical_user_data`):
+///
+/// * `#[user_data(canonical)]` - hash the canonical JSON of the message.
+/// * `#[user_data(domain)]` or `#[user_data(domain = "...")]` - additionally bind the user data to
+///   the message type, i.e. the type's name or the specified one.
+/// * `#[user_data(contract)]` and `#[user_data(chain_id)]` on fields - additionally bind the user
+///   data to the contract address and chain ID in those fields. The contract MUST check that they
+///   match its own.
+#[proc_macro_derive(UserData, attributes(user_data))]
 pub fn user_data_derive(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+#[derive(Default)]
+struct Options {
+    canonical: bool,
+    domain: Option<String>,
+    contract: Option<Ident>,
+    chain_id: Option<Ident>,
+}
+
+fn expand(input: DeriveInput) -> Result<TokenStream2, Error> {
     let name = input.ident;
+    let options = parse_options(&name, &input.attrs, &input.data)?;
+
+    let body = if let Some(msg_type) = options.domain {
+        let contract = options.contract.map(|field| {
+            quote! { .with_contract(::core::convert::AsRef::<str>::as_ref(&self.#field)) }
+        });
+        let chain_id = options.chain_id.map(|field| {
+            quote! { .with_chain_id(::core::convert::AsRef::<str>::as_ref(&self.#field)) }
+        });
+        quote! {
+            let domain = ::quartz_contract_core::msg::execute::attested::UserDataDomain::new(#msg_type)
+                #contract
+                #chain_id;
+            ::quartz_contract_core::msg::execute::attested::canonical_user_data(Some(domain), self)
+        }
+    } else if options.canonical {
+        quote! {
+            ::quartz_contract_core::msg::execute::attested::canonical_user_data(None, self)
+        }
+    } else {
+        quote! {
+            use ::sha2::Digest;
+
+            let mut hasher = ::sha2::Sha256::new();
+            hasher.update(::serde_json::to_string(&self).expect("infallible serializer"));
+            let digest: [u8; 32] = hasher.finalize().into();
+
+            let mut user_data = [0u8; 64];
+            user_data[0..32].copy_from_slice(&digest);
+            user_data
+        }
+    };
 
-    let expanded = quote! {
+    Ok(quote! {
         impl ::quartz_contract_core::msg::execute::attested::HasUserData for #name {
             fn user_data(&self) -> ::quartz_contract_core::state::UserData {
-                use ::sha2::Digest;
+                #body
+            }
+        }
+    })
+}
 
-                let mut hasher = ::sha2::Sha256::new();
-                hasher.update(::serde_json::to_string(&self).expect("infallible serializer"));
-                let digest: [u8; 32] = hasher.finalize().into();
+fn parse_options(name: &Ident, attrs: &[Attribute], data: &Data) -> Result<Options, Error> {
+    let mut options = Options::default();
 
-                let mut user_data = [0u8; 64];
-                user_data[0..32].copy_from_slice(&digest);
-                user_data
+    for meta in user_data_attrs(attrs)? {
+        match &meta {
+            NestedMeta::Meta(Meta::Path(path)) if path.is_ident("canonical") => {
+                options.canonical = true;
+            }
+            NestedMeta::Meta(Meta::Path(path)) if path.is_ident("domain") => {
+                options.domain = Some(name.to_string());
             }
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("domain") => match &nv.lit {
+                Lit::Str(msg_type) => options.domain = Some(msg_type.value()),
+                lit => return Err(Error::new_spanned(lit, "expected a string literal")),
+            },
+            meta => return Err(Error::new_spanned(meta, "unknown user_data attribute")),
         }
+    }
+
+    let fields = match data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => fields.named.iter().collect(),
+            _ => vec![],
+        },
+        _ => vec![],
     };
+    for field in fields {
+        for meta in user_data_attrs(&field.attrs)? {
+            let slot = match &meta {
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("contract") => {
+                    &mut options.contract
+                }
+                NestedMeta::Meta(Meta::Path(path)) if path.is_ident("chain_id") => {
+                    &mut options.chain_id
+                }
+                meta => return Err(Error::new_spanned(meta, "unknown user_data attribute")),
+            };
+            if slot.is_some() {
+                return Err(Error::new_spanned(meta, "duplicate user_data attribute"));
+            }
+            *slot = field.ident.clone();
+        }
+    }
 
-    TokenStream::from(expanded)
+    if options.domain.is_none() {
+        if let Some(field) = options.contract.as_ref().or(options.chain_id.as_ref()) {
+            return Err(Error::new_spanned(
+                field,
+                "contract and chain_id fields require #[user_data(domain)]",
+            ));
+        }
+    }
+
+    Ok(options)
+}
+
+fn user_data_attrs(attrs: &[Attribute]) -> Result<Vec<NestedMeta>, Error> {
+    let mut metas = vec![];
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("user_data")) {
+        match attr.parse_meta()? {
+            Meta::List(list) => metas.extend(list.nested),
+            meta => return Err(Error::new_spanned(meta, "expected #[user_data(...)]")),
+        }
+    }
+    Ok(metas)
 }
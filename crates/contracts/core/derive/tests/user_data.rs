@@ -0,0 +1,97 @@
+use quartz_contract_core::msg::execute::attested::{
+    canonical_user_data, HasUserData, UserDataDomain,
+};
+use quartz_contract_core_derive::UserData;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+#[derive(Serialize, UserData)]
+struct Legacy {
+    b: u8,
+    a: u8,
+}
+
+#[derive(Serialize, UserData)]
+#[user_data(canonical)]
+struct Canonical {
+    b: u8,
+    a: u8,
+}
+
+#[derive(Serialize, UserData)]
+#[user_data(canonical)]
+struct CanonicalReordered {
+    a: u8,
+    b: u8,
+}
+
+#[derive(Serialize, UserData)]
+#[user_data(domain)]
+struct Ping {
+    a: u8,
+    b: u8,
+}
+
+#[derive(Serialize, UserData)]
+#[user_data(domain)]
+struct Pong {
+    a: u8,
+    b: u8,
+}
+
+#[derive(Serialize, UserData)]
+#[user_data(domain = "app/bound")]
+struct Bound {
+    a: u8,
+    #[user_data(contract)]
+    contract: String,
+    #[user_data(chain_id)]
+    chain_id: String,
+}
+
+#[test]
+fn legacy_user_data_is_unchanged() {
+    let digest: [u8; 32] = Sha256::digest(r#"{"b":2,"a":1}"#).into();
+    let user_data = Legacy { b: 2, a: 1 }.user_data();
+    assert_eq!(user_data[..32], digest);
+    assert_eq!(user_data[32..], [0; 32]);
+}
+
+#[test]
+fn canonical_user_data_ignores_field_order() {
+    assert_eq!(
+        Canonical { b: 2, a: 1 }.user_data(),
+        CanonicalReordered { a: 1, b: 2 }.user_data()
+    );
+}
+
+#[test]
+fn domain_separates_msg_types() {
+    let ping = Ping { a: 1, b: 2 }.user_data();
+    assert_ne!(ping, Pong { a: 1, b: 2 }.user_data());
+    assert_ne!(ping, CanonicalReordered { a: 1, b: 2 }.user_data());
+    assert_eq!(
+        ping,
+        canonical_user_data(Some(UserDataDomain::new("Ping")), &Ping { a: 1, b: 2 })
+    );
+}
+
+#[test]
+fn domain_binds_contract_and_chain_id() {
+    let msg = |contract: &str, chain_id: &str| Bound {
+        a: 1,
+        contract: contract.to_string(),
+        chain_id: chain_id.to_string(),
+    };
+    let user_data = msg("wasm1a", "testing").user_data();
+    assert_ne!(user_data, msg("wasm1b", "testing").user_data());
+    assert_ne!(user_data, msg("wasm1a", "mainnet").user_data());
+
+    let domain = UserDataDomain::new("app/bound")
+        .with_contract("wasm1a")
+        .with_chain_id("testing");
+    assert_eq!(
+        user_data,
+        canonical_user_data(Some(domain), &msg("wasm1a", "testing"))
+    );
+}
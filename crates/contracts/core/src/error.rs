@@ -13,14 +13,38 @@ pub enum Error {
     K256(K256Error),
     #[error("invalid session nonce or attempt to reset pub_key")]
     BadSessionTransition,
+    #[error("tcbinfo contract address is required for DCAP")]
+    MissingTcbInfoContract,
+    #[error("dcap-verifier contract address is required for DCAP")]
+    MissingDcapVerifierContract,
     #[error("Invalid FMSPC: {0}")]
     InvalidFmspc(String),
     #[error("TCB Info query error: {0}")]
     TcbInfoQueryError(String),
+    #[error("TCB Info is stale, i.e. its next update has passed")]
+    StaleTcbInfo,
+    #[error("Invalid collateral: {0}")]
+    InvalidCollateral(String),
+    #[error("Serialization error: {0}")]
+    Serialization(String),
     #[error("DCAP verification query error: {0}")]
     DcapVerificationQueryError(String),
+    #[error("DCAP verification failed: {0}")]
+    DcapVerificationFailed(String),
     #[error("contract address mismatch")]
     ContractAddrMismatch,
+    #[error("chain ID mismatch")]
+    ChainIdMismatch,
+    #[error("unauthorized")]
+    Unauthorized,
+    #[error("no matching session handover request")]
+    HandoverMismatch,
+    #[error("no session key to verify the signature against")]
+    MissingSessionKey,
+    #[error("invalid session key signature")]
+    InvalidSessionKeySignature,
+    #[error("the session key holder is not an active enclave")]
+    InactiveSessionHolder,
 }
 
 impl From<K256Error> for Error {
@@ -1,7 +1,8 @@
 pub mod execute;
 pub mod instantiate;
+pub mod query;
 
-use cosmwasm_std::{DepsMut, Env, MessageInfo, Response};
+use cosmwasm_std::{Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
 
 use crate::{error::Error, msg::HasDomainType};
 
@@ -34,6 +35,10 @@ where
     }
 }
 
+pub trait QueryHandler {
+    fn handle_query(self, deps: Deps<'_>, env: &Env) -> StdResult<Binary>;
+}
+
 #[cfg(test)]
 mod tests {
     use cosmwasm_std::{
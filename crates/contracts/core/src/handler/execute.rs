@@ -1,9 +1,17 @@
+pub mod add_mr_enclave;
 pub mod attested;
+pub mod batched;
+pub mod retire_mr_enclave;
 pub mod sequenced;
 pub mod session_create;
+pub mod session_handover_complete;
+pub mod session_handover_request;
+pub mod session_rotate_pub_key;
 pub mod session_set_pub_key;
+pub mod set_attestation_policy;
 
-use cosmwasm_std::{DepsMut, Env, MessageInfo, Response};
+use cosmwasm_std::{Deps, DepsMut, Env, MessageInfo, Response};
+use quartz_tee_ra::Error as RaVerificationError;
 
 use crate::{
     error::Error,
@@ -12,16 +20,624 @@ use crate::{
         attested::{Attestation, HasUserData},
         Execute,
     },
+    state::{MrEnclave, SESSION},
 };
 
+/// Ensures that `mr_enclave` is the enclave holding the session key.
+fn ensure_session_holder(deps: Deps<'_>, mr_enclave: MrEnclave) -> Result<(), Error> {
+    let session = SESSION.load(deps.storage).map_err(Error::Std)?;
+    if !session.is_held_by(mr_enclave) {
+        return Err(RaVerificationError::MrEnclaveMismatch.into());
+    }
+    Ok(())
+}
+
 impl<A> Handler for Execute<A>
 where
     A: Handler + HasUserData + Attestation,
 {
-    fn handle(self, deps: DepsMut<'_>, env: &Env, info: &MessageInfo) -> Result<Response, Error> {
+    fn handle(
+        self,
+        mut deps: DepsMut<'_>,
+        env: &Env,
+        info: &MessageInfo,
+    ) -> Result<Response, Error> {
         match self {
             Execute::SessionCreate(msg) => msg.handle(deps, env, info),
-            Execute::SessionSetPubKey(msg) => msg.handle(deps, env, info),
+            Execute::SessionSetPubKey(msg) => {
+                // the enclave that completes the handshake holds the session key
+                let holder = msg.attestation().mr_enclave();
+                let res = msg.handle(deps.branch(), env, info)?;
+                let session = SESSION.load(deps.storage).map_err(Error::Std)?;
+                SESSION
+                    .save(deps.storage, &session.with_holder(holder))
+                    .map_err(Error::Std)?;
+                Ok(res)
+            }
+            Execute::SessionRotatePubKey(msg) => {
+                // other enclaves must take over the session via a handover
+                ensure_session_holder(deps.as_ref(), msg.attestation().mr_enclave())?;
+                msg.handle(deps, env, info)
+            }
+            Execute::SessionHandoverRequest(msg) => {
+                // the requesting enclave must attest to its own mr_enclave
+                if msg.msg().mr_enclave() != msg.attestation().mr_enclave() {
+                    return Err(RaVerificationError::MrEnclaveMismatch.into());
+                }
+                msg.handle(deps, env, info)
+            }
+            Execute::SessionHandoverComplete(msg) => {
+                // only the enclave holding the session can hand it over
+                ensure_session_holder(deps.as_ref(), msg.attestation().mr_enclave())?;
+                msg.handle(deps, env, info)
+            }
+            Execute::AddMrEnclave(msg) => msg.handle(deps, env, info),
+            Execute::RetireMrEnclave(msg) => msg.handle(deps, env, info),
+            Execute::SetAttestationPolicy(msg) => msg.handle(deps, env, info),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{
+        testing::{message_info, mock_dependencies, mock_env, MockApi},
+        DepsMut, Env, MessageInfo, Response,
+    };
+    use k256::ecdsa::SigningKey;
+    use quartz_tee_ra::{
+        intel_sgx::dcap::{AttestationPolicy, TcbStatus},
+        Error as RaVerificationError,
+    };
+
+    use crate::{
+        error::Error,
+        handler::Handler,
+        msg::execute::{
+            add_mr_enclave::AddMrEnclave,
+            attested::{
+                Attestation, Attested, HasUserData, Noop, SessionKeyAttestation, SignerIdentity,
+            },
+            batched::{batch, Batched},
+            retire_mr_enclave::RetireMrEnclave,
+            session_handover_complete::SessionHandoverComplete,
+            session_handover_request::SessionHandoverRequest,
+            session_rotate_pub_key::SessionRotatePubKey,
+            session_set_pub_key::SessionSetPubKey,
+            set_attestation_policy::SetAttestationPolicy,
+            Execute,
+        },
+        state::{
+            Config, LightClientOpts, MrEnclave, MrSignerIdentity, RawConfig, Session, UserData,
+            CONFIG, PENDING_HANDOVER, SESSION, VERIFIED_BATCH_TTL,
+        },
+    };
+
+    const OLD_MR_ENCLAVE: MrEnclave = [1; 32];
+    const NEW_MR_ENCLAVE: MrEnclave = [2; 32];
+    const NONCE: [u8; 32] = [3; 32];
+    const MR_SIGNER: [u8; 32] = [5; 32];
+
+    /// An attestation that is always valid, for an arbitrary mr_enclave and signer.
+    #[derive(Clone, Debug, PartialEq)]
+    struct TestAttestation(MrEnclave, Option<SignerIdentity>, UserData);
+
+    impl TestAttestation {
+        fn new(mr_enclave: MrEnclave, msg: &impl HasUserData) -> Self {
+            Self(mr_enclave, None, msg.user_data())
+        }
+
+        fn signed(mr_enclave: MrEnclave, isv_svn: u16, msg: &impl HasUserData) -> Self {
+            let signer = SignerIdentity {
+                mr_signer: MR_SIGNER,
+                isv_prod_id: 1,
+                isv_svn,
+            };
+            Self(mr_enclave, Some(signer), msg.user_data())
+        }
+    }
+
+    impl Attestation for TestAttestation {
+        fn mr_enclave(&self) -> MrEnclave {
+            self.0
+        }
+
+        fn signer_identity(&self) -> Option<SignerIdentity> {
+            self.1
+        }
+    }
+
+    impl HasUserData for TestAttestation {
+        fn user_data(&self) -> UserData {
+            self.2
+        }
+    }
+
+    impl Handler for TestAttestation {
+        fn handle(self, _: DepsMut<'_>, _: &Env, _: &MessageInfo) -> Result<Response, Error> {
+            Ok(Response::new().add_attribute("attestation", "verified"))
+        }
+    }
+
+    fn is_attestation_verified(res: &Response) -> bool {
+        res.attributes.iter().any(|a| a.key == "attestation")
+    }
+
+    fn execute(
+        deps: DepsMut<'_>,
+        env: &Env,
+        sender: &str,
+        msg: Execute<TestAttestation>,
+    ) -> Result<Response, Error> {
+        let info = message_info(&MockApi::default().addr_make(sender), &[]);
+        msg.handle(deps, env, &info)
+    }
+
+    /// A mock env whose contract address is a valid bech32 address (unlike the default one).
+    fn test_env() -> Env {
+        let mut env = mock_env();
+        env.contract.address = MockApi::default().addr_make("contract");
+        env
+    }
+
+    fn contract() -> String {
+        test_env().contract.address.to_string()
+    }
+
+    fn chain_id() -> String {
+        test_env().block.chain_id
+    }
+
+    fn request(mr_enclave: MrEnclave, attested_by: MrEnclave) -> Execute<TestAttestation> {
+        let msg =
+            SessionHandoverRequest::new(NONCE, mr_enclave, vec![4; 33], contract(), chain_id());
+        let attestation = TestAttestation::new(attested_by, &msg);
+        Execute::SessionHandoverRequest(Attested::new(msg, attestation))
+    }
+
+    fn complete(
+        mr_enclave: MrEnclave,
+        pub_key: Vec<u8>,
+        attested_by: MrEnclave,
+    ) -> Execute<TestAttestation> {
+        let msg =
+            SessionHandoverComplete::new(NONCE, 1, mr_enclave, pub_key, contract(), chain_id());
+        let attestation = TestAttestation::new(attested_by, &msg);
+        Execute::SessionHandoverComplete(Attested::new(msg, attestation))
+    }
+
+    fn setup(deps: DepsMut<'_>, mr_signer: Option<MrSignerIdentity>) {
+        let light_client_opts =
+            LightClientOpts::new("testing".to_string(), 1, [0; 32], (1, 3), 1_209_600, 5, 20)
+                .expect("valid light client opts");
+        let mut config = Config::new(OLD_MR_ENCLAVE, light_client_opts, None, None)
+            .with_admin(MockApi::default().addr_make("admin").to_string());
+        if let Some(mr_signer) = mr_signer {
+            config = config.with_mr_signer(mr_signer);
+        }
+        CONFIG
+            .save(deps.storage, &RawConfig::from(config))
+            .expect("failed to save config");
+        let session = Session::create(NONCE)
+            .with_pub_key(NONCE, vec![3; 33])
+            .expect("valid session transition")
+            .with_holder(OLD_MR_ENCLAVE);
+        SESSION
+            .save(deps.storage, &session)
+            .expect("failed to save session");
+    }
+
+    #[test]
+    fn test_mr_enclave_upgrade_with_handover() {
+        let mut deps = mock_dependencies();
+        let mut env = test_env();
+        setup(deps.as_mut(), None);
+
+        // the new enclave isn't allowlisted yet
+        let res = execute(
+            deps.as_mut(),
+            &env,
+            "host",
+            request(NEW_MR_ENCLAVE, NEW_MR_ENCLAVE),
+        );
+        assert!(matches!(
+            res,
+            Err(Error::RaVerification(
+                RaVerificationError::MrEnclaveMismatch
+            ))
+        ));
+
+        let add = || Execute::AddMrEnclave(AddMrEnclave::new(NEW_MR_ENCLAVE, None, None));
+        let res = execute(deps.as_mut(), &env, "mallory", add());
+        assert!(matches!(res, Err(Error::Unauthorized)));
+        execute(deps.as_mut(), &env, "admin", add()).expect("admin can add mr_enclave");
+
+        // a handover request must be attested by the enclave it names
+        let res = execute(
+            deps.as_mut(),
+            &env,
+            "host",
+            request(NEW_MR_ENCLAVE, OLD_MR_ENCLAVE),
+        );
+        assert!(matches!(
+            res,
+            Err(Error::RaVerification(
+                RaVerificationError::MrEnclaveMismatch
+            ))
+        ));
+        execute(
+            deps.as_mut(),
+            &env,
+            "host",
+            request(NEW_MR_ENCLAVE, NEW_MR_ENCLAVE),
+        )
+        .expect("handover request from allowlisted enclave");
+
+        // the completion must match the pending request
+        let res = execute(
+            deps.as_mut(),
+            &env,
+            "host",
+            complete(NEW_MR_ENCLAVE, vec![5; 33], OLD_MR_ENCLAVE),
+        );
+        assert!(matches!(res, Err(Error::HandoverMismatch)));
+        execute(
+            deps.as_mut(),
+            &env,
+            "host",
+            complete(NEW_MR_ENCLAVE, vec![4; 33], OLD_MR_ENCLAVE),
+        )
+        .expect("handover completion");
+
+        let session = SESSION.load(&deps.storage).expect("session exists");
+        assert_eq!(session.epoch(), 1);
+        assert_eq!(session.pub_key(), Some(&vec![4; 33].into()));
+        assert_eq!(session.holder(), Some(NEW_MR_ENCLAVE));
+        assert!(PENDING_HANDOVER
+            .may_load(&deps.storage)
+            .expect("storage read")
+            .is_none());
+
+        // once retired, the old enclave's attestations are rejected
+        let retire = Execute::RetireMrEnclave(RetireMrEnclave::new(OLD_MR_ENCLAVE, None));
+        execute(deps.as_mut(), &env, "admin", retire).expect("admin can retire mr_enclave");
+        env.block.height += 1;
+        let res = execute(
+            deps.as_mut(),
+            &env,
+            "host",
+            request(OLD_MR_ENCLAVE, OLD_MR_ENCLAVE),
+        );
+        assert!(matches!(
+            res,
+            Err(Error::RaVerification(
+                RaVerificationError::MrEnclaveMismatch
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_handover_must_be_completed_by_session_holder() {
+        let mut deps = mock_dependencies();
+        let env = test_env();
+        setup(deps.as_mut(), None);
+
+        // the enclave that sets the session key holds the session
+        SESSION
+            .save(deps.as_mut().storage, &Session::create(NONCE))
+            .expect("failed to save session");
+        let msg = SessionSetPubKey::new(NONCE, vec![3; 33]);
+        let attestation = TestAttestation::new(OLD_MR_ENCLAVE, &msg);
+        let set_pub_key = Execute::SessionSetPubKey(Attested::new(msg, attestation));
+        execute(deps.as_mut(), &env, "host", set_pub_key).expect("handshake completion");
+        let session = SESSION.load(&deps.storage).expect("session exists");
+        assert_eq!(session.holder(), Some(OLD_MR_ENCLAVE));
+
+        let add = Execute::AddMrEnclave(AddMrEnclave::new(NEW_MR_ENCLAVE, None, None));
+        execute(deps.as_mut(), &env, "admin", add).expect("admin can add mr_enclave");
+        execute(
+            deps.as_mut(),
+            &env,
+            "host",
+            request(NEW_MR_ENCLAVE, NEW_MR_ENCLAVE),
+        )
+        .expect("handover request from allowlisted enclave");
+
+        // the requesting enclave can't complete the handover itself
+        let res = execute(
+            deps.as_mut(),
+            &env,
+            "host",
+            complete(NEW_MR_ENCLAVE, vec![4; 33], NEW_MR_ENCLAVE),
+        );
+        assert!(matches!(
+            res,
+            Err(Error::RaVerification(
+                RaVerificationError::MrEnclaveMismatch
+            ))
+        ));
+
+        // nor can the holder hand the session over to itself
+        execute(
+            deps.as_mut(),
+            &env,
+            "host",
+            request(OLD_MR_ENCLAVE, OLD_MR_ENCLAVE),
+        )
+        .expect("handover request from allowlisted enclave");
+        let res = execute(
+            deps.as_mut(),
+            &env,
+            "host",
+            complete(OLD_MR_ENCLAVE, vec![4; 33], OLD_MR_ENCLAVE),
+        );
+        assert!(matches!(res, Err(Error::HandoverMismatch)));
+
+        let session = SESSION.load(&deps.storage).expect("session exists");
+        assert_eq!(session.epoch(), 0);
+        assert_eq!(session.holder(), Some(OLD_MR_ENCLAVE));
+    }
+
+    #[test]
+    fn test_session_rotate_pub_key() {
+        let mut deps = mock_dependencies();
+        let env = test_env();
+        setup(deps.as_mut(), None);
+
+        let rotate = |nonce, epoch, pub_key| {
+            let msg = SessionRotatePubKey::new(nonce, epoch, pub_key, contract(), chain_id());
+            let attestation = TestAttestation::new(OLD_MR_ENCLAVE, &msg);
+            Execute::SessionRotatePubKey(Attested::new(msg, attestation))
+        };
+
+        // the epoch must be exactly one more than the current one
+        let res = execute(deps.as_mut(), &env, "host", rotate(NONCE, 2, vec![4; 33]));
+        assert!(matches!(res, Err(Error::BadSessionTransition)));
+        let res = execute(deps.as_mut(), &env, "host", rotate([9; 32], 1, vec![4; 33]));
+        assert!(matches!(res, Err(Error::BadSessionTransition)));
+
+        // the rotation is bound to the contract and chain
+        let rotate_at = |contract: String, chain_id: String| {
+            let msg = SessionRotatePubKey::new(NONCE, 1, vec![4; 33], contract, chain_id);
+            let attestation = TestAttestation::new(OLD_MR_ENCLAVE, &msg);
+            Execute::SessionRotatePubKey(Attested::new(msg, attestation))
+        };
+        let other_contract = MockApi::default().addr_make("other").to_string();
+        let res = execute(
+            deps.as_mut(),
+            &env,
+            "host",
+            rotate_at(other_contract, chain_id()),
+        );
+        assert!(matches!(res, Err(Error::ContractAddrMismatch)));
+        let res = execute(
+            deps.as_mut(),
+            &env,
+            "host",
+            rotate_at(contract(), "other-chain".to_string()),
+        );
+        assert!(matches!(res, Err(Error::ChainIdMismatch)));
+
+        // only the session holder can rotate the key
+        let msg = SessionRotatePubKey::new(NONCE, 1, vec![4; 33], contract(), chain_id());
+        let attestation = TestAttestation::new(NEW_MR_ENCLAVE, &msg);
+        let res = execute(
+            deps.as_mut(),
+            &env,
+            "host",
+            Execute::SessionRotatePubKey(Attested::new(msg, attestation)),
+        );
+        assert!(matches!(
+            res,
+            Err(Error::RaVerification(
+                RaVerificationError::MrEnclaveMismatch
+            ))
+        ));
+        execute(deps.as_mut(), &env, "host", rotate(NONCE, 1, vec![4; 33]))
+            .expect("rotation to the next epoch");
+
+        let session = SESSION.load(&deps.storage).expect("session exists");
+        assert_eq!(session.epoch(), 1);
+        assert_eq!(session.pub_key(), Some(&vec![4; 33].into()));
+
+        // a rotation can't be replayed
+        let res = execute(deps.as_mut(), &env, "host", rotate(NONCE, 1, vec![5; 33]));
+        assert!(matches!(res, Err(Error::BadSessionTransition)));
+        execute(deps.as_mut(), &env, "host", rotate(NONCE, 2, vec![5; 33]))
+            .expect("rotation to the next epoch");
+        let session = SESSION.load(&deps.storage).expect("session exists");
+        assert_eq!(session.epoch(), 2);
+        assert_eq!(session.pub_key(), Some(&vec![5; 33].into()));
+    }
+
+    #[test]
+    fn test_mr_signer_identity() {
+        let mut deps = mock_dependencies();
+        let env = test_env();
+        setup(deps.as_mut(), Some(MrSignerIdentity::new(MR_SIGNER, 1, 2)));
+
+        let request = |isv_svn| {
+            let msg = SessionHandoverRequest::new(
+                NONCE,
+                NEW_MR_ENCLAVE,
+                vec![4; 33],
+                contract(),
+                chain_id(),
+            );
+            let attestation = TestAttestation::signed(NEW_MR_ENCLAVE, isv_svn, &msg);
+            Execute::SessionHandoverRequest(Attested::new(msg, attestation))
+        };
+
+        // a patched build isn't allowlisted but is signed by the pinned signer
+        execute(deps.as_mut(), &env, "host", request(3)).expect("newer svn is trusted");
+        execute(deps.as_mut(), &env, "host", request(2)).expect("minimum svn is trusted");
+        let res = execute(deps.as_mut(), &env, "host", request(1));
+        assert!(matches!(
+            res,
+            Err(Error::RaVerification(
+                RaVerificationError::MrEnclaveMismatch
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_set_attestation_policy() {
+        let mut deps = mock_dependencies();
+        let env = test_env();
+        setup(deps.as_mut(), None);
+
+        let config: Config = CONFIG
+            .load(&deps.storage)
+            .expect("config exists")
+            .try_into()
+            .expect("valid config");
+        assert_eq!(config.attestation_policy(), &AttestationPolicy::default());
+
+        let policy = AttestationPolicy::new(
+            [TcbStatus::UpToDate, TcbStatus::OutOfDate],
+            ["INTEL-SA-00615"],
+        );
+        let set = || Execute::SetAttestationPolicy(SetAttestationPolicy::new(policy.clone()));
+        let res = execute(deps.as_mut(), &env, "mallory", set());
+        assert!(matches!(res, Err(Error::Unauthorized)));
+        execute(deps.as_mut(), &env, "admin", set()).expect("admin can set policy");
+
+        let config: Config = CONFIG
+            .load(&deps.storage)
+            .expect("config exists")
+            .try_into()
+            .expect("valid config");
+        assert_eq!(config.attestation_policy(), &policy);
+    }
+
+    #[test]
+    fn test_session_key_attestation() {
+        let mut deps = mock_dependencies();
+        let mut env = test_env();
+        setup(deps.as_mut(), None);
+
+        let session_key = SigningKey::from_bytes(&[7; 32].into()).expect("valid signing key");
+        let pub_key = session_key.verifying_key().to_sec1_bytes().to_vec();
+        let session = Session::create(NONCE)
+            .with_pub_key(NONCE, pub_key)
+            .expect("valid session transition")
+            .with_holder(OLD_MR_ENCLAVE);
+        SESSION
+            .save(deps.as_mut().storage, &session)
+            .expect("failed to save session");
+
+        let msg = Noop(SessionHandoverRequest::new(
+            NONCE,
+            NEW_MR_ENCLAVE,
+            vec![4; 33],
+            contract(),
+            chain_id(),
+        ));
+        let info = message_info(&MockApi::default().addr_make("host"), &[]);
+
+        let attestation = SessionKeyAttestation::sign(&session_key, &msg);
+        Attested::new(msg.clone(), attestation)
+            .handle(deps.as_mut(), &env, &info)
+            .expect("signed by the session key");
+
+        let other_key = SigningKey::from_bytes(&[8; 32].into()).expect("valid signing key");
+        let attestation = SessionKeyAttestation::sign(&other_key, &msg);
+        let res = Attested::new(msg.clone(), attestation).handle(deps.as_mut(), &env, &info);
+        assert!(matches!(res, Err(Error::InvalidSessionKeySignature)));
+
+        // the signature must be over the message's user data
+        let other_msg = Noop(SessionHandoverRequest::new(
+            NONCE,
+            OLD_MR_ENCLAVE,
+            vec![4; 33],
+            contract(),
+            chain_id(),
+        ));
+        let attestation = SessionKeyAttestation::sign(&session_key, &other_msg);
+        let res = Attested::new(msg.clone(), attestation).handle(deps.as_mut(), &env, &info);
+        assert!(matches!(
+            res,
+            Err(Error::RaVerification(RaVerificationError::UserDataMismatch))
+        ));
+
+        // the session key isn't trusted once its holder is retired
+        let add = Execute::AddMrEnclave(AddMrEnclave::new(NEW_MR_ENCLAVE, None, None));
+        execute(deps.as_mut(), &env, "admin", add).expect("admin can add mr_enclave");
+        let retire = Execute::RetireMrEnclave(RetireMrEnclave::new(OLD_MR_ENCLAVE, None));
+        execute(deps.as_mut(), &env, "admin", retire).expect("admin can retire mr_enclave");
+        env.block.height += 1;
+        let attestation = SessionKeyAttestation::sign(&session_key, &msg);
+        let res = Attested::new(msg, attestation).handle(deps.as_mut(), &env, &info);
+        assert!(matches!(res, Err(Error::InactiveSessionHolder)));
+    }
+
+    #[test]
+    fn test_batched_attestation() {
+        let mut deps = mock_dependencies();
+        let mut env = test_env();
+        setup(deps.as_mut(), None);
+        let info = message_info(&MockApi::default().addr_make("host"), &[]);
+
+        let msgs = (0..3)
+            .map(|i| {
+                Noop(SessionHandoverRequest::new(
+                    NONCE,
+                    NEW_MR_ENCLAVE,
+                    vec![i; 33],
+                    contract(),
+                    chain_id(),
+                ))
+            })
+            .collect();
+        let (root, batched) = batch(msgs).expect("non-empty batch");
+        let attestation = TestAttestation::new(OLD_MR_ENCLAVE, &root);
+
+        // a single attestation covers every message in the batch, it's only verified once
+        for (i, msg) in batched.clone().into_iter().enumerate() {
+            let res = Attested::new(msg, attestation.clone())
+                .handle(deps.as_mut(), &env, &info)
+                .expect("message is part of the attested batch");
+            assert_eq!(is_attestation_verified(&res), i == 0);
         }
+
+        // unless it's attested with other claims
+        let signed = TestAttestation::signed(OLD_MR_ENCLAVE, 1, &root);
+        let res = Attested::new(batched[0].clone(), signed.clone())
+            .handle(deps.as_mut(), &env, &info)
+            .expect("message is part of the attested batch");
+        assert!(is_attestation_verified(&res));
+        let res = Attested::new(batched[1].clone(), signed)
+            .handle(deps.as_mut(), &env, &info)
+            .expect("message is part of the attested batch");
+        assert!(!is_attestation_verified(&res));
+
+        // the verified attestation expires
+        env.block.height += VERIFIED_BATCH_TTL;
+        let res = Attested::new(batched[1].clone(), attestation.clone())
+            .handle(deps.as_mut(), &env, &info)
+            .expect("message is part of the attested batch");
+        assert!(!is_attestation_verified(&res));
+        env.block.height += 1;
+        let res = Attested::new(batched[1].clone(), attestation.clone())
+            .handle(deps.as_mut(), &env, &info)
+            .expect("message is part of the attested batch");
+        assert!(is_attestation_verified(&res));
+
+        // and doesn't cover the batch under another attestation policy
+        let policy = AttestationPolicy::new([TcbStatus::UpToDate], ["INTEL-SA-00615"]);
+        let set = Execute::SetAttestationPolicy(SetAttestationPolicy::new(policy));
+        execute(deps.as_mut(), &env, "admin", set).expect("admin can set policy");
+        let res = Attested::new(batched[2].clone(), attestation.clone())
+            .handle(deps.as_mut(), &env, &info)
+            .expect("message is part of the attested batch");
+        assert!(is_attestation_verified(&res));
+
+        // but not a message with another message's inclusion proof
+        let forged = Batched::new(batched[0].msg().clone(), batched[1].proof().clone());
+        let res = Attested::new(forged, attestation).handle(deps.as_mut(), &env, &info);
+        assert!(matches!(
+            res,
+            Err(Error::RaVerification(RaVerificationError::UserDataMismatch))
+        ));
     }
 }
@@ -0,0 +1,33 @@
+use cosmwasm_std::{DepsMut, Env, HexBinary, MessageInfo, Response};
+
+use crate::{
+    error::Error,
+    handler::Handler,
+    msg::execute::add_mr_enclave::AddMrEnclave,
+    state::{Config, MrEnclaveEntry, RawConfig, CONFIG},
+};
+
+impl Handler for AddMrEnclave {
+    fn handle(self, deps: DepsMut<'_>, env: &Env, info: &MessageInfo) -> Result<Response, Error> {
+        let mut config: Config = CONFIG.load(deps.storage)?.try_into()?;
+        if config.admin() != Some(info.sender.as_str()) {
+            return Err(Error::Unauthorized);
+        }
+
+        let (mr_enclave, activation_height, expiry_height) = self.into_tuple();
+        let activation_height = activation_height.unwrap_or(env.block.height);
+        config.add_mr_enclave(MrEnclaveEntry::new(
+            mr_enclave,
+            activation_height,
+            expiry_height,
+        )?)?;
+        CONFIG
+            .save(deps.storage, &RawConfig::from(config))
+            .map_err(Error::Std)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "add_mr_enclave")
+            .add_attribute("mr_enclave", HexBinary::from(mr_enclave).to_hex())
+            .add_attribute("activation_height", activation_height.to_string()))
+    }
+}
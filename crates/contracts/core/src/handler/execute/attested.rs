@@ -1,22 +1,31 @@
 use ciborium::{from_reader as from_cbor_slice, into_writer as into_cbor, Value as CborValue};
 use cosmwasm_std::{
-    to_json_binary, Deps, DepsMut, Env, MessageInfo, QueryRequest, Response, StdResult, WasmQuery,
+    to_json_binary, Deps, DepsMut, Env, MessageInfo, QueryRequest, Response, StdError, StdResult,
+    WasmQuery,
 };
-use quartz_dcap_verifier_msgs::QueryMsg as DcapVerifierQueryMsg;
+use quartz_dcap_verifier_msgs::{DcapVerificationResponse, QueryMsg as DcapVerifierQueryMsg};
 use quartz_tcbinfo_msgs::{GetTcbInfoResponse, QueryMsg as TcbInfoQueryMsg};
 use quartz_tee_ra::{
-    intel_sgx::dcap::{Collateral, TrustedIdentity, TrustedMrEnclaveIdentity},
+    intel_sgx::dcap::{
+        self, AttestationPolicy, Collateral, TrustedIdentity, TrustedMrEnclaveIdentity,
+        TrustedMrSignerIdentity, FMSPC_SIZE,
+    },
     Error as RaVerificationError,
 };
 use serde::{de::DeserializeOwned, Serialize};
+use sha2::{Digest, Sha256};
 
 use crate::{
     error::Error,
     handler::Handler,
-    msg::execute::attested::{
-        Attestation, Attested, DcapAttestation, HasUserData, MockAttestation, Noop, Quote,
+    msg::execute::{
+        attested::{
+            Attestation, Attested, DcapAttestation, HasUserData, MockAttestation, Noop, Quote,
+            SessionKeyAttestation,
+        },
+        batched::BatchRoot,
     },
-    state::CONFIG,
+    state::{Config, Hash, CONFIG, SESSION, VERIFIED_BATCHES, VERIFIED_BATCH_TTL},
 };
 
 fn query_contract<T: DeserializeOwned>(
@@ -32,101 +41,138 @@ fn query_contract<T: DeserializeOwned>(
     deps.querier.query(&request)
 }
 
-fn query_tcbinfo(deps: Deps<'_>, fmspc: String) -> Result<GetTcbInfoResponse, Error> {
-    let tcbinfo_addr = {
-        let config = CONFIG.load(deps.storage).map_err(Error::Std)?;
-        config
-            .tcbinfo_contract()
-            .expect("TcbInfo contract address is required for DCAP")
-            .to_string()
-    };
-
-    let fmspc_bytes =
-        hex::decode(&fmspc).map_err(|_| Error::InvalidFmspc("Invalid FMSPC format".to_string()))?;
-    if fmspc_bytes.len() != 6 {
-        return Err(Error::InvalidFmspc("FMSPC must be 6 bytes".to_string()));
-    }
+fn query_tcbinfo(
+    deps: Deps<'_>,
+    config: &Config,
+    fmspc: [u8; FMSPC_SIZE],
+) -> Result<GetTcbInfoResponse, Error> {
+    let tcbinfo_addr = config
+        .tcbinfo_contract()
+        .ok_or(Error::MissingTcbInfoContract)?
+        .to_string();
 
-    let query_msg = TcbInfoQueryMsg::GetTcbInfo { fmspc };
+    let query_msg = TcbInfoQueryMsg::GetTcbInfo {
+        fmspc: hex::encode(fmspc),
+    };
 
     query_contract(deps, tcbinfo_addr, &query_msg)
         .map_err(|err| Error::TcbInfoQueryError(err.to_string()))
 }
 
-fn to_cbor_vec<T: Serialize>(value: &T) -> Vec<u8> {
+fn to_cbor_vec<T: Serialize>(value: &T) -> Result<Vec<u8>, Error> {
     let mut buffer = Vec::new();
-    into_cbor(&value, &mut buffer).expect("Serialization failed");
-    buffer
+    into_cbor(&value, &mut buffer).map_err(|e| Error::Serialization(e.to_string()))?;
+    Ok(buffer)
 }
 
 fn query_dcap_verifier(
     deps: Deps<'_>,
+    config: &Config,
     quote: Quote,
     mr_enclave: impl Into<TrustedIdentity>,
     updated_collateral: Collateral,
 ) -> Result<(), Error> {
+    let dcap_verifier_contract = config
+        .dcap_verifier_contract()
+        .ok_or(Error::MissingDcapVerifierContract)?
+        .to_string();
+
     let query_msg = DcapVerifierQueryMsg::VerifyDcapAttestation {
         quote: quote.as_ref().to_vec().into(),
-        collateral: to_cbor_vec(&updated_collateral).into(),
-        identities: Some(to_cbor_vec(&[mr_enclave.into()])),
+        collateral: to_cbor_vec(&updated_collateral)?.into(),
+        identities: Some(to_cbor_vec(&[mr_enclave.into()])?),
+        policy: Some(to_cbor_vec(config.attestation_policy())?),
     };
 
-    let dcap_verifier_contract = {
-        let config = CONFIG.load(deps.storage).map_err(Error::Std)?;
-        config
-            .dcap_verifier_contract()
-            .expect("verifier_contract address is required for DCAP")
-            .to_string()
-    };
+    let response: DcapVerificationResponse =
+        query_contract(deps, dcap_verifier_contract, &query_msg)
+            .map_err(|err| Error::DcapVerificationQueryError(err.to_string()))?;
+    if !response.verified {
+        return Err(Error::DcapVerificationFailed(
+            response.failed_check.unwrap_or_default(),
+        ));
+    }
 
-    query_contract(deps, dcap_verifier_contract, &query_msg)
-        .map_err(|err| Error::DcapVerificationQueryError(err.to_string()))
+    Ok(())
+}
+
+/// Identifies a batch by its root and the (claimed) identity of the enclave that attested to it,
+/// so that a verified batch attestation only covers attestations with the same claims. The
+/// attestation `policy` is included too, so that a batch is verified again once it changes.
+fn verified_batch_key(
+    root: &BatchRoot,
+    attestation: &impl Attestation,
+    policy: Option<&AttestationPolicy>,
+) -> Result<Hash, Error> {
+    let mut hasher = Sha256::new();
+    hasher.update(root.0);
+    hasher.update(attestation.mr_enclave());
+    if let Some(signer) = attestation.signer_identity() {
+        hasher.update(signer.mr_signer);
+        hasher.update(signer.isv_prod_id.to_be_bytes());
+        hasher.update(signer.isv_svn.to_be_bytes());
+    }
+    if let Some(policy) = policy {
+        hasher.update(to_cbor_vec(policy)?);
+    }
+    Ok(hasher.finalize().into())
+}
+
+/// Replaces the TCB info in `collateral` (which the enclave doesn't fill in) with `tcb_info`.
+fn with_tcb_info(collateral: &Collateral, tcb_info: String) -> Result<Collateral, Error> {
+    let collateral_serialized = to_cbor_vec(collateral)?;
+    let mut collateral_value: CborValue = from_cbor_slice(collateral_serialized.as_slice())
+        .map_err(|e| Error::InvalidCollateral(format!("Failed to deserialize collateral: {e}")))?;
+
+    let tcb_info_value = match &mut collateral_value {
+        CborValue::Map(map) => map
+            .iter_mut()
+            .find(|(k, _)| k == &CborValue::Text("tcb_info".to_string()))
+            .map(|(_, v)| v),
+        _ => None,
+    }
+    .ok_or_else(|| Error::InvalidCollateral("missing tcb_info".to_string()))?;
+    *tcb_info_value = CborValue::Text(tcb_info);
+
+    let collateral_serialized = to_cbor_vec(&collateral_value)?;
+    from_cbor_slice(collateral_serialized.as_slice()).map_err(|e| {
+        Error::InvalidCollateral(format!("Failed to deserialize updated collateral: {e}"))
+    })
 }
 
 impl Handler for DcapAttestation {
-    fn handle(self, deps: DepsMut<'_>, _env: &Env, _info: &MessageInfo) -> Result<Response, Error> {
-        let (quote, collateral) = self.clone().into_tuple();
-        let mr_enclave = TrustedMrEnclaveIdentity::new(
-            self.mr_enclave().into(),
-            [""; 0],
-            ["INTEL-SA-00334", "INTEL-SA-00615"],
-        );
-
-        // Retrieve the FMSPC from the collateral
-        let fmspc_hex = collateral.tcb_info().to_string();
-
-        // Query the tcbinfo contract with the FMSPC retrieved and validated
-        let tcb_info_response = query_tcbinfo(deps.as_ref(), fmspc_hex)?;
-
-        // Serialize the existing collateral
-        let collateral_serialized = to_cbor_vec(&collateral);
-        let mut collateral_value: CborValue = from_cbor_slice(collateral_serialized.as_slice())
-            .map_err(|e| {
-                Error::TcbInfoQueryError(format!("Failed to serialize collateral: {}", e))
-            })?;
-
-        // Update the tcb_info in the serialized data
-        fn try_get_tcb_info(collateral_value: &mut CborValue) -> Option<&mut CborValue> {
-            if let CborValue::Map(map) = collateral_value {
-                return map
-                    .iter_mut()
-                    .find(|(k, _)| k == &CborValue::Text("tcb_info".to_string()))
-                    .map(|(_, v)| v);
-            }
-            None
-        }
+    fn handle(self, deps: DepsMut<'_>, env: &Env, _info: &MessageInfo) -> Result<Response, Error> {
+        let config: Config = CONFIG.load(deps.storage)?.try_into()?;
 
-        let tcb_info_value = try_get_tcb_info(&mut collateral_value).expect("infallible serde");
-        *tcb_info_value = CborValue::Text(tcb_info_response.tcb_info.to_string());
+        // enclaves that aren't allowlisted can only be trusted via the pinned MRSIGNER, in which
+        // case the verifier also enforces the product ID and minimum SVN. The platform's TCB
+        // status and advisories are checked against the configured policy instead.
+        let identity: TrustedIdentity = match config.mr_signer() {
+            Some(pinned) if !config.is_mr_enclave_active(self.mr_enclave(), env.block.height) => {
+                TrustedMrSignerIdentity::new(
+                    pinned.mr_signer().into(),
+                    pinned.isv_prod_id().into(),
+                    pinned.min_isv_svn().into(),
+                    [""; 0],
+                    [""; 0],
+                )
+                .into()
+            }
+            _ => TrustedMrEnclaveIdentity::new(self.mr_enclave().into(), [""; 0], [""; 0]).into(),
+        };
+        let (quote, collateral) = self.into_tuple();
 
-        // Deserialize back into a Collateral
-        let collateral_serialized = to_cbor_vec(&collateral_value);
-        let updated_collateral: Collateral = from_cbor_slice(collateral_serialized.as_slice())
-            .map_err(|e| {
-                Error::TcbInfoQueryError(format!("Failed to deserialize updated collateral: {}", e))
-            })?;
+        // The FMSPC is read from the PCK certificate in the quote rather than trusted from the
+        // collateral. The verifier then checks the certificate chain and that the TCB info we got
+        // from the tcbinfo contract is for that FMSPC.
+        let fmspc = dcap::fmspc(&quote).map_err(|e| Error::InvalidFmspc(e.to_string()))?;
+        let tcb_info_response = query_tcbinfo(deps.as_ref(), &config, fmspc)?;
+        if tcb_info_response.stale {
+            return Err(Error::StaleTcbInfo);
+        }
+        let updated_collateral = with_tcb_info(&collateral, tcb_info_response.tcb_info)?;
 
-        query_dcap_verifier(deps.as_ref(), quote, mr_enclave, updated_collateral)
+        query_dcap_verifier(deps.as_ref(), &config, quote, identity, updated_collateral)
             .map(|_| Response::default())
     }
 }
@@ -142,6 +188,61 @@ impl Handler for MockAttestation {
     }
 }
 
+impl Handler for SessionKeyAttestation {
+    fn handle(self, deps: DepsMut<'_>, env: &Env, _info: &MessageInfo) -> Result<Response, Error> {
+        let session = SESSION.load(deps.storage).map_err(Error::Std)?;
+        let pub_key = session.pub_key().ok_or(Error::MissingSessionKey)?;
+
+        // the session key is only as trustworthy as the enclave that holds it, i.e. it can't be
+        // used once that enclave is retired
+        let config: Config = CONFIG.load(deps.storage)?.try_into()?;
+        let is_holder_active = session
+            .holder()
+            .is_some_and(|holder| config.is_mr_enclave_active(holder, env.block.height));
+        if !is_holder_active {
+            return Err(Error::InactiveSessionHolder);
+        }
+
+        // the enclave signs with `k256`, which hashes the message with SHA-256
+        let hash = Sha256::digest(self.user_data());
+        let is_valid = deps
+            .api
+            .secp256k1_verify(&hash, self.signature(), pub_key)
+            .map_err(StdError::from)?;
+        if !is_valid {
+            return Err(Error::InvalidSessionKeySignature);
+        }
+
+        Ok(Response::default())
+    }
+}
+
+impl<M> Handler for Attested<M, SessionKeyAttestation>
+where
+    M: Handler + HasUserData,
+{
+    fn handle(
+        self,
+        mut deps: DepsMut<'_>,
+        env: &Env,
+        info: &MessageInfo,
+    ) -> Result<Response, Error> {
+        let (msg, attestation) = self.into_tuple();
+        if msg.user_data() != attestation.user_data() {
+            return Err(RaVerificationError::UserDataMismatch.into());
+        }
+
+        // unlike DCAP, verifying the signature is cheap, so we do it before handling the message
+        // (which might change the session)
+        let res_attest = Handler::handle(attestation, deps.branch(), env, info)?;
+        let res_msg = Handler::handle(msg, deps, env, info)?;
+
+        Ok(res_msg
+            .add_events(res_attest.events)
+            .add_attributes(res_attest.attributes))
+    }
+}
+
 impl<M, A> Handler for Attested<M, A>
 where
     M: Handler + HasUserData,
@@ -158,21 +259,55 @@ where
             return Err(RaVerificationError::UserDataMismatch.into());
         }
 
-        if let Some(config) = CONFIG.may_load(deps.storage)? {
-            // if we weren't able to load then the context was from InstantiateMsg so we don't fail
-            // in such cases, the InstantiateMsg handler will verify that the mr_enclave matches
-            if config.mr_enclave() != attestation.mr_enclave() {
+        // if we weren't able to load then the context was from InstantiateMsg so we don't fail
+        // in such cases, the InstantiateMsg handler will verify that the mr_enclave matches
+        let config: Option<Config> = CONFIG
+            .may_load(deps.storage)?
+            .map(TryInto::try_into)
+            .transpose()?;
+        if let Some(config) = &config {
+            let signer_trusted = match (config.mr_signer(), attestation.signer_identity()) {
+                (Some(pinned), Some(signer)) => {
+                    pinned.accepts(signer.mr_signer, signer.isv_prod_id, signer.isv_svn)
+                }
+                _ => false,
+            };
+            if !signer_trusted
+                && !config.is_mr_enclave_active(attestation.mr_enclave(), env.block.height)
+            {
                 return Err(RaVerificationError::MrEnclaveMismatch.into());
             }
         }
 
+        let batch_key = msg
+            .batch_root()
+            .map(|root| {
+                let policy = config.as_ref().map(Config::attestation_policy);
+                verified_batch_key(&root, &attestation, policy)
+            })
+            .transpose()?;
+
         // handle message first, this has 2 benefits -
         // 1. we avoid (the more expensive) attestation verification if the message handler fails
         // 2. we allow the message handler to make changes to the config so that the attestation
         //    handler can use those changes, e.g. InstantiateMsg
         // return response from msg handle to include pub_key attribute
         let res_msg = Handler::handle(msg, deps.branch(), env, info)?;
-        let res_attest = Handler::handle(attestation, deps, env, info)?;
+
+        // the batch's attestation is verified with its first message, the message's user data
+        // (i.e. the root computed from its inclusion proof) was checked against it above. It's
+        // verified again once it's older than `VERIFIED_BATCH_TTL` blocks.
+        if let Some(batch_key) = batch_key {
+            let verified_at = VERIFIED_BATCHES.may_load(deps.storage, &batch_key)?;
+            if verified_at.is_some_and(|h| env.block.height <= h.saturating_add(VERIFIED_BATCH_TTL))
+            {
+                return Ok(res_msg);
+            }
+        }
+        let res_attest = Handler::handle(attestation, deps.branch(), env, info)?;
+        if let Some(batch_key) = batch_key {
+            VERIFIED_BATCHES.save(deps.storage, &batch_key, &env.block.height)?;
+        }
 
         Ok(res_msg
             .add_events(res_attest.events)
@@ -0,0 +1,13 @@
+use cosmwasm_std::{DepsMut, Env, MessageInfo, Response};
+
+use crate::{error::Error, handler::Handler, msg::execute::batched::Batched};
+
+impl<M> Handler for Batched<M>
+where
+    M: Handler,
+{
+    fn handle(self, deps: DepsMut<'_>, env: &Env, info: &MessageInfo) -> Result<Response, Error> {
+        // the inclusion proof is checked by the `Attested` handler as part of the user data
+        self.into_msg().handle(deps, env, info)
+    }
+}
@@ -0,0 +1,29 @@
+use cosmwasm_std::{DepsMut, Env, HexBinary, MessageInfo, Response};
+
+use crate::{
+    error::Error,
+    handler::Handler,
+    msg::execute::retire_mr_enclave::RetireMrEnclave,
+    state::{Config, RawConfig, CONFIG},
+};
+
+impl Handler for RetireMrEnclave {
+    fn handle(self, deps: DepsMut<'_>, env: &Env, info: &MessageInfo) -> Result<Response, Error> {
+        let mut config: Config = CONFIG.load(deps.storage)?.try_into()?;
+        if config.admin() != Some(info.sender.as_str()) {
+            return Err(Error::Unauthorized);
+        }
+
+        let (mr_enclave, expiry_height) = self.into_tuple();
+        let expiry_height = expiry_height.unwrap_or(env.block.height);
+        config.retire_mr_enclave(mr_enclave, expiry_height)?;
+        CONFIG
+            .save(deps.storage, &RawConfig::from(config))
+            .map_err(Error::Std)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "retire_mr_enclave")
+            .add_attribute("mr_enclave", HexBinary::from(mr_enclave).to_hex())
+            .add_attribute("expiry_height", expiry_height.to_string()))
+    }
+}
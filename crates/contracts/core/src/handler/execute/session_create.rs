@@ -15,6 +15,9 @@ impl Handler for SessionCreate {
         if addr != env.contract.address {
             return Err(Error::ContractAddrMismatch);
         }
+        if self.chain_id() != env.block.chain_id {
+            return Err(Error::ChainIdMismatch);
+        }
 
         SESSION
             .save(deps.storage, &Session::create(self.nonce()))
@@ -0,0 +1,48 @@
+use cosmwasm_std::{DepsMut, Env, HexBinary, MessageInfo, Response};
+
+use crate::{
+    error::Error,
+    handler::Handler,
+    msg::execute::session_handover_complete::SessionHandoverComplete,
+    state::{PENDING_HANDOVER, SESSION},
+};
+
+impl Handler for SessionHandoverComplete {
+    fn handle(self, deps: DepsMut<'_>, env: &Env, _info: &MessageInfo) -> Result<Response, Error> {
+        let addr = deps.api.addr_validate(self.contract())?;
+        if addr != env.contract.address {
+            return Err(Error::ContractAddrMismatch);
+        }
+        if self.chain_id() != env.block.chain_id {
+            return Err(Error::ChainIdMismatch);
+        }
+
+        let (nonce, epoch, mr_enclave, pub_key) = self.into_tuple();
+
+        let pending = PENDING_HANDOVER
+            .may_load(deps.storage)
+            .map_err(Error::Std)?
+            .ok_or(Error::HandoverMismatch)?;
+        if pending.mr_enclave() != mr_enclave || pending.pub_key().as_slice() != pub_key {
+            return Err(Error::HandoverMismatch);
+        }
+
+        // the session can only be handed over to another enclave
+        let session = SESSION.load(deps.storage).map_err(Error::Std)?;
+        if session.holder() == Some(mr_enclave) {
+            return Err(Error::HandoverMismatch);
+        }
+        let session = session
+            .with_rotated_pub_key(nonce, epoch, pub_key.clone())
+            .ok_or(Error::BadSessionTransition)?
+            .with_holder(mr_enclave);
+        SESSION.save(deps.storage, &session).map_err(Error::Std)?;
+        PENDING_HANDOVER.remove(deps.storage);
+
+        Ok(Response::new()
+            .add_attribute("action", "session_handover_complete")
+            .add_attribute("mr_enclave", HexBinary::from(mr_enclave).to_hex())
+            .add_attribute("epoch", epoch.to_string())
+            .add_attribute("pub_key", HexBinary::from(pub_key).to_hex()))
+    }
+}
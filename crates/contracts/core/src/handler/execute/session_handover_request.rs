@@ -0,0 +1,42 @@
+use cosmwasm_std::{DepsMut, Env, HexBinary, MessageInfo, Response};
+
+use crate::{
+    error::Error,
+    handler::Handler,
+    msg::execute::session_handover_request::SessionHandoverRequest,
+    state::{PendingHandover, PENDING_HANDOVER, SESSION},
+};
+
+impl Handler for SessionHandoverRequest {
+    fn handle(self, deps: DepsMut<'_>, env: &Env, _info: &MessageInfo) -> Result<Response, Error> {
+        let addr = deps.api.addr_validate(self.contract())?;
+        if addr != env.contract.address {
+            return Err(Error::ContractAddrMismatch);
+        }
+        if self.chain_id() != env.block.chain_id {
+            return Err(Error::ChainIdMismatch);
+        }
+
+        let session = SESSION.load(deps.storage).map_err(Error::Std)?;
+        let (nonce, mr_enclave, pub_key) = self.into_tuple();
+
+        // only a session with a completed handshake can be handed over
+        if session.nonce() != nonce || session.pub_key().is_none() {
+            return Err(Error::BadSessionTransition);
+        }
+
+        // a newer request replaces any pending one, the completion message binds the pub key so
+        // the current enclave can't be tricked into completing a handover it didn't perform
+        PENDING_HANDOVER
+            .save(
+                deps.storage,
+                &PendingHandover::new(mr_enclave, pub_key.clone()),
+            )
+            .map_err(Error::Std)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "session_handover_request")
+            .add_attribute("mr_enclave", HexBinary::from(mr_enclave).to_hex())
+            .add_attribute("pub_key", HexBinary::from(pub_key).to_hex()))
+    }
+}
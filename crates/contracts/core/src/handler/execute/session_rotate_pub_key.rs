@@ -0,0 +1,31 @@
+use cosmwasm_std::{DepsMut, Env, HexBinary, MessageInfo, Response};
+
+use crate::{
+    error::Error, handler::Handler, msg::execute::session_rotate_pub_key::SessionRotatePubKey,
+    state::SESSION,
+};
+
+impl Handler for SessionRotatePubKey {
+    fn handle(self, deps: DepsMut<'_>, env: &Env, _info: &MessageInfo) -> Result<Response, Error> {
+        let addr = deps.api.addr_validate(self.contract())?;
+        if addr != env.contract.address {
+            return Err(Error::ContractAddrMismatch);
+        }
+        if self.chain_id() != env.block.chain_id {
+            return Err(Error::ChainIdMismatch);
+        }
+
+        let session = SESSION.load(deps.storage).map_err(Error::Std)?;
+        let (nonce, epoch, pub_key) = self.into_tuple();
+
+        let session = session
+            .with_rotated_pub_key(nonce, epoch, pub_key.clone())
+            .ok_or(Error::BadSessionTransition)?;
+        SESSION.save(deps.storage, &session).map_err(Error::Std)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "session_rotate_pub_key")
+            .add_attribute("epoch", epoch.to_string())
+            .add_attribute("pub_key", HexBinary::from(pub_key).to_hex()))
+    }
+}
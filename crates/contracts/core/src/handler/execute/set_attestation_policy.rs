@@ -0,0 +1,32 @@
+use cosmwasm_std::{DepsMut, Env, MessageInfo, Response};
+
+use crate::{
+    error::Error,
+    handler::Handler,
+    msg::execute::set_attestation_policy::SetAttestationPolicy,
+    state::{Config, RawConfig, CONFIG},
+};
+
+impl Handler for SetAttestationPolicy {
+    fn handle(self, deps: DepsMut<'_>, _env: &Env, info: &MessageInfo) -> Result<Response, Error> {
+        let mut config: Config = CONFIG.load(deps.storage)?.try_into()?;
+        if config.admin() != Some(info.sender.as_str()) {
+            return Err(Error::Unauthorized);
+        }
+
+        let policy = self.into_policy();
+        let tcb_statuses = policy
+            .tcb_statuses()
+            .map(|status| status.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        config.set_attestation_policy(policy);
+        CONFIG
+            .save(deps.storage, &RawConfig::from(config))
+            .map_err(Error::Std)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "set_attestation_policy")
+            .add_attribute("tcb_statuses", tcb_statuses))
+    }
+}
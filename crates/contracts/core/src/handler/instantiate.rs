@@ -19,16 +19,67 @@ where
         if self.0.msg().config().mr_enclave() != self.0.attestation().mr_enclave() {
             return Err(RaVerificationError::MrEnclaveMismatch.into());
         }
+        // the enclave only tracks (and attests to) the chain in its light client opts
+        if self.0.msg().config().light_client_opts().chain_id() != &env.block.chain_id {
+            return Err(Error::ChainIdMismatch);
+        }
         self.0.handle(deps, env, info)
     }
 }
 
 impl Handler for CoreInstantiate {
-    fn handle(self, deps: DepsMut<'_>, _env: &Env, _info: &MessageInfo) -> Result<Response, Error> {
+    fn handle(self, deps: DepsMut<'_>, _env: &Env, info: &MessageInfo) -> Result<Response, Error> {
+        let config = self.config().clone();
+        let config = match config.admin() {
+            Some(admin) => {
+                deps.api.addr_validate(admin)?;
+                config
+            }
+            None => config.with_admin(info.sender.to_string()),
+        };
+
         CONFIG
-            .save(deps.storage, &RawConfig::from(self.config().clone()))
+            .save(deps.storage, &RawConfig::from(config))
             .map_err(Error::Std)?;
 
         Ok(Response::new().add_attribute("action", "instantiate"))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::testing::{message_info, mock_dependencies, mock_env, MockApi};
+
+    use super::*;
+    use crate::{
+        msg::execute::attested::{Attested, MockAttestation},
+        state::{Config, LightClientOpts},
+    };
+
+    fn instantiate(chain_id: &str) -> Instantiate<MockAttestation> {
+        let light_client_opts =
+            LightClientOpts::new(chain_id.to_string(), 1, [0; 32], (1, 3), 1_209_600, 5, 20)
+                .expect("valid light client opts");
+        let msg = CoreInstantiate::new(Config::new([0; 32], light_client_opts, None, None));
+        let attestation = MockAttestation(msg.user_data());
+        Instantiate(Attested::new(msg, attestation))
+    }
+
+    #[test]
+    fn test_instantiate_chain_id_must_match() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let info = message_info(&MockApi::default().addr_make("creator"), &[]);
+
+        let res = instantiate("other-chain").handle(deps.as_mut(), &env, &info);
+        assert!(matches!(res, Err(Error::ChainIdMismatch)));
+        assert!(CONFIG
+            .may_load(&deps.storage)
+            .expect("storage read")
+            .is_none());
+
+        instantiate(&env.block.chain_id)
+            .handle(deps.as_mut(), &env, &info)
+            .expect("instantiation on the configured chain");
+    }
+}
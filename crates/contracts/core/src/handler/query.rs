@@ -0,0 +1,134 @@
+use cosmwasm_std::{to_json_binary, Binary, Deps, Env, HexBinary, StdError, StdResult};
+
+use crate::{
+    handler::QueryHandler,
+    msg::query::{
+        MrEnclaveResponse, MrEnclavesResponse, Query, SequenceNumResponse, SessionResponse,
+    },
+    state::{Config, CONFIG, PENDING_HANDOVER, SEQUENCE_NUM, SESSION},
+};
+
+impl QueryHandler for Query {
+    fn handle_query(self, deps: Deps<'_>, env: &Env) -> StdResult<Binary> {
+        match self {
+            Query::Config {} => to_json_binary(&CONFIG.load(deps.storage)?),
+            Query::Session {} => {
+                let session = SESSION
+                    .may_load(deps.storage)?
+                    .map(|session| SessionResponse {
+                        nonce: session.nonce().into(),
+                        pub_key: session.pub_key().cloned(),
+                        epoch: session.epoch().into(),
+                    });
+                to_json_binary(&session)
+            }
+            Query::SequenceNum {} => {
+                // the sequence number is only initialized once the handshake completes
+                let seq_num = SEQUENCE_NUM.may_load(deps.storage)?.unwrap_or_default();
+                to_json_binary(&SequenceNumResponse { seq_num })
+            }
+            Query::MrEnclave {} => {
+                let config: Config = CONFIG.load(deps.storage)?.try_into()?;
+                let entry = config
+                    .latest_mr_enclave(env.block.height)
+                    .ok_or_else(|| StdError::not_found("active mr_enclave"))?;
+                to_json_binary(&MrEnclaveResponse {
+                    mr_enclave: HexBinary::from(entry.mr_enclave()),
+                })
+            }
+            Query::MrEnclaves {} => {
+                let config: Config = CONFIG.load(deps.storage)?.try_into()?;
+                to_json_binary(&MrEnclavesResponse {
+                    mr_enclaves: config
+                        .mr_enclaves()
+                        .iter()
+                        .cloned()
+                        .map(Into::into)
+                        .collect(),
+                })
+            }
+            Query::PendingHandover {} => to_json_binary(&PENDING_HANDOVER.may_load(deps.storage)?),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{
+        from_json,
+        testing::{mock_dependencies, mock_env},
+        Uint64,
+    };
+
+    use super::*;
+    use crate::state::{Config, LightClientOpts, RawConfig, Session};
+
+    const MR_ENCLAVE: [u8; 32] = [1; 32];
+    const NONCE: [u8; 32] = [2; 32];
+
+    fn config() -> RawConfig {
+        let light_client_opts =
+            LightClientOpts::new("testing".to_string(), 1, [0; 32], (1, 3), 1_209_600, 5, 20)
+                .expect("valid light client opts");
+        Config::new(MR_ENCLAVE, light_client_opts, None, None).into()
+    }
+
+    #[test]
+    fn test_query_config_and_mr_enclave() {
+        let mut deps = mock_dependencies();
+        CONFIG
+            .save(deps.as_mut().storage, &config())
+            .expect("failed to save config");
+
+        let res = Query::Config {}
+            .handle_query(deps.as_ref(), &mock_env())
+            .expect("config query failed");
+        assert_eq!(from_json::<RawConfig>(res).expect("bad response"), config());
+
+        let res = Query::MrEnclave {}
+            .handle_query(deps.as_ref(), &mock_env())
+            .expect("mr_enclave query failed");
+        let res: MrEnclaveResponse = from_json(res).expect("bad response");
+        assert_eq!(res.mr_enclave, HexBinary::from(MR_ENCLAVE));
+    }
+
+    #[test]
+    fn test_query_session_and_seq_num() {
+        let mut deps = mock_dependencies();
+
+        let query = |deps: Deps<'_>, query: Query| {
+            query.handle_query(deps, &mock_env()).expect("query failed")
+        };
+
+        let res: Option<SessionResponse> =
+            from_json(query(deps.as_ref(), Query::Session {})).expect("bad response");
+        assert_eq!(res, None);
+        let res: SequenceNumResponse =
+            from_json(query(deps.as_ref(), Query::SequenceNum {})).expect("bad response");
+        assert_eq!(res.seq_num, Uint64::zero());
+
+        let session = Session::create(NONCE)
+            .with_pub_key(NONCE, vec![3; 33])
+            .expect("valid session transition");
+        SESSION
+            .save(deps.as_mut().storage, &session)
+            .expect("failed to save session");
+        SEQUENCE_NUM
+            .save(deps.as_mut().storage, &Uint64::new(7))
+            .expect("failed to save seq num");
+
+        let res: Option<SessionResponse> =
+            from_json(query(deps.as_ref(), Query::Session {})).expect("bad response");
+        assert_eq!(
+            res,
+            Some(SessionResponse {
+                nonce: NONCE.into(),
+                pub_key: Some(vec![3; 33].into()),
+                epoch: Uint64::zero(),
+            })
+        );
+        let res: SequenceNumResponse =
+            from_json(query(deps.as_ref(), Query::SequenceNum {})).expect("bad response");
+        assert_eq!(res.seq_num, Uint64::new(7));
+    }
+}
@@ -0,0 +1,445 @@
+use std::fmt::Debug;
+
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    to_json_binary, Addr, Binary, Deps, DepsMut, Empty, Env, MessageInfo, Response, StdResult,
+    Timestamp,
+};
+use cw_multi_test::{App, Contract, ContractWrapper, Executor};
+use quartz_tee_ra::intel_sgx::dcap::{synthetic::SyntheticDcap, Collateral, Quote3};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    error::Error,
+    handler::RawHandler,
+    msg::execute::{
+        attested::{DcapAttestation, HasUserData, Noop, RawAttested, RawDcapAttestation, RawNoop},
+        batched::{batch, RawBatched},
+    },
+    state::{Config, LightClientOpts, MrEnclave, RawConfig, UserData, CONFIG},
+};
+
+const MR_ENCLAVE: MrEnclave = [1; 32];
+const ADMIN: &str = "admin";
+/// 2025-01-01, i.e. while the synthetic certificates are valid.
+const BLOCK_TIME: Timestamp = Timestamp::from_seconds(1_735_689_600);
+
+#[cw_serde]
+struct TestMsg(u8);
+
+impl HasUserData for TestMsg {
+    fn user_data(&self) -> UserData {
+        [self.0; 64]
+    }
+}
+
+type RawTestExecuteMsg = RawAttested<RawNoop<TestMsg>, RawDcapAttestation>;
+type RawTestBatchedMsg = RawAttested<RawBatched<RawNoop<TestMsg>>, RawDcapAttestation>;
+
+/// A minimal app contract that is instantiated with a config and only handles DCAP attested
+/// messages of type `M`.
+fn app_contract<M>() -> Box<dyn Contract<Empty>>
+where
+    M: RawHandler + DeserializeOwned + Debug + 'static,
+{
+    fn instantiate(
+        deps: DepsMut<'_>,
+        _env: Env,
+        _info: MessageInfo,
+        msg: RawConfig,
+    ) -> Result<Response, Error> {
+        CONFIG.save(deps.storage, &msg)?;
+        Ok(Response::default())
+    }
+
+    fn execute<M: RawHandler>(
+        deps: DepsMut<'_>,
+        env: Env,
+        info: MessageInfo,
+        msg: M,
+    ) -> Result<Response, Error> {
+        msg.handle_raw(deps, &env, &info)
+    }
+
+    fn query(_deps: Deps<'_>, _env: Env, _msg: Empty) -> StdResult<Binary> {
+        to_json_binary(&())
+    }
+
+    Box::new(ContractWrapper::new(execute::<M>, instantiate, query))
+}
+
+fn tcbinfo_contract() -> Box<dyn Contract<Empty>> {
+    Box::new(ContractWrapper::new(
+        quartz_tcbinfo::contract::execute,
+        quartz_tcbinfo::contract::instantiate,
+        quartz_tcbinfo::contract::query,
+    ))
+}
+
+fn dcap_verifier_contract() -> Box<dyn Contract<Empty>> {
+    Box::new(ContractWrapper::new(
+        quartz_dcap_verifier::contract::execute,
+        quartz_dcap_verifier::contract::instantiate,
+        quartz_dcap_verifier::contract::query,
+    ))
+}
+
+struct Setup {
+    app: App,
+    admin: Addr,
+    tcbinfo: Addr,
+    dcap_verifier: Addr,
+}
+
+impl Setup {
+    /// Deploys the tcbinfo contract (trusting the synthetic root CA and storing the TCB info of
+    /// `dcap`'s platform) and the DCAP verifier contract (trusting the synthetic root CA).
+    fn new(dcap: &SyntheticDcap) -> Self {
+        let mut app = App::default();
+        app.update_block(|block| block.time = BLOCK_TIME);
+        let admin = app.api().addr_make(ADMIN);
+
+        let code_id = app.store_code(tcbinfo_contract());
+        let tcbinfo = app
+            .instantiate_contract(
+                code_id,
+                admin.clone(),
+                &quartz_tcbinfo_msgs::InstantiateMsg {
+                    root_cert: dcap.root_ca_pem(),
+                    admin: None,
+                },
+                &[],
+                "tcbinfo",
+                None,
+            )
+            .expect("tcbinfo instantiation failed");
+        app.execute_contract(
+            admin.clone(),
+            tcbinfo.clone(),
+            &quartz_tcbinfo_msgs::ExecuteMsg {
+                tcb_info: dcap.tcb_info(),
+                certificate: dcap.tcb_signer_pem(),
+                root_crl: None,
+            },
+            &[],
+        )
+        .expect("failed to store TCB info");
+
+        let code_id = app.store_code(dcap_verifier_contract());
+        let dcap_verifier = app
+            .instantiate_contract(
+                code_id,
+                admin.clone(),
+                &quartz_dcap_verifier_msgs::InstantiateMsg {
+                    root_cert: Some(dcap.root_ca_pem()),
+                },
+                &[],
+                "dcap-verifier",
+                None,
+            )
+            .expect("dcap-verifier instantiation failed");
+
+        Self {
+            app,
+            admin,
+            tcbinfo,
+            dcap_verifier,
+        }
+    }
+
+    /// Instantiates the app contract with the specified tcbinfo and DCAP verifier contracts.
+    fn instantiate_app(&mut self, tcbinfo: Option<&Addr>, dcap_verifier: Option<&Addr>) -> Addr {
+        self.instantiate_app_contract(app_contract::<RawTestExecuteMsg>(), tcbinfo, dcap_verifier)
+    }
+
+    fn instantiate_app_contract(
+        &mut self,
+        contract: Box<dyn Contract<Empty>>,
+        tcbinfo: Option<&Addr>,
+        dcap_verifier: Option<&Addr>,
+    ) -> Addr {
+        let light_client_opts =
+            LightClientOpts::new("testing".to_string(), 1, [0; 32], (1, 3), 1_209_600, 5, 20)
+                .expect("valid light client opts");
+        let config = Config::new(
+            MR_ENCLAVE,
+            light_client_opts,
+            tcbinfo.map(ToString::to_string),
+            dcap_verifier.map(ToString::to_string),
+        );
+
+        let code_id = self.app.store_code(contract);
+        self.app
+            .instantiate_contract(
+                code_id,
+                self.admin.clone(),
+                &RawConfig::from(config),
+                &[],
+                "app",
+                None,
+            )
+            .expect("app instantiation failed")
+    }
+
+    fn app_with_contracts(&mut self) -> Addr {
+        let (tcbinfo, dcap_verifier) = (self.tcbinfo.clone(), self.dcap_verifier.clone());
+        self.instantiate_app(Some(&tcbinfo), Some(&dcap_verifier))
+    }
+
+    /// Executes a message attested by `quote` and `collateral` on the app contract and returns
+    /// the contract's error, if any.
+    fn execute(
+        &mut self,
+        app: &Addr,
+        msg: TestMsg,
+        quote: Quote3<Vec<u8>>,
+        collateral: Collateral,
+    ) -> Result<(), Error> {
+        let msg = RawTestExecuteMsg {
+            msg: RawNoop(msg),
+            attestation: DcapAttestation::new(quote, collateral).into(),
+        };
+        self.execute_raw(app, &msg)
+    }
+
+    fn execute_raw(&mut self, app: &Addr, msg: &(impl Serialize + Debug)) -> Result<(), Error> {
+        match self
+            .app
+            .execute_contract(self.admin.clone(), app.clone(), msg, &[])
+        {
+            Ok(_) => Ok(()),
+            Err(err) => Err(err
+                .downcast::<Error>()
+                .expect("app contract must fail with a contract error")),
+        }
+    }
+}
+
+/// The collateral the enclave sends for on-chain verification, i.e. with the FMSPC instead of the
+/// TCB info.
+fn collateral(dcap: &SyntheticDcap) -> Collateral {
+    dcap.collateral_with_tcb_info(&hex::encode(dcap.fmspc()))
+}
+
+#[test]
+fn test_dcap_attestation_is_verified() {
+    let dcap = SyntheticDcap::new();
+    let mut setup = Setup::new(&dcap);
+    let app = setup.app_with_contracts();
+
+    let msg = TestMsg(1);
+    let quote = dcap.quote(MR_ENCLAVE, msg.user_data());
+    setup
+        .execute(&app, msg, quote, collateral(&dcap))
+        .expect("valid attestation must be accepted");
+}
+
+#[test]
+fn test_missing_contracts() {
+    let dcap = SyntheticDcap::new();
+    let mut setup = Setup::new(&dcap);
+    let (tcbinfo, dcap_verifier) = (setup.tcbinfo.clone(), setup.dcap_verifier.clone());
+
+    let msg = TestMsg(1);
+    let quote = dcap.quote(MR_ENCLAVE, msg.user_data());
+
+    let app = setup.instantiate_app(None, Some(&dcap_verifier));
+    let err = setup
+        .execute(&app, msg.clone(), quote.clone(), collateral(&dcap))
+        .expect_err("tcbinfo contract is required");
+    assert!(matches!(err, Error::MissingTcbInfoContract), "{err}");
+
+    let app = setup.instantiate_app(Some(&tcbinfo), None);
+    let err = setup
+        .execute(&app, msg, quote, collateral(&dcap))
+        .expect_err("dcap-verifier contract is required");
+    assert!(matches!(err, Error::MissingDcapVerifierContract), "{err}");
+}
+
+#[test]
+fn test_fmspc_is_taken_from_pck_certificate() {
+    let dcap = SyntheticDcap::new();
+    let mut setup = Setup::new(&dcap);
+    let app = setup.app_with_contracts();
+
+    // whatever the enclave sends as TCB info (or FMSPC) is ignored
+    for tcb_info in ["not hex", "00906ea1", "ffffffffffff"] {
+        let msg = TestMsg(1);
+        let quote = dcap.quote(MR_ENCLAVE, msg.user_data());
+        setup
+            .execute(&app, msg, quote, dcap.collateral_with_tcb_info(tcb_info))
+            .expect("TCB info must be looked up by the PCK certificate's FMSPC");
+    }
+
+    // a platform with an FMSPC that has no TCB info in the tcbinfo contract
+    let other = SyntheticDcap::new().with_fmspc([0xff; 6]);
+    let msg = TestMsg(2);
+    let quote = other.quote(MR_ENCLAVE, msg.user_data());
+    let err = setup
+        .execute(&app, msg, quote, collateral(&dcap))
+        .expect_err("unknown FMSPC must be rejected");
+    assert!(matches!(err, Error::TcbInfoQueryError(_)), "{err}");
+}
+
+#[test]
+fn test_mismatched_collateral() {
+    let dcap = SyntheticDcap::new();
+    let mut setup = Setup::new(&dcap);
+    let app = setup.app_with_contracts();
+
+    // collateral (i.e. QE identity and CRLs) of another PKI with the same FMSPC
+    let other = SyntheticDcap::from_seed(b"other");
+    let msg = TestMsg(1);
+    let quote = dcap.quote(MR_ENCLAVE, msg.user_data());
+    let err = setup
+        .execute(&app, msg, quote, collateral(&other))
+        .expect_err("mismatched collateral must be rejected");
+    assert!(
+        matches!(&err, Error::DcapVerificationFailed(check) if check == "tcb_signing_chain"),
+        "{err}"
+    );
+
+    // a quote from another PKI, for which the stored TCB info isn't signed
+    let msg = TestMsg(2);
+    let quote = other.quote(MR_ENCLAVE, msg.user_data());
+    let err = setup
+        .execute(&app, msg, quote, collateral(&other))
+        .expect_err("quote from an untrusted PKI must be rejected");
+    assert!(
+        matches!(&err, Error::DcapVerificationFailed(check) if check == "tcb_signing_chain"),
+        "{err}"
+    );
+}
+
+#[test]
+fn test_untrusted_platform_and_identity() {
+    // the default policy doesn't accept out-of-date platforms
+    let dcap = SyntheticDcap::new().with_tcb_status("OutOfDate", &[]);
+    let mut setup = Setup::new(&dcap);
+    let app = setup.app_with_contracts();
+
+    let msg = TestMsg(1);
+    let quote = dcap.quote(MR_ENCLAVE, msg.user_data());
+    let err = setup
+        .execute(&app, msg, quote, collateral(&dcap))
+        .expect_err("out-of-date platform must be rejected");
+    assert!(
+        matches!(&err, Error::DcapVerificationFailed(check) if check == "policy"),
+        "{err}"
+    );
+
+    // an enclave that isn't allowlisted is rejected before the quote is verified
+    let dcap = SyntheticDcap::new();
+    let mut setup = Setup::new(&dcap);
+    let app = setup.app_with_contracts();
+
+    let msg = TestMsg(1);
+    let quote = dcap.quote([9; 32], msg.user_data());
+    let err = setup
+        .execute(&app, msg, quote, collateral(&dcap))
+        .expect_err("unknown enclave must be rejected");
+    assert!(matches!(err, Error::RaVerification(_)), "{err}");
+}
+
+#[test]
+fn test_revoked_and_expired_certificates() {
+    // TCB info that outlives the certificates
+    let dcap =
+        SyntheticDcap::new().with_tcb_info_dates("2024-01-01T00:00:00Z", "2060-01-01T00:00:00Z");
+    let mut setup = Setup::new(&dcap);
+    let app = setup.app_with_contracts();
+
+    // the CRLs are part of the collateral
+    let revoked = dcap.clone().with_revoked_pck_cert();
+    let msg = TestMsg(1);
+    let quote = dcap.quote(MR_ENCLAVE, msg.user_data());
+    let err = setup
+        .execute(&app, msg, quote, collateral(&revoked))
+        .expect_err("revoked PCK certificate must be rejected");
+    assert!(
+        matches!(&err, Error::DcapVerificationFailed(check) if check == "quote_signing_chain"),
+        "{err}"
+    );
+
+    // the certificates are checked at the block time
+    setup.app.update_block(|block| {
+        block.time = Timestamp::from_seconds(2_556_144_000); // 2051-01-01
+    });
+    let msg = TestMsg(2);
+    let quote = dcap.quote(MR_ENCLAVE, msg.user_data());
+    let err = setup
+        .execute(&app, msg, quote, collateral(&dcap))
+        .expect_err("expired certificates must be rejected");
+    assert!(
+        matches!(&err, Error::DcapVerificationFailed(check) if check == "tcb_signing_chain"),
+        "{err}"
+    );
+}
+
+#[test]
+fn test_stale_tcb_info() {
+    let dcap =
+        SyntheticDcap::new().with_tcb_info_dates("2024-01-01T00:00:00Z", "2025-06-01T00:00:00Z");
+    let mut setup = Setup::new(&dcap);
+    let app = setup.app_with_contracts();
+
+    let msg = TestMsg(1);
+    let quote = dcap.quote(MR_ENCLAVE, msg.user_data());
+    setup
+        .execute(&app, msg, quote, collateral(&dcap))
+        .expect("TCB info is still valid");
+
+    // the TCB info's next update has passed
+    setup.app.update_block(|block| {
+        block.time = Timestamp::from_seconds(1_751_328_000); // 2025-07-01
+    });
+    let msg = TestMsg(2);
+    let quote = dcap.quote(MR_ENCLAVE, msg.user_data());
+    let err = setup
+        .execute(&app, msg, quote, collateral(&dcap))
+        .expect_err("stale TCB info must be rejected");
+    assert!(matches!(err, Error::StaleTcbInfo), "{err}");
+}
+
+#[test]
+fn test_batch_attestation_is_verified_once() {
+    let dcap = SyntheticDcap::new();
+    let mut setup = Setup::new(&dcap);
+    let (tcbinfo, dcap_verifier) = (setup.tcbinfo.clone(), setup.dcap_verifier.clone());
+    let app = setup.instantiate_app_contract(
+        app_contract::<RawTestBatchedMsg>(),
+        Some(&tcbinfo),
+        Some(&dcap_verifier),
+    );
+
+    let (root, batched) = batch((1..=3).map(|i| Noop(TestMsg(i))).collect()).expect("non-empty");
+    let quote = dcap.quote(MR_ENCLAVE, root.user_data());
+    let attested = |batched, collateral| RawTestBatchedMsg {
+        msg: RawBatched::from(batched),
+        attestation: DcapAttestation::new(quote.clone(), collateral).into(),
+    };
+
+    // the batch's attestation must be valid ...
+    let other = SyntheticDcap::from_seed(b"other");
+    let err = setup
+        .execute_raw(&app, &attested(batched[0].clone(), collateral(&other)))
+        .expect_err("mismatched collateral must be rejected");
+    assert!(matches!(err, Error::DcapVerificationFailed(_)), "{err}");
+    setup
+        .execute_raw(&app, &attested(batched[0].clone(), collateral(&dcap)))
+        .expect("valid attestation must be accepted");
+
+    // ... but once verified, it isn't verified again for the other messages of the batch
+    for msg in &batched[1..] {
+        setup
+            .execute_raw(&app, &attested(msg.clone(), collateral(&other)))
+            .expect("batch attestation was already verified");
+    }
+
+    // which must still be part of the batch
+    let (_, other_batch) = batch(vec![Noop(TestMsg(9))]).expect("non-empty");
+    let err = setup
+        .execute_raw(&app, &attested(other_batch[0].clone(), collateral(&dcap)))
+        .expect_err("message must be part of the attested batch");
+    assert!(matches!(err, Error::RaVerification(_)), "{err}");
+}
@@ -20,3 +20,6 @@ pub mod handler;
 pub mod msg;
 pub mod prelude;
 pub mod state;
+
+#[cfg(test)]
+mod integration_tests;
@@ -5,6 +5,7 @@ pub mod query;
 use cosmwasm_std::StdError;
 pub use execute::{Execute as ExecuteMsg, RawExecute as RawExecuteMsg};
 pub use instantiate::{Instantiate as InstantiateMsg, RawInstantiate as RawInstantiateMsg};
+pub use query::Query as QueryMsg;
 use serde::Serialize;
 
 pub trait HasDomainType: From<Self::DomainType> + Serialize {
@@ -1,16 +1,29 @@
+pub mod add_mr_enclave;
 pub mod attested;
+pub mod batched;
+pub mod retire_mr_enclave;
 pub mod sequenced;
 pub mod session_create;
+pub mod session_handover_complete;
+pub mod session_handover_request;
+pub mod session_rotate_pub_key;
 pub mod session_set_pub_key;
+pub mod set_attestation_policy;
 
 use cosmwasm_schema::cw_serde;
 use cosmwasm_std::StdError;
 
 use crate::msg::{
     execute::{
+        add_mr_enclave::{AddMrEnclave, RawAddMrEnclave},
         attested::{Attested, DefaultAttestation, RawAttested, RawDefaultAttestation},
+        retire_mr_enclave::{RawRetireMrEnclave, RetireMrEnclave},
         session_create::{RawSessionCreate, SessionCreate},
+        session_handover_complete::{RawSessionHandoverComplete, SessionHandoverComplete},
+        session_handover_request::{RawSessionHandoverRequest, SessionHandoverRequest},
+        session_rotate_pub_key::{RawSessionRotatePubKey, SessionRotatePubKey},
         session_set_pub_key::{RawSessionSetPubKey, SessionSetPubKey},
+        set_attestation_policy::{RawSetAttestationPolicy, SetAttestationPolicy},
     },
     HasDomainType,
 };
@@ -19,6 +32,12 @@ use crate::msg::{
 pub enum Execute<Attestation = DefaultAttestation> {
     SessionCreate(Attested<SessionCreate, Attestation>),
     SessionSetPubKey(Attested<SessionSetPubKey, Attestation>),
+    SessionRotatePubKey(Attested<SessionRotatePubKey, Attestation>),
+    SessionHandoverRequest(Attested<SessionHandoverRequest, Attestation>),
+    SessionHandoverComplete(Attested<SessionHandoverComplete, Attestation>),
+    AddMrEnclave(AddMrEnclave),
+    RetireMrEnclave(RetireMrEnclave),
+    SetAttestationPolicy(SetAttestationPolicy),
 }
 
 #[cw_serde]
@@ -27,6 +46,18 @@ pub enum RawExecute<RawAttestation = RawDefaultAttestation> {
     RawSessionCreate(RawAttested<RawSessionCreate, RawAttestation>),
     #[serde(rename = "session_set_pub_key")]
     RawSessionSetPubKey(RawAttested<RawSessionSetPubKey, RawAttestation>),
+    #[serde(rename = "session_rotate_pub_key")]
+    RawSessionRotatePubKey(RawAttested<RawSessionRotatePubKey, RawAttestation>),
+    #[serde(rename = "session_handover_request")]
+    RawSessionHandoverRequest(RawAttested<RawSessionHandoverRequest, RawAttestation>),
+    #[serde(rename = "session_handover_complete")]
+    RawSessionHandoverComplete(RawAttested<RawSessionHandoverComplete, RawAttestation>),
+    #[serde(rename = "add_mr_enclave")]
+    RawAddMrEnclave(RawAddMrEnclave),
+    #[serde(rename = "retire_mr_enclave")]
+    RawRetireMrEnclave(RawRetireMrEnclave),
+    #[serde(rename = "set_attestation_policy")]
+    RawSetAttestationPolicy(RawSetAttestationPolicy),
 }
 
 impl<RA> TryFrom<RawExecute<RA>> for Execute<RA::DomainType>
@@ -43,6 +74,22 @@ where
             RawExecute::RawSessionSetPubKey(msg) => {
                 Ok(Execute::SessionSetPubKey(TryFrom::try_from(msg)?))
             }
+            RawExecute::RawSessionRotatePubKey(msg) => {
+                Ok(Execute::SessionRotatePubKey(TryFrom::try_from(msg)?))
+            }
+            RawExecute::RawSessionHandoverRequest(msg) => {
+                Ok(Execute::SessionHandoverRequest(TryFrom::try_from(msg)?))
+            }
+            RawExecute::RawSessionHandoverComplete(msg) => {
+                Ok(Execute::SessionHandoverComplete(TryFrom::try_from(msg)?))
+            }
+            RawExecute::RawAddMrEnclave(msg) => Ok(Execute::AddMrEnclave(TryFrom::try_from(msg)?)),
+            RawExecute::RawRetireMrEnclave(msg) => {
+                Ok(Execute::RetireMrEnclave(TryFrom::try_from(msg)?))
+            }
+            RawExecute::RawSetAttestationPolicy(msg) => {
+                Ok(Execute::SetAttestationPolicy(TryFrom::try_from(msg)?))
+            }
         }
     }
 }
@@ -55,6 +102,20 @@ where
         match value {
             Execute::SessionCreate(msg) => RawExecute::RawSessionCreate(From::from(msg)),
             Execute::SessionSetPubKey(msg) => RawExecute::RawSessionSetPubKey(From::from(msg)),
+            Execute::SessionRotatePubKey(msg) => {
+                RawExecute::RawSessionRotatePubKey(From::from(msg))
+            }
+            Execute::SessionHandoverRequest(msg) => {
+                RawExecute::RawSessionHandoverRequest(From::from(msg))
+            }
+            Execute::SessionHandoverComplete(msg) => {
+                RawExecute::RawSessionHandoverComplete(From::from(msg))
+            }
+            Execute::AddMrEnclave(msg) => RawExecute::RawAddMrEnclave(From::from(msg)),
+            Execute::RetireMrEnclave(msg) => RawExecute::RawRetireMrEnclave(From::from(msg)),
+            Execute::SetAttestationPolicy(msg) => {
+                RawExecute::RawSetAttestationPolicy(From::from(msg))
+            }
         }
     }
 }
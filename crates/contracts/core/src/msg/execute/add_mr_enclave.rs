@@ -0,0 +1,67 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{HexBinary, StdError};
+
+use crate::{
+    msg::HasDomainType,
+    state::{Height, MrEnclave},
+};
+
+/// Adds an MRENCLAVE to the allowlist. Only the admin may send this.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AddMrEnclave {
+    mr_enclave: MrEnclave,
+    activation_height: Option<Height>,
+    expiry_height: Option<Height>,
+}
+
+impl AddMrEnclave {
+    pub fn new(
+        mr_enclave: MrEnclave,
+        activation_height: Option<Height>,
+        expiry_height: Option<Height>,
+    ) -> Self {
+        Self {
+            mr_enclave,
+            activation_height,
+            expiry_height,
+        }
+    }
+
+    pub fn into_tuple(self) -> (MrEnclave, Option<Height>, Option<Height>) {
+        (self.mr_enclave, self.activation_height, self.expiry_height)
+    }
+}
+
+#[cw_serde]
+pub struct RawAddMrEnclave {
+    mr_enclave: HexBinary,
+    /// Defaults to the height at which the message is executed.
+    activation_height: Option<u64>,
+    expiry_height: Option<u64>,
+}
+
+impl TryFrom<RawAddMrEnclave> for AddMrEnclave {
+    type Error = StdError;
+
+    fn try_from(value: RawAddMrEnclave) -> Result<Self, Self::Error> {
+        Ok(Self {
+            mr_enclave: value.mr_enclave.to_array()?,
+            activation_height: value.activation_height,
+            expiry_height: value.expiry_height,
+        })
+    }
+}
+
+impl From<AddMrEnclave> for RawAddMrEnclave {
+    fn from(value: AddMrEnclave) -> Self {
+        Self {
+            mr_enclave: value.mr_enclave.into(),
+            activation_height: value.activation_height,
+            expiry_height: value.expiry_height,
+        }
+    }
+}
+
+impl HasDomainType for RawAddMrEnclave {
+    type DomainType = AddMrEnclave;
+}
@@ -2,8 +2,11 @@ use std::{convert::Into, default::Default};
 
 use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{HexBinary, StdError};
+use k256::ecdsa::{signature::Signer, Signature, SigningKey};
 use quartz_tee_ra::intel_sgx::dcap::{Collateral, Quote3, Quote3Error};
 use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
 
 /// Alias for an owned DCAP quote. This is the main part of a DCAP attestation generated by an
 /// enclave that we want to verify on-chain.
@@ -20,8 +23,8 @@ pub type DefaultAttestation = MockAttestation;
 pub type RawDefaultAttestation = RawMockAttestation;
 
 use crate::{
-    msg::HasDomainType,
-    state::{MrEnclave, UserData},
+    msg::{execute::batched::BatchRoot, HasDomainType},
+    state::{MrEnclave, MrSigner, UserData},
 };
 
 /// A wrapper struct for holding a message and it's attestation.
@@ -95,10 +98,122 @@ where
 /// A trait that defines how to extract user data from a given type.
 pub trait HasUserData {
     fn user_data(&self) -> UserData;
+
+    /// The root of the batch that the message was attested with, if any (see [`Batched`]).
+    ///
+    /// [`Batched`]: crate::msg::execute::batched::Batched
+    fn batch_root(&self) -> Option<BatchRoot> {
+        None
+    }
+}
+
+/// The context that a message's user data is bound to, so that an attestation for one type of
+/// message can't be passed off as another, or be replayed to another contract or chain.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct UserDataDomain<'a> {
+    msg_type: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    contract: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    chain_id: Option<&'a str>,
+}
+
+impl<'a> UserDataDomain<'a> {
+    pub fn new(msg_type: &'a str) -> Self {
+        Self {
+            msg_type,
+            contract: None,
+            chain_id: None,
+        }
+    }
+
+    pub fn with_contract(mut self, contract: &'a str) -> Self {
+        self.contract = Some(contract);
+        self
+    }
+
+    pub fn with_chain_id(mut self, chain_id: &'a str) -> Self {
+        self.chain_id = Some(chain_id);
+        self
+    }
+}
+
+/// Computes the user data of `msg` as the SHA-256 hash of its canonical JSON serialization, i.e.
+/// without whitespace and with object keys sorted. If a `domain` is given, the hash is over
+/// `{"domain": domain, "msg": msg}` instead.
+pub fn canonical_user_data(domain: Option<UserDataDomain<'_>>, msg: &impl Serialize) -> UserData {
+    #[derive(Serialize)]
+    struct Envelope<'a> {
+        domain: UserDataDomain<'a>,
+        msg: Value,
+    }
+
+    let msg = serde_json::to_value(msg).expect("infallible serializer");
+    let value = match domain {
+        Some(domain) => {
+            serde_json::to_value(Envelope { domain, msg }).expect("infallible serializer")
+        }
+        None => msg,
+    };
+
+    let mut json = String::new();
+    write_canonical_json(&value, &mut json);
+    let digest: [u8; 32] = Sha256::digest(json).into();
+
+    let mut user_data = [0u8; 64];
+    user_data[0..32].copy_from_slice(&digest);
+    user_data
+}
+
+// `serde_json` only sorts object keys if its `preserve_order` feature is disabled, which we can't
+// rely on since features are unified across the dependency graph of the enclave
+fn write_canonical_json(value: &Value, out: &mut String) {
+    match value {
+        Value::Array(values) => {
+            out.push('[');
+            for (i, value) in values.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_canonical_json(value, out);
+            }
+            out.push(']');
+        }
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by(|(k1, _), (k2, _)| k1.cmp(k2));
+
+            out.push('{');
+            for (i, (key, value)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str(&Value::String(key.clone()).to_string());
+                out.push(':');
+                write_canonical_json(value, out);
+            }
+            out.push('}');
+        }
+        scalar => out.push_str(&scalar.to_string()),
+    }
 }
 
 pub trait Attestation {
     fn mr_enclave(&self) -> MrEnclave;
+
+    /// The signer identity of the attested enclave, if the attestation carries one. Attestations
+    /// without it are only accepted through the MRENCLAVE allowlist.
+    fn signer_identity(&self) -> Option<SignerIdentity> {
+        None
+    }
+}
+
+/// The MRSIGNER, ISV product ID and ISV SVN reported by an attested enclave.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SignerIdentity {
+    pub mr_signer: MrSigner,
+    pub isv_prod_id: u16,
+    pub isv_svn: u16,
 }
 
 /// A verifiable DCAP attestation generated by an enclave.
@@ -177,6 +292,89 @@ impl Attestation for DcapAttestation {
             .try_into()
             .expect("fixed size array")
     }
+
+    fn signer_identity(&self) -> Option<SignerIdentity> {
+        let report_body = self.quote.app_report_body();
+        let mr_signer = report_body.mr_signer();
+        let mr_signer_slice: &[u8] = mr_signer.as_ref();
+        Some(SignerIdentity {
+            mr_signer: mr_signer_slice.try_into().expect("fixed size array"),
+            isv_prod_id: report_body.isv_product_id().into(),
+            isv_svn: report_body.isv_svn().into(),
+        })
+    }
+}
+
+/// A signature by the enclave's session key over the user data of a message.
+///
+/// The session key is attested (via DCAP) during the handshake, so once a session is established,
+/// this is a much cheaper way for the enclave to vouch for its messages than a full DCAP
+/// attestation. It doesn't implement [`Attestation`] since it doesn't report an MRENCLAVE, instead
+/// it is trusted for as long as the enclave holding the session key is active in the MRENCLAVE
+/// allowlist.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SessionKeyAttestation {
+    user_data: UserData,
+    signature: [u8; 64],
+}
+
+impl SessionKeyAttestation {
+    pub fn new(user_data: UserData, signature: [u8; 64]) -> Self {
+        Self {
+            user_data,
+            signature,
+        }
+    }
+
+    /// Signs the user data of `msg` (i.e. its SHA-256 hash) with the session key.
+    pub fn sign(session_key: &SigningKey, msg: &impl HasUserData) -> Self {
+        let user_data = msg.user_data();
+        let signature: Signature = session_key.sign(&user_data);
+        Self {
+            user_data,
+            signature: signature.to_bytes().into(),
+        }
+    }
+
+    pub fn signature(&self) -> &[u8; 64] {
+        &self.signature
+    }
+}
+
+#[cw_serde]
+pub struct RawSessionKeyAttestation {
+    pub user_data: HexBinary,
+    pub signature: HexBinary,
+}
+
+impl TryFrom<RawSessionKeyAttestation> for SessionKeyAttestation {
+    type Error = StdError;
+
+    fn try_from(value: RawSessionKeyAttestation) -> Result<Self, Self::Error> {
+        Ok(Self {
+            user_data: value.user_data.to_array()?,
+            signature: value.signature.to_array()?,
+        })
+    }
+}
+
+impl From<SessionKeyAttestation> for RawSessionKeyAttestation {
+    fn from(value: SessionKeyAttestation) -> Self {
+        Self {
+            user_data: value.user_data.into(),
+            signature: value.signature.into(),
+        }
+    }
+}
+
+impl HasDomainType for RawSessionKeyAttestation {
+    type DomainType = SessionKeyAttestation;
+}
+
+impl HasUserData for SessionKeyAttestation {
+    fn user_data(&self) -> UserData {
+        self.user_data
+    }
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -0,0 +1,322 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{HexBinary, StdError};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    msg::{execute::attested::HasUserData, HasDomainType},
+    state::{Hash, UserData},
+};
+
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+/// A message that is part of a batch of messages that were attested together.
+///
+/// A batch is attested once, to the Merkle root of the user data of all of its messages. The user
+/// data of a `Batched` message is that root, as computed from the message and its inclusion proof,
+/// so it matches the batch's attestation iff the message is part of the batch.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Batched<M> {
+    msg: M,
+    proof: InclusionProof,
+}
+
+impl<M> Batched<M> {
+    pub fn new(msg: M, proof: InclusionProof) -> Self {
+        Self { msg, proof }
+    }
+
+    pub fn msg(&self) -> &M {
+        &self.msg
+    }
+
+    pub fn proof(&self) -> &InclusionProof {
+        &self.proof
+    }
+
+    pub fn into_msg(self) -> M {
+        self.msg
+    }
+}
+
+impl<M> Batched<M>
+where
+    M: HasUserData,
+{
+    /// The root of the batch, as computed from the message and its inclusion proof.
+    fn root(&self) -> BatchRoot {
+        BatchRoot(self.proof.root(leaf_hash(&self.msg.user_data())))
+    }
+}
+
+impl<M> HasUserData for Batched<M>
+where
+    M: HasUserData,
+{
+    fn user_data(&self) -> UserData {
+        self.root().user_data()
+    }
+
+    fn batch_root(&self) -> Option<BatchRoot> {
+        Some(self.root())
+    }
+}
+
+#[cw_serde]
+pub struct RawBatched<RM> {
+    pub msg: RM,
+    pub proof: RawInclusionProof,
+}
+
+impl<RM> TryFrom<RawBatched<RM>> for Batched<RM::DomainType>
+where
+    RM: HasDomainType,
+{
+    type Error = StdError;
+
+    fn try_from(value: RawBatched<RM>) -> Result<Self, Self::Error> {
+        Ok(Self {
+            msg: value.msg.try_into()?,
+            proof: value.proof.try_into()?,
+        })
+    }
+}
+
+impl<RM> From<Batched<RM::DomainType>> for RawBatched<RM>
+where
+    RM: HasDomainType,
+{
+    fn from(value: Batched<RM::DomainType>) -> Self {
+        Self {
+            msg: value.msg.into(),
+            proof: value.proof.into(),
+        }
+    }
+}
+
+impl<RM> HasDomainType for RawBatched<RM>
+where
+    RM: HasDomainType,
+{
+    type DomainType = Batched<RM::DomainType>;
+}
+
+/// The Merkle root of a batch of messages, i.e. what the batch's attestation attests to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BatchRoot(pub Hash);
+
+impl HasUserData for BatchRoot {
+    fn user_data(&self) -> UserData {
+        let mut user_data = [0u8; 64];
+        user_data[0..32].copy_from_slice(&self.0);
+        user_data
+    }
+}
+
+/// A proof that a leaf is part of a Merkle tree with `leaf_count` leaves.
+///
+/// Leaves are hashed as `sha256(0x00 || user_data)` and inner nodes as
+/// `sha256(0x01 || left || right)`. The last node of a level with an odd number of nodes is
+/// promoted to the next level as is, so it has no sibling in the proof.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InclusionProof {
+    index: u64,
+    leaf_count: u64,
+    siblings: Vec<Hash>,
+}
+
+impl InclusionProof {
+    pub fn new(index: u64, leaf_count: u64, siblings: Vec<Hash>) -> Result<Self, StdError> {
+        if index >= leaf_count {
+            return Err(StdError::generic_err("leaf index out of bounds"));
+        }
+        if siblings.len() != path_len(index, leaf_count) {
+            return Err(StdError::generic_err(
+                "number of siblings doesn't match the tree size",
+            ));
+        }
+
+        Ok(Self {
+            index,
+            leaf_count,
+            siblings,
+        })
+    }
+
+    pub fn index(&self) -> u64 {
+        self.index
+    }
+
+    pub fn leaf_count(&self) -> u64 {
+        self.leaf_count
+    }
+
+    /// Computes the root of the tree from the hash of the leaf at `self.index()`.
+    pub fn root(&self, leaf: Hash) -> Hash {
+        let mut siblings = self.siblings.iter();
+        let (mut index, mut width, mut hash) = (self.index, self.leaf_count, leaf);
+        while width > 1 {
+            if index % 2 == 1 {
+                let sibling = siblings.next().expect("checked in constructor");
+                hash = node_hash(sibling, &hash);
+            } else if index + 1 < width {
+                let sibling = siblings.next().expect("checked in constructor");
+                hash = node_hash(&hash, sibling);
+            }
+            index /= 2;
+            width = width.div_ceil(2);
+        }
+        hash
+    }
+}
+
+#[cw_serde]
+pub struct RawInclusionProof {
+    pub index: u64,
+    pub leaf_count: u64,
+    pub siblings: Vec<HexBinary>,
+}
+
+impl TryFrom<RawInclusionProof> for InclusionProof {
+    type Error = StdError;
+
+    fn try_from(value: RawInclusionProof) -> Result<Self, Self::Error> {
+        let siblings = value
+            .siblings
+            .iter()
+            .map(HexBinary::to_array)
+            .collect::<Result<_, _>>()?;
+        Self::new(value.index, value.leaf_count, siblings)
+    }
+}
+
+impl From<InclusionProof> for RawInclusionProof {
+    fn from(value: InclusionProof) -> Self {
+        Self {
+            index: value.index,
+            leaf_count: value.leaf_count,
+            siblings: value.siblings.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+/// Builds the Merkle tree over the user data of `msgs` and returns its root (to be attested) along
+/// with each message and its inclusion proof. Returns `None` if `msgs` is empty.
+pub fn batch<M: HasUserData>(msgs: Vec<M>) -> Option<(BatchRoot, Vec<Batched<M>>)> {
+    if msgs.is_empty() {
+        return None;
+    }
+
+    let mut levels = vec![msgs
+        .iter()
+        .map(|msg| leaf_hash(&msg.user_data()))
+        .collect::<Vec<_>>()];
+    while let Some(level) = levels.last().filter(|level| level.len() > 1) {
+        let next = level
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => node_hash(left, right),
+                [single] => *single,
+                _ => unreachable!("chunks of 2"),
+            })
+            .collect();
+        levels.push(next);
+    }
+    let root = levels.last().expect("at least the leaf level")[0];
+
+    let leaf_count = msgs.len() as u64;
+    let batched = msgs
+        .into_iter()
+        .enumerate()
+        .map(|(i, msg)| {
+            let mut index = i;
+            let siblings = levels
+                .iter()
+                .filter_map(|level| {
+                    let sibling = level.get(index ^ 1).copied();
+                    index /= 2;
+                    sibling
+                })
+                .collect();
+            let proof = InclusionProof::new(i as u64, leaf_count, siblings)
+                .expect("proof for a leaf of this tree");
+            Batched::new(msg, proof)
+        })
+        .collect();
+
+    Some((BatchRoot(root), batched))
+}
+
+fn leaf_hash(user_data: &UserData) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.update([LEAF_PREFIX]);
+    hasher.update(user_data);
+    hasher.finalize().into()
+}
+
+fn node_hash(left: &Hash, right: &Hash) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.update([NODE_PREFIX]);
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// The number of siblings on the path from leaf `index` to the root.
+fn path_len(mut index: u64, mut width: u64) -> usize {
+    let mut len = 0;
+    while width > 1 {
+        if index % 2 == 1 || index + 1 < width {
+            len += 1;
+        }
+        index /= 2;
+        width = width.div_ceil(2);
+    }
+    len
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TestMsg(u8);
+
+    impl HasUserData for TestMsg {
+        fn user_data(&self) -> UserData {
+            [self.0; 64]
+        }
+    }
+
+    #[test]
+    fn test_batch_proofs_verify() {
+        for n in 1..=9u8 {
+            let (root, batched) = batch((0..n).map(TestMsg).collect()).expect("non-empty batch");
+            assert_eq!(batched.len(), usize::from(n));
+            for msg in &batched {
+                assert_eq!(msg.user_data(), root.user_data());
+            }
+        }
+        assert!(batch(Vec::<TestMsg>::new()).is_none());
+    }
+
+    #[test]
+    fn test_batch_proofs_are_bound_to_msg() {
+        let (root, batched) = batch((0..5).map(TestMsg).collect()).expect("non-empty batch");
+        let proof = batched[2].proof().clone();
+
+        let other = Batched::new(TestMsg(7), proof.clone());
+        assert_ne!(other.user_data(), root.user_data());
+
+        // the same siblings at another index don't verify either
+        let moved = InclusionProof::new(3, proof.leaf_count(), proof.siblings.clone())
+            .expect("same path length");
+        assert_ne!(
+            Batched::new(TestMsg(2), moved).user_data(),
+            root.user_data()
+        );
+
+        // a proof must have exactly as many siblings as the path is long
+        assert!(InclusionProof::new(4, 5, proof.siblings).is_err());
+        assert!(InclusionProof::new(5, 5, vec![]).is_err());
+    }
+}
@@ -0,0 +1,58 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{HexBinary, StdError};
+
+use crate::{
+    msg::HasDomainType,
+    state::{Height, MrEnclave},
+};
+
+/// Retires an allowlisted MRENCLAVE. Only the admin may send this.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RetireMrEnclave {
+    mr_enclave: MrEnclave,
+    expiry_height: Option<Height>,
+}
+
+impl RetireMrEnclave {
+    pub fn new(mr_enclave: MrEnclave, expiry_height: Option<Height>) -> Self {
+        Self {
+            mr_enclave,
+            expiry_height,
+        }
+    }
+
+    pub fn into_tuple(self) -> (MrEnclave, Option<Height>) {
+        (self.mr_enclave, self.expiry_height)
+    }
+}
+
+#[cw_serde]
+pub struct RawRetireMrEnclave {
+    mr_enclave: HexBinary,
+    /// Defaults to the height at which the message is executed, i.e. retire immediately.
+    expiry_height: Option<u64>,
+}
+
+impl TryFrom<RawRetireMrEnclave> for RetireMrEnclave {
+    type Error = StdError;
+
+    fn try_from(value: RawRetireMrEnclave) -> Result<Self, Self::Error> {
+        Ok(Self {
+            mr_enclave: value.mr_enclave.to_array()?,
+            expiry_height: value.expiry_height,
+        })
+    }
+}
+
+impl From<RetireMrEnclave> for RawRetireMrEnclave {
+    fn from(value: RetireMrEnclave) -> Self {
+        Self {
+            mr_enclave: value.mr_enclave.into(),
+            expiry_height: value.expiry_height,
+        }
+    }
+}
+
+impl HasDomainType for RawRetireMrEnclave {
+    type DomainType = RetireMrEnclave;
+}
@@ -1,9 +1,11 @@
 use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{HexBinary, StdError};
-use sha2::{Digest, Sha256};
 
 use crate::{
-    msg::{execute::attested::HasUserData, HasDomainType},
+    msg::{
+        execute::attested::{canonical_user_data, HasUserData, UserDataDomain},
+        HasDomainType,
+    },
     state::{Nonce, UserData},
 };
 
@@ -11,11 +13,16 @@ use crate::{
 pub struct SessionCreate {
     nonce: Nonce,
     contract: String,
+    chain_id: String,
 }
 
 impl SessionCreate {
-    pub fn new(nonce: Nonce, contract: String) -> Self {
-        Self { nonce, contract }
+    pub fn new(nonce: Nonce, contract: String, chain_id: String) -> Self {
+        Self {
+            nonce,
+            contract,
+            chain_id,
+        }
     }
 
     pub fn nonce(&self) -> Nonce {
@@ -25,12 +32,17 @@ impl SessionCreate {
     pub fn contract(&self) -> &str {
         self.contract.as_str()
     }
+
+    pub fn chain_id(&self) -> &str {
+        self.chain_id.as_str()
+    }
 }
 
 #[cw_serde]
 pub struct RawSessionCreate {
     nonce: HexBinary,
     contract: String,
+    chain_id: String,
 }
 
 impl TryFrom<RawSessionCreate> for SessionCreate {
@@ -38,8 +50,11 @@ impl TryFrom<RawSessionCreate> for SessionCreate {
 
     fn try_from(value: RawSessionCreate) -> Result<Self, Self::Error> {
         let nonce = value.nonce.to_array()?;
-        let contract = value.contract;
-        Ok(Self { nonce, contract })
+        Ok(Self {
+            nonce,
+            contract: value.contract,
+            chain_id: value.chain_id,
+        })
     }
 }
 
@@ -48,6 +63,7 @@ impl From<SessionCreate> for RawSessionCreate {
         Self {
             nonce: value.nonce.into(),
             contract: value.contract,
+            chain_id: value.chain_id,
         }
     }
 }
@@ -58,15 +74,9 @@ impl HasDomainType for RawSessionCreate {
 
 impl HasUserData for SessionCreate {
     fn user_data(&self) -> UserData {
-        let mut hasher = Sha256::new();
-        hasher.update(
-            serde_json::to_string(&RawSessionCreate::from(self.clone()))
-                .expect("infallible serializer"),
-        );
-        let digest: [u8; 32] = hasher.finalize().into();
-
-        let mut user_data = [0u8; 64];
-        user_data[0..32].copy_from_slice(&digest);
-        user_data
+        let domain = UserDataDomain::new("quartz/session_create")
+            .with_contract(&self.contract)
+            .with_chain_id(&self.chain_id);
+        canonical_user_data(Some(domain), &RawSessionCreate::from(self.clone()))
     }
 }
@@ -0,0 +1,108 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{HexBinary, StdError, Uint64};
+
+use crate::{
+    msg::{
+        execute::attested::{canonical_user_data, HasUserData, UserDataDomain},
+        HasDomainType,
+    },
+    state::{MrEnclave, Nonce, UserData},
+};
+
+/// Sent by the enclave currently holding the session once it has handed its state over to the
+/// enclave that requested it. The session pub key is replaced with `pub_key` at `epoch`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SessionHandoverComplete {
+    nonce: Nonce,
+    epoch: u64,
+    mr_enclave: MrEnclave,
+    pub_key: Vec<u8>,
+    contract: String,
+    chain_id: String,
+}
+
+impl SessionHandoverComplete {
+    pub fn new(
+        nonce: Nonce,
+        epoch: u64,
+        mr_enclave: MrEnclave,
+        pub_key: Vec<u8>,
+        contract: String,
+        chain_id: String,
+    ) -> Self {
+        Self {
+            nonce,
+            epoch,
+            mr_enclave,
+            pub_key,
+            contract,
+            chain_id,
+        }
+    }
+
+    pub fn contract(&self) -> &str {
+        self.contract.as_str()
+    }
+
+    pub fn chain_id(&self) -> &str {
+        self.chain_id.as_str()
+    }
+
+    pub fn into_tuple(self) -> (Nonce, u64, MrEnclave, Vec<u8>) {
+        (self.nonce, self.epoch, self.mr_enclave, self.pub_key)
+    }
+}
+
+#[cw_serde]
+pub struct RawSessionHandoverComplete {
+    nonce: HexBinary,
+    epoch: Uint64,
+    mr_enclave: HexBinary,
+    pub_key: HexBinary,
+    contract: String,
+    chain_id: String,
+}
+
+impl TryFrom<RawSessionHandoverComplete> for SessionHandoverComplete {
+    type Error = StdError;
+
+    fn try_from(value: RawSessionHandoverComplete) -> Result<Self, Self::Error> {
+        Ok(Self {
+            nonce: value.nonce.to_array()?,
+            epoch: value.epoch.u64(),
+            mr_enclave: value.mr_enclave.to_array()?,
+            pub_key: value.pub_key.into(),
+            contract: value.contract,
+            chain_id: value.chain_id,
+        })
+    }
+}
+
+impl From<SessionHandoverComplete> for RawSessionHandoverComplete {
+    fn from(value: SessionHandoverComplete) -> Self {
+        Self {
+            nonce: value.nonce.into(),
+            epoch: value.epoch.into(),
+            mr_enclave: value.mr_enclave.into(),
+            pub_key: value.pub_key.into(),
+            contract: value.contract,
+            chain_id: value.chain_id,
+        }
+    }
+}
+
+impl HasDomainType for RawSessionHandoverComplete {
+    type DomainType = SessionHandoverComplete;
+}
+
+impl HasUserData for SessionHandoverComplete {
+    fn user_data(&self) -> UserData {
+        let domain = UserDataDomain::new("quartz/session_handover_complete")
+            .with_contract(&self.contract)
+            .with_chain_id(&self.chain_id);
+        canonical_user_data(
+            Some(domain),
+            &RawSessionHandoverComplete::from(self.clone()),
+        )
+    }
+}
@@ -0,0 +1,103 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{HexBinary, StdError};
+
+use crate::{
+    msg::{
+        execute::attested::{canonical_user_data, HasUserData, UserDataDomain},
+        HasDomainType,
+    },
+    state::{MrEnclave, Nonce, UserData},
+};
+
+/// Sent by an upgraded enclave to request that the enclave currently holding the session hands it
+/// over. The attestation must come from `mr_enclave` itself.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SessionHandoverRequest {
+    nonce: Nonce,
+    mr_enclave: MrEnclave,
+    pub_key: Vec<u8>,
+    contract: String,
+    chain_id: String,
+}
+
+impl SessionHandoverRequest {
+    pub fn new(
+        nonce: Nonce,
+        mr_enclave: MrEnclave,
+        pub_key: Vec<u8>,
+        contract: String,
+        chain_id: String,
+    ) -> Self {
+        Self {
+            nonce,
+            mr_enclave,
+            pub_key,
+            contract,
+            chain_id,
+        }
+    }
+
+    pub fn contract(&self) -> &str {
+        self.contract.as_str()
+    }
+
+    pub fn chain_id(&self) -> &str {
+        self.chain_id.as_str()
+    }
+
+    pub fn mr_enclave(&self) -> MrEnclave {
+        self.mr_enclave
+    }
+
+    pub fn into_tuple(self) -> (Nonce, MrEnclave, Vec<u8>) {
+        (self.nonce, self.mr_enclave, self.pub_key)
+    }
+}
+
+#[cw_serde]
+pub struct RawSessionHandoverRequest {
+    nonce: HexBinary,
+    mr_enclave: HexBinary,
+    pub_key: HexBinary,
+    contract: String,
+    chain_id: String,
+}
+
+impl TryFrom<RawSessionHandoverRequest> for SessionHandoverRequest {
+    type Error = StdError;
+
+    fn try_from(value: RawSessionHandoverRequest) -> Result<Self, Self::Error> {
+        Ok(Self {
+            nonce: value.nonce.to_array()?,
+            mr_enclave: value.mr_enclave.to_array()?,
+            pub_key: value.pub_key.into(),
+            contract: value.contract,
+            chain_id: value.chain_id,
+        })
+    }
+}
+
+impl From<SessionHandoverRequest> for RawSessionHandoverRequest {
+    fn from(value: SessionHandoverRequest) -> Self {
+        Self {
+            nonce: value.nonce.into(),
+            mr_enclave: value.mr_enclave.into(),
+            pub_key: value.pub_key.into(),
+            contract: value.contract,
+            chain_id: value.chain_id,
+        }
+    }
+}
+
+impl HasDomainType for RawSessionHandoverRequest {
+    type DomainType = SessionHandoverRequest;
+}
+
+impl HasUserData for SessionHandoverRequest {
+    fn user_data(&self) -> UserData {
+        let domain = UserDataDomain::new("quartz/session_handover_request")
+            .with_contract(&self.contract)
+            .with_chain_id(&self.chain_id);
+        canonical_user_data(Some(domain), &RawSessionHandoverRequest::from(self.clone()))
+    }
+}
@@ -0,0 +1,108 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{HexBinary, StdError, Uint64};
+
+use crate::{
+    msg::{
+        execute::attested::{canonical_user_data, HasUserData, UserDataDomain},
+        HasDomainType,
+    },
+    state::{Nonce, UserData},
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SessionRotatePubKey {
+    nonce: Nonce,
+    epoch: u64,
+    pub_key: Vec<u8>,
+    contract: String,
+    chain_id: String,
+}
+
+impl SessionRotatePubKey {
+    pub fn new(
+        nonce: Nonce,
+        epoch: u64,
+        pub_key: Vec<u8>,
+        contract: String,
+        chain_id: String,
+    ) -> Self {
+        Self {
+            nonce,
+            epoch,
+            pub_key,
+            contract,
+            chain_id,
+        }
+    }
+
+    pub fn contract(&self) -> &str {
+        self.contract.as_str()
+    }
+
+    pub fn chain_id(&self) -> &str {
+        self.chain_id.as_str()
+    }
+
+    pub fn into_tuple(self) -> (Nonce, u64, Vec<u8>) {
+        (self.nonce, self.epoch, self.pub_key)
+    }
+}
+
+#[cw_serde]
+pub struct RawSessionRotatePubKey {
+    nonce: HexBinary,
+    epoch: Uint64,
+    pub_key: HexBinary,
+    contract: String,
+    chain_id: String,
+}
+
+impl RawSessionRotatePubKey {
+    pub fn pub_key(&self) -> &HexBinary {
+        &self.pub_key
+    }
+
+    pub fn epoch(&self) -> u64 {
+        self.epoch.u64()
+    }
+}
+
+impl TryFrom<RawSessionRotatePubKey> for SessionRotatePubKey {
+    type Error = StdError;
+
+    fn try_from(value: RawSessionRotatePubKey) -> Result<Self, Self::Error> {
+        let nonce = value.nonce.to_array()?;
+        Ok(Self {
+            nonce,
+            epoch: value.epoch.u64(),
+            pub_key: value.pub_key.into(),
+            contract: value.contract,
+            chain_id: value.chain_id,
+        })
+    }
+}
+
+impl From<SessionRotatePubKey> for RawSessionRotatePubKey {
+    fn from(value: SessionRotatePubKey) -> Self {
+        Self {
+            nonce: value.nonce.into(),
+            epoch: value.epoch.into(),
+            pub_key: value.pub_key.into(),
+            contract: value.contract,
+            chain_id: value.chain_id,
+        }
+    }
+}
+
+impl HasDomainType for RawSessionRotatePubKey {
+    type DomainType = SessionRotatePubKey;
+}
+
+impl HasUserData for SessionRotatePubKey {
+    fn user_data(&self) -> UserData {
+        let domain = UserDataDomain::new("quartz/session_rotate_pub_key")
+            .with_contract(&self.contract)
+            .with_chain_id(&self.chain_id);
+        canonical_user_data(Some(domain), &RawSessionRotatePubKey::from(self.clone()))
+    }
+}
@@ -1,9 +1,11 @@
 use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{HexBinary, StdError};
-use sha2::{Digest, Sha256};
 
 use crate::{
-    msg::{execute::attested::HasUserData, HasDomainType},
+    msg::{
+        execute::attested::{canonical_user_data, HasUserData, UserDataDomain},
+        HasDomainType,
+    },
     state::{Nonce, UserData},
 };
 
@@ -62,15 +64,8 @@ impl HasDomainType for RawSessionSetPubKey {
 
 impl HasUserData for SessionSetPubKey {
     fn user_data(&self) -> UserData {
-        let mut hasher = Sha256::new();
-        hasher.update(
-            serde_json::to_string(&RawSessionSetPubKey::from(self.clone()))
-                .expect("infallible serializer"),
-        );
-        let digest: [u8; 32] = hasher.finalize().into();
-
-        let mut user_data = [0u8; 64];
-        user_data[0..32].copy_from_slice(&digest);
-        user_data
+        // the nonce binds the message to the session (and thus the contract) it was created for
+        let domain = UserDataDomain::new("quartz/session_set_pub_key");
+        canonical_user_data(Some(domain), &RawSessionSetPubKey::from(self.clone()))
     }
 }
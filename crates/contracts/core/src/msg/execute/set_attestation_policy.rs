@@ -0,0 +1,49 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::StdError;
+use quartz_tee_ra::intel_sgx::dcap::AttestationPolicy;
+
+use crate::{msg::HasDomainType, state::RawAttestationPolicy};
+
+/// Replaces the TCB statuses and advisories that are acceptable for the attesting platform. Only
+/// the admin may send this.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SetAttestationPolicy {
+    policy: AttestationPolicy,
+}
+
+impl SetAttestationPolicy {
+    pub fn new(policy: AttestationPolicy) -> Self {
+        Self { policy }
+    }
+
+    pub fn into_policy(self) -> AttestationPolicy {
+        self.policy
+    }
+}
+
+#[cw_serde]
+pub struct RawSetAttestationPolicy {
+    policy: RawAttestationPolicy,
+}
+
+impl TryFrom<RawSetAttestationPolicy> for SetAttestationPolicy {
+    type Error = StdError;
+
+    fn try_from(value: RawSetAttestationPolicy) -> Result<Self, Self::Error> {
+        Ok(Self {
+            policy: value.policy.try_into()?,
+        })
+    }
+}
+
+impl From<SetAttestationPolicy> for RawSetAttestationPolicy {
+    fn from(value: SetAttestationPolicy) -> Self {
+        Self {
+            policy: value.policy.into(),
+        }
+    }
+}
+
+impl HasDomainType for RawSetAttestationPolicy {
+    type DomainType = SetAttestationPolicy;
+}
@@ -1,11 +1,11 @@
 use cosmwasm_schema::cw_serde;
 use cosmwasm_std::StdError;
-use sha2::{Digest, Sha256};
 
 use crate::{
     msg::{
         execute::attested::{
-            Attested, DefaultAttestation, HasUserData, RawAttested, RawDefaultAttestation,
+            canonical_user_data, Attested, DefaultAttestation, HasUserData, RawAttested,
+            RawDefaultAttestation, UserDataDomain,
         },
         HasDomainType,
     },
@@ -89,15 +89,9 @@ impl HasDomainType for RawCoreInstantiate {
 
 impl HasUserData for CoreInstantiate {
     fn user_data(&self) -> UserData {
-        let mut hasher = Sha256::new();
-        hasher.update(
-            serde_json::to_string(&RawCoreInstantiate::from(self.clone()))
-                .expect("infallible serializer"),
-        );
-        let digest: [u8; 32] = hasher.finalize().into();
-
-        let mut user_data = [0u8; 64];
-        user_data[0..32].copy_from_slice(&digest);
-        user_data
+        // the contract's address isn't known before it's instantiated
+        let domain = UserDataDomain::new("quartz/instantiate")
+            .with_chain_id(self.config.light_client_opts().chain_id());
+        canonical_user_data(Some(domain), &RawCoreInstantiate::from(self.clone()))
     }
 }
@@ -1 +1,49 @@
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::{HexBinary, Uint64};
 
+use crate::state::{PendingHandover, RawConfig, RawMrEnclaveEntry};
+
+/// Queries for the state that Quartz keeps in the contract. Apps can expose these by mounting the
+/// `QueryHandler` in their `query` entry point.
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum Query {
+    #[returns(RawConfig)]
+    Config {},
+    /// Returns `None` if no session was created yet.
+    #[returns(Option<SessionResponse>)]
+    Session {},
+    #[returns(SequenceNumResponse)]
+    SequenceNum {},
+    /// Returns the most recently activated MRENCLAVE that is currently active.
+    #[returns(MrEnclaveResponse)]
+    MrEnclave {},
+    /// Returns the MRENCLAVE allowlist, including entries that are not yet active or retired.
+    #[returns(MrEnclavesResponse)]
+    MrEnclaves {},
+    /// Returns `None` if no session handover was requested.
+    #[returns(Option<PendingHandover>)]
+    PendingHandover {},
+}
+
+#[cw_serde]
+pub struct SessionResponse {
+    pub nonce: HexBinary,
+    pub pub_key: Option<HexBinary>,
+    pub epoch: Uint64,
+}
+
+#[cw_serde]
+pub struct SequenceNumResponse {
+    pub seq_num: Uint64,
+}
+
+#[cw_serde]
+pub struct MrEnclaveResponse {
+    pub mr_enclave: HexBinary,
+}
+
+#[cw_serde]
+pub struct MrEnclavesResponse {
+    pub mr_enclaves: Vec<RawMrEnclaveEntry>,
+}
@@ -1,7 +1,7 @@
 pub use crate::{
-    handler::RawHandler,
+    handler::{QueryHandler, RawHandler},
     msg::{
         execute::RawExecute as QuartzExecuteMsg,
-        instantiate::RawInstantiate as QuartzInstantiateMsg,
+        instantiate::RawInstantiate as QuartzInstantiateMsg, query::Query as QuartzQueryMsg,
     },
 };
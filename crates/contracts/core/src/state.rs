@@ -1,8 +1,10 @@
 use cosmwasm_schema::cw_serde;
 use cosmwasm_std::{HexBinary, StdError, Uint64};
-use cw_storage_plus::Item;
+use cw_storage_plus::{Item, Map};
+use quartz_tee_ra::intel_sgx::dcap::{AttestationPolicy, TcbStatus};
 
 pub type MrEnclave = [u8; 32];
+pub type MrSigner = [u8; 32];
 pub type Nonce = [u8; 32];
 pub type UserData = [u8; 64];
 pub type Hash = [u8; 32];
@@ -12,9 +14,18 @@ pub type TrustThreshold = (u64, u64);
 pub const CONFIG_KEY: &str = "quartz_config";
 pub const SESSION_KEY: &str = "quartz_session";
 pub const SEQUENCE_NUM_KEY: &str = "quartz_seq_num";
+pub const PENDING_HANDOVER_KEY: &str = "quartz_pending_handover";
+pub const VERIFIED_BATCHES_KEY: &str = "quartz_verified_batches";
 pub const CONFIG: Item<RawConfig> = Item::new(CONFIG_KEY);
 pub const SESSION: Item<Session> = Item::new(SESSION_KEY);
 pub const SEQUENCE_NUM: Item<Uint64> = Item::new(SEQUENCE_NUM_KEY);
+pub const PENDING_HANDOVER: Item<PendingHandover> = Item::new(PENDING_HANDOVER_KEY);
+/// The heights at which batch attestations were verified, by the hash of the batch root, the
+/// attested enclave identity and the attestation policy they were verified against.
+pub const VERIFIED_BATCHES: Map<&[u8], Height> = Map::new(VERIFIED_BATCHES_KEY);
+/// Number of blocks after its verification that a batch attestation covers the rest of the batch.
+/// After that, it's verified again (e.g. against TCB info that was updated in the meantime).
+pub const VERIFIED_BATCH_TTL: Height = 100;
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Config {
@@ -22,6 +33,10 @@ pub struct Config {
     light_client_opts: LightClientOpts,
     tcbinfo_contract: Option<String>,
     dcap_verifier_contract: Option<String>,
+    admin: Option<String>,
+    mr_enclaves: Vec<MrEnclaveEntry>,
+    mr_signer: Option<MrSignerIdentity>,
+    attestation_policy: AttestationPolicy,
 }
 
 impl Config {
@@ -36,13 +51,38 @@ impl Config {
             light_client_opts,
             tcbinfo_contract,
             dcap_verifier_contract,
+            admin: None,
+            mr_enclaves: vec![MrEnclaveEntry::genesis(mr_enclave)],
+            mr_signer: None,
+            attestation_policy: AttestationPolicy::default(),
         }
     }
 
+    /// Additionally trusts any enclave signed by the given MRSIGNER, so that patched enclave builds
+    /// are accepted without being added to the MRENCLAVE allowlist.
+    pub fn with_mr_signer(mut self, mr_signer: MrSignerIdentity) -> Self {
+        self.mr_signer = Some(mr_signer);
+        self
+    }
+
+    /// Sets the TCB statuses and advisories that are acceptable for the attesting platform.
+    pub fn with_attestation_policy(mut self, attestation_policy: AttestationPolicy) -> Self {
+        self.attestation_policy = attestation_policy;
+        self
+    }
+
+    /// Sets the account that is allowed to add and retire MRENCLAVEs. If unset, it defaults to the
+    /// account that instantiates the contract.
+    pub fn with_admin(mut self, admin: String) -> Self {
+        self.admin = Some(admin);
+        self
+    }
+
     pub fn light_client_opts(&self) -> &LightClientOpts {
         &self.light_client_opts
     }
 
+    /// The MRENCLAVE the contract was instantiated with.
     pub fn mr_enclave(&self) -> MrEnclave {
         self.mr_enclave
     }
@@ -50,6 +90,266 @@ impl Config {
     pub fn tcbinfo_contract(&self) -> Option<&str> {
         self.tcbinfo_contract.as_deref()
     }
+
+    pub fn dcap_verifier_contract(&self) -> Option<&str> {
+        self.dcap_verifier_contract.as_deref()
+    }
+
+    pub fn admin(&self) -> Option<&str> {
+        self.admin.as_deref()
+    }
+
+    pub fn mr_enclaves(&self) -> &[MrEnclaveEntry] {
+        &self.mr_enclaves
+    }
+
+    pub fn mr_signer(&self) -> Option<&MrSignerIdentity> {
+        self.mr_signer.as_ref()
+    }
+
+    pub fn attestation_policy(&self) -> &AttestationPolicy {
+        &self.attestation_policy
+    }
+
+    pub fn set_attestation_policy(&mut self, attestation_policy: AttestationPolicy) {
+        self.attestation_policy = attestation_policy;
+    }
+
+    /// Returns `true` if `mr_enclave` is in the allowlist and active at `height`.
+    pub fn is_mr_enclave_active(&self, mr_enclave: MrEnclave, height: Height) -> bool {
+        self.mr_enclaves
+            .iter()
+            .any(|entry| entry.mr_enclave() == mr_enclave && entry.is_active_at(height))
+    }
+
+    /// Returns the most recently activated MRENCLAVE that is active at `height`.
+    pub fn latest_mr_enclave(&self, height: Height) -> Option<&MrEnclaveEntry> {
+        self.mr_enclaves
+            .iter()
+            .filter(|entry| entry.is_active_at(height))
+            .max_by_key(|entry| entry.activation_height())
+    }
+
+    pub fn add_mr_enclave(&mut self, entry: MrEnclaveEntry) -> Result<(), StdError> {
+        if self
+            .mr_enclaves
+            .iter()
+            .any(|e| e.mr_enclave() == entry.mr_enclave())
+        {
+            return Err(StdError::generic_err("mr_enclave already in allowlist"));
+        }
+        self.mr_enclaves.push(entry);
+        Ok(())
+    }
+
+    /// Sets the expiry height of an allowlisted MRENCLAVE. An entry can only be retired earlier than
+    /// its current expiry, never extended. Entries retired before they activate are removed.
+    pub fn retire_mr_enclave(
+        &mut self,
+        mr_enclave: MrEnclave,
+        expiry_height: Height,
+    ) -> Result<(), StdError> {
+        let idx = self
+            .mr_enclaves
+            .iter()
+            .position(|e| e.mr_enclave() == mr_enclave)
+            .ok_or_else(|| StdError::not_found("mr_enclave"))?;
+        let entry = &mut self.mr_enclaves[idx];
+        if entry.expiry_height().is_some_and(|h| h <= expiry_height) {
+            return Err(StdError::generic_err("mr_enclave already retired"));
+        }
+
+        if expiry_height > entry.activation_height() {
+            entry.expiry_height = Some(expiry_height);
+        } else if self.mr_enclaves.len() > 1 {
+            self.mr_enclaves.remove(idx);
+        } else {
+            return Err(StdError::generic_err("cannot remove the last mr_enclave"));
+        }
+        Ok(())
+    }
+}
+
+/// An allowlisted MRENCLAVE that is accepted in attestations from its activation height (inclusive)
+/// up to its expiry height (exclusive).
+#[derive(Clone, Debug, PartialEq)]
+pub struct MrEnclaveEntry {
+    mr_enclave: MrEnclave,
+    activation_height: Height,
+    expiry_height: Option<Height>,
+}
+
+impl MrEnclaveEntry {
+    pub fn new(
+        mr_enclave: MrEnclave,
+        activation_height: Height,
+        expiry_height: Option<Height>,
+    ) -> Result<Self, StdError> {
+        if expiry_height.is_some_and(|h| h <= activation_height) {
+            return Err(StdError::generic_err(
+                "expiry_height must be greater than activation_height",
+            ));
+        }
+
+        Ok(Self {
+            mr_enclave,
+            activation_height,
+            expiry_height,
+        })
+    }
+
+    fn genesis(mr_enclave: MrEnclave) -> Self {
+        Self {
+            mr_enclave,
+            activation_height: 0,
+            expiry_height: None,
+        }
+    }
+
+    pub fn mr_enclave(&self) -> MrEnclave {
+        self.mr_enclave
+    }
+
+    pub fn activation_height(&self) -> Height {
+        self.activation_height
+    }
+
+    pub fn expiry_height(&self) -> Option<Height> {
+        self.expiry_height
+    }
+
+    pub fn is_active_at(&self, height: Height) -> bool {
+        self.activation_height <= height && self.expiry_height.map_or(true, |h| height < h)
+    }
+}
+
+/// An MRSIGNER-based enclave identity. Enclaves signed by `mr_signer` for product `isv_prod_id` are
+/// trusted as long as their ISV SVN is at least `min_isv_svn`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MrSignerIdentity {
+    mr_signer: MrSigner,
+    isv_prod_id: u16,
+    min_isv_svn: u16,
+}
+
+impl MrSignerIdentity {
+    pub fn new(mr_signer: MrSigner, isv_prod_id: u16, min_isv_svn: u16) -> Self {
+        Self {
+            mr_signer,
+            isv_prod_id,
+            min_isv_svn,
+        }
+    }
+
+    pub fn mr_signer(&self) -> MrSigner {
+        self.mr_signer
+    }
+
+    pub fn isv_prod_id(&self) -> u16 {
+        self.isv_prod_id
+    }
+
+    pub fn min_isv_svn(&self) -> u16 {
+        self.min_isv_svn
+    }
+
+    pub fn accepts(&self, mr_signer: MrSigner, isv_prod_id: u16, isv_svn: u16) -> bool {
+        self.mr_signer == mr_signer
+            && self.isv_prod_id == isv_prod_id
+            && self.min_isv_svn <= isv_svn
+    }
+}
+
+#[cw_serde]
+pub struct RawMrSignerIdentity {
+    pub mr_signer: HexBinary,
+    pub isv_prod_id: u16,
+    pub min_isv_svn: u16,
+}
+
+impl TryFrom<RawMrSignerIdentity> for MrSignerIdentity {
+    type Error = StdError;
+
+    fn try_from(value: RawMrSignerIdentity) -> Result<Self, Self::Error> {
+        Ok(Self {
+            mr_signer: value.mr_signer.to_array()?,
+            isv_prod_id: value.isv_prod_id,
+            min_isv_svn: value.min_isv_svn,
+        })
+    }
+}
+
+impl From<MrSignerIdentity> for RawMrSignerIdentity {
+    fn from(value: MrSignerIdentity) -> Self {
+        Self {
+            mr_signer: value.mr_signer.into(),
+            isv_prod_id: value.isv_prod_id,
+            min_isv_svn: value.min_isv_svn,
+        }
+    }
+}
+
+#[cw_serde]
+pub struct RawAttestationPolicy {
+    /// TCB statuses as named in Intel's TCB info, e.g. `UpToDate` or `SWHardeningNeeded`.
+    pub tcb_statuses: Vec<String>,
+    pub advisory_ids: Vec<String>,
+}
+
+impl TryFrom<RawAttestationPolicy> for AttestationPolicy {
+    type Error = StdError;
+
+    fn try_from(value: RawAttestationPolicy) -> Result<Self, Self::Error> {
+        let tcb_statuses = value
+            .tcb_statuses
+            .iter()
+            .map(|status| status.parse::<TcbStatus>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| StdError::parse_err("tcb_statuses", e))?;
+
+        Ok(Self::new(
+            tcb_statuses,
+            value.advisory_ids.iter().map(String::as_str),
+        ))
+    }
+}
+
+impl From<AttestationPolicy> for RawAttestationPolicy {
+    fn from(value: AttestationPolicy) -> Self {
+        Self {
+            tcb_statuses: value.tcb_statuses().map(|s| s.to_string()).collect(),
+            advisory_ids: value.advisory_ids().map(ToString::to_string).collect(),
+        }
+    }
+}
+
+#[cw_serde]
+pub struct RawMrEnclaveEntry {
+    pub mr_enclave: HexBinary,
+    pub activation_height: u64,
+    pub expiry_height: Option<u64>,
+}
+
+impl TryFrom<RawMrEnclaveEntry> for MrEnclaveEntry {
+    type Error = StdError;
+
+    fn try_from(value: RawMrEnclaveEntry) -> Result<Self, Self::Error> {
+        Self::new(
+            value.mr_enclave.to_array()?,
+            value.activation_height,
+            value.expiry_height,
+        )
+    }
+}
+
+impl From<MrEnclaveEntry> for RawMrEnclaveEntry {
+    fn from(value: MrEnclaveEntry) -> Self {
+        Self {
+            mr_enclave: value.mr_enclave.into(),
+            activation_height: value.activation_height,
+            expiry_height: value.expiry_height,
+        }
+    }
 }
 
 #[cw_serde]
@@ -58,12 +358,30 @@ pub struct RawConfig {
     light_client_opts: RawLightClientOpts,
     tcbinfo_contract: Option<String>,
     dcap_verifier_contract: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    admin: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    mr_enclaves: Vec<RawMrEnclaveEntry>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    mr_signer: Option<RawMrSignerIdentity>,
+    /// Defaults to [`AttestationPolicy::default`] if unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    attestation_policy: Option<RawAttestationPolicy>,
 }
 
 impl RawConfig {
     pub fn mr_enclave(&self) -> &[u8] {
         self.mr_enclave.as_slice()
     }
+
+    pub fn admin(&self) -> Option<&str> {
+        self.admin.as_deref()
+    }
+
+    pub fn mr_enclaves(&self) -> &[RawMrEnclaveEntry] {
+        &self.mr_enclaves
+    }
+
     pub fn tcbinfo_contract(&self) -> Option<&str> {
         self.tcbinfo_contract.as_deref()
     }
@@ -77,14 +395,35 @@ impl TryFrom<RawConfig> for Config {
     type Error = StdError;
 
     fn try_from(value: RawConfig) -> Result<Self, Self::Error> {
+        let mr_enclave = value.mr_enclave.to_array()?;
+
+        // configs stored before the allowlist existed only accept the instantiating mr_enclave
+        let mr_enclaves = if value.mr_enclaves.is_empty() {
+            vec![MrEnclaveEntry::genesis(mr_enclave)]
+        } else {
+            value
+                .mr_enclaves
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?
+        };
+
         Ok(Self {
-            mr_enclave: value.mr_enclave.to_array()?,
+            mr_enclave,
             light_client_opts: value
                 .light_client_opts
                 .try_into()
                 .map_err(|e| StdError::parse_err("light_client_opts", e))?,
             tcbinfo_contract: value.tcbinfo_contract,
             dcap_verifier_contract: value.dcap_verifier_contract,
+            admin: value.admin,
+            mr_enclaves,
+            mr_signer: value.mr_signer.map(TryInto::try_into).transpose()?,
+            attestation_policy: value
+                .attestation_policy
+                .map(TryInto::try_into)
+                .transpose()?
+                .unwrap_or_default(),
         })
     }
 }
@@ -96,6 +435,10 @@ impl From<Config> for RawConfig {
             light_client_opts: value.light_client_opts.into(),
             tcbinfo_contract: value.tcbinfo_contract,
             dcap_verifier_contract: value.dcap_verifier_contract,
+            admin: value.admin,
+            mr_enclaves: value.mr_enclaves.into_iter().map(Into::into).collect(),
+            mr_signer: value.mr_signer.map(Into::into),
+            attestation_policy: Some(value.attestation_policy.into()),
         }
     }
 }
@@ -222,6 +565,12 @@ impl From<LightClientOpts> for RawLightClientOpts {
 pub struct Session {
     nonce: HexBinary,
     pub_key: Option<HexBinary>,
+    #[serde(default)]
+    epoch: Uint64,
+    /// The MRENCLAVE of the enclave that holds the session key. Unset for sessions whose handshake
+    /// isn't complete yet (or that were stored before the holder was recorded).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    holder: Option<HexBinary>,
 }
 
 impl Session {
@@ -229,9 +578,17 @@ impl Session {
         Self {
             nonce: nonce.into(),
             pub_key: None,
+            epoch: Uint64::zero(),
+            holder: None,
         }
     }
 
+    /// Records the enclave that (now) holds the session key.
+    pub fn with_holder(mut self, mr_enclave: MrEnclave) -> Self {
+        self.holder = Some(mr_enclave.into());
+        self
+    }
+
     pub fn with_pub_key(mut self, nonce: Nonce, pub_key: Vec<u8>) -> Option<Self> {
         if self.nonce == nonce && self.pub_key.is_none() {
             self.pub_key = Some(pub_key.into());
@@ -241,7 +598,72 @@ impl Session {
         }
     }
 
+    /// Replaces the session's pub key with a rotated one. The `epoch` must be exactly one more than
+    /// the current epoch and a pub key must already be set (i.e. the handshake must be complete).
+    pub fn with_rotated_pub_key(
+        mut self,
+        nonce: Nonce,
+        epoch: u64,
+        pub_key: Vec<u8>,
+    ) -> Option<Self> {
+        if self.nonce == nonce
+            && self.pub_key.is_some()
+            && Some(epoch) == self.epoch.u64().checked_add(1)
+        {
+            self.pub_key = Some(pub_key.into());
+            self.epoch = epoch.into();
+            Some(self)
+        } else {
+            None
+        }
+    }
+
     pub fn nonce(&self) -> Nonce {
         self.nonce.to_array().expect("correct by construction")
     }
+
+    pub fn pub_key(&self) -> Option<&HexBinary> {
+        self.pub_key.as_ref()
+    }
+
+    pub fn epoch(&self) -> u64 {
+        self.epoch.u64()
+    }
+
+    pub fn holder(&self) -> Option<MrEnclave> {
+        self.holder
+            .as_ref()
+            .map(|h| h.to_array().expect("correct by construction"))
+    }
+
+    /// Returns `true` if `mr_enclave` may act for the session holder, i.e. it's the holder or no
+    /// holder was recorded for the session.
+    pub fn is_held_by(&self, mr_enclave: MrEnclave) -> bool {
+        self.holder().map_or(true, |holder| holder == mr_enclave)
+    }
+}
+
+/// A session handover requested by an allowlisted enclave that wants to take over the session from
+/// the enclave currently holding it.
+#[cw_serde]
+pub struct PendingHandover {
+    mr_enclave: HexBinary,
+    pub_key: HexBinary,
+}
+
+impl PendingHandover {
+    pub fn new(mr_enclave: MrEnclave, pub_key: Vec<u8>) -> Self {
+        Self {
+            mr_enclave: mr_enclave.into(),
+            pub_key: pub_key.into(),
+        }
+    }
+
+    pub fn mr_enclave(&self) -> MrEnclave {
+        self.mr_enclave.to_array().expect("correct by construction")
+    }
+
+    pub fn pub_key(&self) -> &HexBinary {
+        &self.pub_key
+    }
 }
@@ -1,20 +1,73 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::HexBinary;
+use cosmwasm_std::{HexBinary, Timestamp};
 
 #[cw_serde]
-pub struct InstantiateMsg;
+#[derive(Default)]
+pub struct InstantiateMsg {
+    /// PEM-encoded root CA certificate that all certificate chains must end in. Defaults to
+    /// Intel's SGX root CA.
+    pub root_cert: Option<String>,
+}
 
 #[cw_serde]
-pub enum ExecuteMsg {}
+pub enum ExecuteMsg {
+    /// Verify a DCAP attestation and, if it succeeds, record it by the SHA-256 hash of the quote
+    /// and the `identities` and `policy` it was verified against (see
+    /// `QueryMsg::GetVerification`). Fails if the verification was already recorded.
+    VerifyDcapAttestation {
+        quote: HexBinary,
+        collateral: HexBinary,
+        identities: Option<Vec<u8>>,
+        /// CBOR-encoded `AttestationPolicy`. If set, the platform's TCB status and advisories are
+        /// checked against it instead of the advisories of the `identities`.
+        policy: Option<Vec<u8>>,
+    },
+}
 
 #[cw_serde]
 #[derive(QueryResponses)]
 pub enum QueryMsg {
     /// Verify a DCAP attestation
-    #[returns(())]
+    #[returns(DcapVerificationResponse)]
     VerifyDcapAttestation {
         quote: HexBinary,
         collateral: HexBinary,
         identities: Option<Vec<u8>>,
+        /// CBOR-encoded `AttestationPolicy`. If set, the platform's TCB status and advisories are
+        /// checked against it instead of the advisories of the `identities`.
+        policy: Option<Vec<u8>>,
     },
+    /// A verification that was recorded by `ExecuteMsg::VerifyDcapAttestation` for the same
+    /// `identities` and `policy`
+    #[returns(Option<RecordedVerificationResponse>)]
+    GetVerification {
+        quote_hash: HexBinary,
+        identities: Option<Vec<u8>>,
+        policy: Option<Vec<u8>>,
+    },
+}
+
+/// The outcome of a DCAP verification. The platform and enclave fields are taken from the
+/// evidence and can only be trusted if `verified` is `true`.
+#[cw_serde]
+pub struct DcapVerificationResponse {
+    pub verified: bool,
+    /// The first check that failed, e.g. `quote_signing_chain`
+    pub failed_check: Option<String>,
+    /// The TCB status of the platform, or `None` if its TCB level is revoked
+    pub tcb_status: Option<String>,
+    pub advisory_ids: Vec<String>,
+    pub mr_enclave: HexBinary,
+    pub mr_signer: HexBinary,
+    pub isv_prod_id: u16,
+    pub isv_svn: u16,
+    pub report_data: HexBinary,
+}
+
+#[cw_serde]
+pub struct RecordedVerificationResponse {
+    pub verification: DcapVerificationResponse,
+    /// The block height and time at which the attestation was verified
+    pub height: u64,
+    pub time: Timestamp,
 }
@@ -1,65 +1,317 @@
+use std::time::Duration;
+
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    to_json_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult,
+    to_json_binary, Binary, Deps, DepsMut, Env, HexBinary, MessageInfo, Response, StdError,
+    StdResult,
+};
+use der::{DateTime, DecodePem};
+use quartz_dcap_verifier_msgs::{
+    DcapVerificationResponse, ExecuteMsg, InstantiateMsg, QueryMsg, RecordedVerificationResponse,
 };
-use quartz_dcap_verifier_msgs::{ExecuteMsg, InstantiateMsg, QueryMsg};
-use quartz_tee_ra::{
-    intel_sgx::dcap::{Collateral, Quote3, TrustedIdentity},
-    verify_dcap_attestation, Error,
+use quartz_tee_ra::intel_sgx::dcap::{
+    certificate_chain::{TlsCertificateChainVerifier, INTEL_SGX_ROOT_CA_PEM},
+    AttestationPolicy, Collateral, DcapPolicyVerifier, Quote3, TrustedIdentity,
 };
+use sha2::{Digest, Sha256};
+use x509_cert::Certificate;
+
+use crate::state::{ROOT_CERTIFICATE, VERIFICATIONS};
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
-    _deps: DepsMut,
+    deps: DepsMut,
     _env: Env,
     _info: MessageInfo,
-    _msg: InstantiateMsg,
+    msg: InstantiateMsg,
 ) -> Result<Response, StdError> {
+    let root_cert = msg
+        .root_cert
+        .unwrap_or_else(|| INTEL_SGX_ROOT_CA_PEM.to_string());
+    let _ = parse_root_cert(&root_cert)?;
+    ROOT_CERTIFICATE.save(deps.storage, &root_cert)?;
     Ok(Response::default())
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
-    _deps: DepsMut,
-    _env: Env,
+    deps: DepsMut,
+    env: Env,
     _info: MessageInfo,
-    _msg: ExecuteMsg,
+    msg: ExecuteMsg,
 ) -> StdResult<Response> {
-    unimplemented!()
+    match msg {
+        ExecuteMsg::VerifyDcapAttestation {
+            quote,
+            collateral,
+            identities,
+            policy,
+        } => {
+            // the same quote may be recorded for other identities and policies, but a recorded
+            // verification is never replaced
+            let quote_hash = Sha256::digest(quote.as_slice());
+            let params_hash = params_hash(identities.as_deref(), policy.as_deref());
+            if VERIFICATIONS.has(deps.storage, (&quote_hash, &params_hash)) {
+                return Err(StdError::generic_err("verification already recorded"));
+            }
+
+            let verification = verify(deps.as_ref(), &env, quote, collateral, identities, policy)?;
+            if !verification.verified {
+                return Err(StdError::generic_err(format!(
+                    "DCAP verification failed: {}",
+                    verification.failed_check.unwrap_or_default()
+                )));
+            }
+
+            let record = RecordedVerificationResponse {
+                verification,
+                height: env.block.height,
+                time: env.block.time,
+            };
+            VERIFICATIONS.save(deps.storage, (&quote_hash, &params_hash), &record)?;
+
+            Ok(Response::default()
+                .add_attribute("action", "verify_dcap_attestation")
+                .add_attribute(
+                    "quote_hash",
+                    HexBinary::from(quote_hash.as_slice()).to_hex(),
+                )
+                .add_attribute("params_hash", HexBinary::from(params_hash).to_hex()))
+        }
+    }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(_deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::VerifyDcapAttestation {
             quote,
             collateral,
             identities,
+            policy,
         } => {
-            let quote = Quote3::<Vec<u8>>::try_from(Vec::<u8>::from(quote))
-                .map_err(|e| StdError::generic_err(format!("Quote parse error: {e}")))?;
-            let collateral: Collateral = ciborium::from_reader(collateral.as_slice())
-                .map_err(|e| StdError::generic_err(format!("Collateral deserialize error: {e}")))?;
-            let identities: Vec<TrustedIdentity> = if let Some(identities) = identities {
-                ciborium::from_reader(identities.as_slice())
-                    .map_err(|e| StdError::generic_err(format!("Identities parse error: {e}")))?
-            } else {
-                vec![]
-            };
-
             // attestation handler MUST verify that the user_data and mr_enclave match the config/msg
-            let verification_output =
-                verify_dcap_attestation(quote, collateral, identities.as_slice());
+            to_json_binary(&verify(deps, &env, quote, collateral, identities, policy)?)
+        }
+        QueryMsg::GetVerification {
+            quote_hash,
+            identities,
+            policy,
+        } => {
+            let params_hash = params_hash(identities.as_deref(), policy.as_deref());
+            to_json_binary(
+                &VERIFICATIONS.may_load(deps.storage, (quote_hash.as_slice(), &params_hash))?,
+            )
+        }
+    }
+}
 
-            // attestation handler MUST verify that the user_data and mr_enclave match the config/msg
-            if verification_output.is_success().into() {
-                to_json_binary(&())
-            } else {
-                Err(StdError::generic_err(
-                    Error::Dcap(verification_output).to_string(),
-                ))
+/// Hashes the (CBOR-encoded) identities and policy of a verification, so that a recorded
+/// verification is only returned to those asking for the same checks.
+fn params_hash(identities: Option<&[u8]>, policy: Option<&[u8]>) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    for param in [identities, policy] {
+        match param {
+            Some(param) => {
+                hasher.update([1]);
+                hasher.update((param.len() as u64).to_be_bytes());
+                hasher.update(param);
             }
+            None => hasher.update([0]),
+        }
+    }
+    hasher.finalize().into()
+}
+
+fn verify(
+    deps: Deps,
+    env: &Env,
+    quote: HexBinary,
+    collateral: HexBinary,
+    identities: Option<Vec<u8>>,
+    policy: Option<Vec<u8>>,
+) -> StdResult<DcapVerificationResponse> {
+    let quote = Quote3::<Vec<u8>>::try_from(Vec::<u8>::from(quote))
+        .map_err(|e| StdError::generic_err(format!("Quote parse error: {e}")))?;
+    let collateral: Collateral = ciborium::from_reader(collateral.as_slice())
+        .map_err(|e| StdError::generic_err(format!("Collateral deserialize error: {e}")))?;
+    let identities: Vec<TrustedIdentity> = if let Some(identities) = identities {
+        ciborium::from_reader(identities.as_slice())
+            .map_err(|e| StdError::generic_err(format!("Identities parse error: {e}")))?
+    } else {
+        vec![]
+    };
+    let policy: Option<AttestationPolicy> = policy
+        .map(|policy| {
+            ciborium::from_reader(policy.as_slice())
+                .map_err(|e| StdError::generic_err(format!("Policy parse error: {e}")))
+        })
+        .transpose()?;
+
+    let root_ca = parse_root_cert(&ROOT_CERTIFICATE.load(deps.storage)?)?;
+    let time = DateTime::from_unix_duration(Duration::from_secs(env.block.time.seconds()))
+        .map_err(|e| StdError::generic_err(format!("Block time error: {e}")))?;
+
+    let verifier = DcapPolicyVerifier::new(
+        TlsCertificateChainVerifier::new(root_ca),
+        identities,
+        policy,
+        time,
+    );
+    let (output, summary) = verifier
+        .verify_with_summary(quote, collateral)
+        .map_err(|e| StdError::generic_err(format!("Invalid DCAP evidence: {e:?}")))?;
+
+    Ok(DcapVerificationResponse {
+        verified: output.is_success(),
+        failed_check: output.failed_check().map(ToString::to_string),
+        tcb_status: summary.tcb_status.map(|status| status.to_string()),
+        advisory_ids: summary.advisory_ids,
+        mr_enclave: summary.mr_enclave.into(),
+        mr_signer: summary.mr_signer.into(),
+        isv_prod_id: summary.isv_prod_id,
+        isv_svn: summary.isv_svn,
+        report_data: summary.report_data.into(),
+    })
+}
+
+fn parse_root_cert(root_cert: &str) -> StdResult<Certificate> {
+    Certificate::from_pem(root_cert)
+        .map_err(|e| StdError::generic_err(format!("Root certificate parse error: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmwasm_std::{
+        from_json,
+        testing::{message_info, mock_dependencies, mock_env, MockApi, MockQuerier, MockStorage},
+        OwnedDeps, Timestamp,
+    };
+    use quartz_tee_ra::intel_sgx::dcap::{synthetic::SyntheticDcap, TrustedMrEnclaveIdentity};
+
+    use super::*;
+
+    const MR_ENCLAVE: [u8; 32] = [0xab; 32];
+    const USER_DATA: [u8; 64] = [0xcd; 64];
+
+    fn to_cbor<T: cosmwasm_schema::serde::Serialize>(value: &T) -> Vec<u8> {
+        let mut buffer = vec![];
+        ciborium::into_writer(value, &mut buffer).expect("infallible serializer");
+        buffer
+    }
+
+    fn env() -> Env {
+        let mut env = mock_env();
+        env.block.time = Timestamp::from_seconds(1_735_689_600); // 2025-01-01
+        env
+    }
+
+    fn setup(dcap: &SyntheticDcap) -> OwnedDeps<MockStorage, MockApi, MockQuerier> {
+        let mut deps = mock_dependencies();
+        let info = message_info(&deps.api.addr_make("creator"), &[]);
+        let msg = InstantiateMsg {
+            root_cert: Some(dcap.root_ca_pem()),
+        };
+        instantiate(deps.as_mut(), env(), info, msg).expect("valid root CA");
+        deps
+    }
+
+    fn verify_msg(dcap: &SyntheticDcap, mr_enclave: [u8; 32]) -> QueryMsg {
+        let identity: TrustedIdentity =
+            TrustedMrEnclaveIdentity::new(MR_ENCLAVE.into(), [""; 0], [""; 0]).into();
+        QueryMsg::VerifyDcapAttestation {
+            quote: dcap.quote(mr_enclave, USER_DATA).as_ref().to_vec().into(),
+            collateral: to_cbor(&dcap.collateral()).into(),
+            identities: Some(to_cbor(&[identity])),
+            policy: Some(to_cbor(&AttestationPolicy::default())),
         }
     }
+
+    #[test]
+    fn verification_result_is_structured() {
+        let dcap = SyntheticDcap::new().with_tcb_status("SWHardeningNeeded", &["INTEL-SA-00615"]);
+        let deps = setup(&dcap);
+
+        let res = query(deps.as_ref(), env(), verify_msg(&dcap, MR_ENCLAVE)).expect("valid query");
+        let res: DcapVerificationResponse = from_json(res).expect("valid response");
+        assert!(res.verified);
+        assert_eq!(res.failed_check, None);
+        assert_eq!(res.tcb_status.as_deref(), Some("SWHardeningNeeded"));
+        assert_eq!(res.advisory_ids, ["INTEL-SA-00615"]);
+        assert_eq!(res.mr_enclave.as_slice(), MR_ENCLAVE);
+        assert_eq!(res.report_data.as_slice(), USER_DATA);
+
+        let res = query(deps.as_ref(), env(), verify_msg(&dcap, [0x01; 32])).expect("valid query");
+        let res: DcapVerificationResponse = from_json(res).expect("valid response");
+        assert!(!res.verified);
+        assert_eq!(res.failed_check.as_deref(), Some("trusted_identities"));
+        assert_eq!(res.mr_enclave.as_slice(), [0x01; 32]);
+    }
+
+    #[test]
+    fn successful_verifications_are_recorded() {
+        let dcap = SyntheticDcap::new();
+        let mut deps = setup(&dcap);
+        let info = message_info(&deps.api.addr_make("anyone"), &[]);
+
+        let execute_msg = |mr_enclave| match verify_msg(&dcap, mr_enclave) {
+            QueryMsg::VerifyDcapAttestation {
+                quote,
+                collateral,
+                identities,
+                policy,
+            } => ExecuteMsg::VerifyDcapAttestation {
+                quote,
+                collateral,
+                identities,
+                policy,
+            },
+            _ => unreachable!(),
+        };
+        let recorded = |deps: Deps, mr_enclave, policy: &AttestationPolicy| {
+            let QueryMsg::VerifyDcapAttestation {
+                quote, identities, ..
+            } = verify_msg(&dcap, mr_enclave)
+            else {
+                unreachable!()
+            };
+            let msg = QueryMsg::GetVerification {
+                quote_hash: Sha256::digest(quote.as_slice()).to_vec().into(),
+                identities,
+                policy: Some(to_cbor(policy)),
+            };
+            let res = query(deps, env(), msg).expect("valid query");
+            from_json::<Option<RecordedVerificationResponse>>(res).expect("valid response")
+        };
+        let default_policy = AttestationPolicy::default();
+
+        let err = execute(deps.as_mut(), env(), info.clone(), execute_msg([0x01; 32]))
+            .expect_err("untrusted enclave must be rejected");
+        assert!(err.to_string().contains("trusted_identities"), "{err}");
+        assert_eq!(recorded(deps.as_ref(), [0x01; 32], &default_policy), None);
+
+        execute(deps.as_mut(), env(), info.clone(), execute_msg(MR_ENCLAVE))
+            .expect("valid attestation must be recorded");
+        let record =
+            recorded(deps.as_ref(), MR_ENCLAVE, &default_policy).expect("recorded verification");
+        assert!(record.verification.verified);
+        assert_eq!(record.verification.mr_enclave.as_slice(), MR_ENCLAVE);
+        assert_eq!(record.time, env().block.time);
+
+        // the verification is only recorded for the identities and policy it was checked against
+        let other_policy = AttestationPolicy::new([], [""; 0]);
+        assert_eq!(recorded(deps.as_ref(), MR_ENCLAVE, &other_policy), None);
+
+        // and a recorded verification can't be replaced
+        let mut later = env();
+        later.block.height += 1;
+        let err = execute(deps.as_mut(), later, info, execute_msg(MR_ENCLAVE))
+            .expect_err("recorded verification must not be overwritten");
+        assert!(err.to_string().contains("already recorded"), "{err}");
+        assert_eq!(
+            recorded(deps.as_ref(), MR_ENCLAVE, &default_policy),
+            Some(record)
+        );
+    }
 }
@@ -0,0 +1,9 @@
+use cw_storage_plus::{Item, Map};
+use quartz_dcap_verifier_msgs::RecordedVerificationResponse;
+
+/// PEM-encoded root CA certificate that all certificate chains must end in.
+pub const ROOT_CERTIFICATE: Item<String> = Item::new("root_certificate");
+/// Successful verifications by the SHA-256 hash of the quote and the hash of the identities and
+/// policy it was verified against (see `contract::params_hash`).
+pub const VERIFICATIONS: Map<(&[u8], &[u8]), RecordedVerificationResponse> =
+    Map::new("verifications");
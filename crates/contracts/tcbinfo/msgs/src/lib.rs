@@ -1,25 +1,57 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::Timestamp;
 
 #[cw_serde]
 pub struct InstantiateMsg {
     pub root_cert: String,
+    /// The only address that may submit TCB infos. Defaults to the instantiator.
+    pub admin: Option<String>,
 }
 
+/// Migrates a contract instantiated before the admin and the TCB info dates were stored.
+#[cw_serde]
+pub struct MigrateMsg {
+    /// The only address that may submit TCB infos. Required if the contract has no admin yet.
+    pub admin: Option<String>,
+}
+
+/// Submits a TCB info, which must be signed by `certificate` and be newer than the stored one for
+/// its FMSPC. The certificate and TCB info must be valid at the current block time.
 #[cw_serde]
 pub struct ExecuteMsg {
     pub tcb_info: String,
     pub certificate: String,
-    pub time: Option<String>,
+    /// The hex-encoded (DER) CRL of the root CA, against which the TCB signing certificate is
+    /// checked.
+    pub root_crl: Option<String>,
 }
 
 #[cw_serde]
 #[derive(QueryResponses)]
 pub enum QueryMsg {
+    /// The latest TCB info for the FMSPC
     #[returns(GetTcbInfoResponse)]
     GetTcbInfo { fmspc: String },
+    /// All TCB infos that were stored for the FMSPC, newest first
+    #[returns(GetTcbInfoHistoryResponse)]
+    GetTcbInfoHistory {
+        fmspc: String,
+        /// Only return TCB infos issued before this date
+        start_before: Option<Timestamp>,
+        limit: Option<u32>,
+    },
 }
 
 #[cw_serde]
 pub struct GetTcbInfoResponse {
     pub tcb_info: String,
+    pub issue_date: Timestamp,
+    pub next_update: Timestamp,
+    /// Whether the TCB info's `nextUpdate` has passed, i.e. it must not be used for verification.
+    pub stale: bool,
+}
+
+#[cw_serde]
+pub struct GetTcbInfoHistoryResponse {
+    pub tcb_infos: Vec<GetTcbInfoResponse>,
 }
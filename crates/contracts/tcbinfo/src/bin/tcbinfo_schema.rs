@@ -1,10 +1,11 @@
 use cosmwasm_schema::write_api;
-use quartz_tcbinfo_msgs::{ExecuteMsg, InstantiateMsg, QueryMsg};
+use quartz_tcbinfo_msgs::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
 
 fn main() {
     write_api! {
         instantiate: InstantiateMsg,
         execute: ExecuteMsg,
         query: QueryMsg,
+        migrate: MigrateMsg,
     }
 }
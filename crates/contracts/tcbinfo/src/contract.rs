@@ -1,18 +1,25 @@
+use std::time::Duration;
+
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
-use cosmwasm_std::{to_json_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
-use cw2::set_contract_version;
-use der::{DateTime, DecodePem};
-use mc_attestation_verifier::{CertificateChainVerifier, SignedTcbInfo};
+use cosmwasm_std::{
+    to_json_binary, Binary, Deps, DepsMut, Env, MessageInfo, Order, Response, StdError, StdResult,
+    Timestamp,
+};
+use cw2::{get_contract_version, set_contract_version};
+use der::{DateTime, Decode, DecodePem};
+use mc_attestation_verifier::{CertificateChainVerifier, Error as TcbError, SignedTcbInfo};
 use p256::ecdsa::VerifyingKey;
-use quartz_tcbinfo_msgs::{ExecuteMsg, GetTcbInfoResponse, InstantiateMsg, QueryMsg};
+use quartz_tcbinfo_msgs::{
+    ExecuteMsg, GetTcbInfoHistoryResponse, GetTcbInfoResponse, InstantiateMsg, MigrateMsg, QueryMsg,
+};
 use quartz_tee_ra::intel_sgx::dcap::certificate_chain::TlsCertificateChainVerifier;
 use serde_json::Value;
-use x509_cert::Certificate;
+use x509_cert::{crl::CertificateList, Certificate};
 
 use crate::{
     error::ContractError,
-    state::{TcbInfo, DATABASE, ROOT_CERTIFICATE},
+    state::{Fmspc, TcbInfo, ADMIN, DATABASE, HISTORY, ROOT_CERTIFICATE},
 };
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:quartz_tcbinfo";
@@ -22,132 +29,250 @@ const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 pub fn instantiate(
     deps: DepsMut,
     _env: Env,
-    _info: MessageInfo,
+    info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
+    if ROOT_CERTIFICATE.exists(deps.storage) || !DATABASE.is_empty(deps.storage) {
+        return Err(ContractError::AlreadyInstantiated);
+    }
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
-    let root = Certificate::from_pem(&msg.root_cert).expect("could not parse PEM");
-    let verifier = TlsCertificateChainVerifier::new(&msg.root_cert);
+    let root =
+        Certificate::from_pem(&msg.root_cert).map_err(|_| ContractError::CertificateReadError)?;
+    let verifier = TlsCertificateChainVerifier::new(root.clone());
     verifier
         .verify_certificate_chain(vec![&root], vec![], None)
         .map_err(|_| ContractError::CertificateVerificationError)?;
     ROOT_CERTIFICATE
         .save(deps.storage, &msg.root_cert.to_string())
         .map_err(ContractError::Std)?;
-    assert!(DATABASE.is_empty(deps.storage));
+    let admin = match msg.admin {
+        Some(admin) => deps.api.addr_validate(&admin)?,
+        None => info.sender,
+    };
+    ADMIN.save(deps.storage, &admin)?;
     Ok(Response::default())
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
-    _env: Env,
-    _info: MessageInfo,
+    env: Env,
+    info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
-    let signed_tcb_info: SignedTcbInfo =
-        SignedTcbInfo::try_from(msg.tcb_info.as_ref()).expect("failed to parse TCBInfo");
-    let raw_root = ROOT_CERTIFICATE.load(deps.storage).unwrap();
-    let root = Certificate::from_pem(raw_root.clone()).expect("could not parse PEM");
-    let verifier = TlsCertificateChainVerifier::new(&raw_root);
-    let fmspc = execute::get_fmspc(&msg.tcb_info);
-    let certificate = Certificate::from_pem(msg.certificate.clone()).expect("failed to parse PEM");
-
-    let time = msg
-        .time
-        .map(|time| time.parse::<DateTime>().expect("could not parse datetime"));
-
-    assert!(
-        execute::check_certificate_validity(&root, time),
-        "On-chain root certificate validity check failed"
-    );
-    assert!(
-        execute::check_certificate_validity(&certificate, time),
-        "Certificate validity check failed"
-    );
-
-    let key = VerifyingKey::from_sec1_bytes(
-        certificate
-            .tbs_certificate
-            .subject_public_key_info
-            .subject_public_key
-            .as_bytes()
-            .expect("Failed to parse public key"),
-    )
-    .expect("Failed to decode public key");
+    if info.sender != ADMIN.load(deps.storage)? {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let signed_tcb_info = SignedTcbInfo::try_from(msg.tcb_info.as_ref())
+        .map_err(|_| ContractError::TcbInfoReadError)?;
+    let raw_root = ROOT_CERTIFICATE.load(deps.storage)?;
+    let root = Certificate::from_pem(raw_root).map_err(|_| ContractError::CertificateReadError)?;
+    let verifier = TlsCertificateChainVerifier::new(root.clone());
+    let (fmspc, tcb_info) = execute::parse_tcb_info(&msg.tcb_info)?;
+    let certificate =
+        Certificate::from_pem(msg.certificate).map_err(|_| ContractError::CertificateReadError)?;
+
+    let time = DateTime::from_unix_duration(Duration::from_secs(env.block.time.seconds()))
+        .map_err(|_| ContractError::DateTimeReadError)?;
+    let crls = msg
+        .root_crl
+        .map(|crl| {
+            hex::decode(crl)
+                .ok()
+                .and_then(|der| CertificateList::from_der(&der).ok())
+                .ok_or(ContractError::CrlReadError)
+        })
+        .transpose()?;
+
+    let key = certificate
+        .tbs_certificate
+        .subject_public_key_info
+        .subject_public_key
+        .as_bytes()
+        .and_then(|key| VerifyingKey::from_sec1_bytes(key).ok())
+        .ok_or(ContractError::PublicKeyReadError)?;
 
     verifier
-        .verify_certificate_chain(vec![&certificate, &root], vec![], None)
+        .verify_certificate_chain(vec![&certificate, &root], crls.iter(), time)
         .map_err(|_| ContractError::CertificateVerificationError)?;
 
     signed_tcb_info
-        .verify(Some(&key), time)
-        .map_err(|_| ContractError::TcbInfoVerificationError)?;
+        .verify(Some(&key), Some(time))
+        .map_err(|e| match e {
+            TcbError::TcbInfoNotYetValid | TcbError::TcbInfoExpired => {
+                ContractError::TcbInfoExpired
+            }
+            _ => ContractError::TcbInfoVerificationError,
+        })?;
 
-    let _ = DATABASE
-        .save(
-            deps.storage,
-            fmspc,
-            &TcbInfo {
-                info: msg.tcb_info.to_string(),
-                //  certificate: msg.certificate.to_string(),
-            },
-        )
-        .map_err(ContractError::Std);
+    if let Some(stored) = DATABASE.may_load(deps.storage, fmspc)? {
+        if tcb_info.issue_date <= stored.issue_date {
+            return Err(ContractError::OutdatedTcbInfo);
+        }
+    }
 
-    Ok(Response::default())
+    DATABASE.save(deps.storage, fmspc, &tcb_info)?;
+    HISTORY.save(
+        deps.storage,
+        (fmspc.as_slice(), tcb_info.issue_date.seconds()),
+        &tcb_info,
+    )?;
+
+    Ok(Response::default()
+        .add_attribute("action", "add_tcb_info")
+        .add_attribute("fmspc", hex::encode(fmspc))
+        .add_attribute("issue_date", tcb_info.issue_date.to_string()))
 }
 
 pub mod execute {
     use super::*;
 
-    pub fn get_fmspc(tcbinfo: &str) -> [u8; 6] {
-        let tcbinfo_raw: Value = serde_json::from_str(tcbinfo).expect("could not read tcbinfo");
-        let fmspc_raw = hex::decode(
-            tcbinfo_raw
+    /// Parses the FMSPC, issue date and next update of the (signed) TCB info.
+    pub fn parse_tcb_info(tcb_info: &str) -> Result<(Fmspc, TcbInfo), ContractError> {
+        let tcb_info_raw: Value =
+            serde_json::from_str(tcb_info).map_err(|_| ContractError::TcbInfoReadError)?;
+        let field = |name: &str| {
+            tcb_info_raw
                 .get("tcbInfo")
-                .unwrap()
-                .get("fmspc")
-                .unwrap()
-                .as_str()
-                .expect("could not find fmspc string"),
-        )
-        .expect("failed to decode fmspc hex string");
-        fmspc_raw.try_into().unwrap()
-    }
-
-    pub fn check_certificate_validity(cert: &Certificate, time: Option<DateTime>) -> bool {
-        match time {
-            None => true,
-            Some(time) => {
-                let validity = cert.tbs_certificate.validity;
-                let start = validity.not_before.to_date_time();
-                let end = validity.not_after.to_date_time();
-                time >= start && time <= end
-            }
+                .and_then(|info| info.get(name))
+                .and_then(Value::as_str)
+                .ok_or(ContractError::TcbInfoReadError)
+        };
+
+        let fmspc = hex::decode(field("fmspc")?)
+            .ok()
+            .and_then(|fmspc| fmspc.try_into().ok())
+            .ok_or(ContractError::TcbInfoReadError)?;
+        let timestamp = |name: &str| {
+            field(name)?
+                .parse::<DateTime>()
+                .map(|time| Timestamp::from_seconds(time.unix_duration().as_secs()))
+                .map_err(|_| ContractError::DateTimeReadError)
+        };
+
+        Ok((
+            fmspc,
+            TcbInfo {
+                info: tcb_info.to_string(),
+                issue_date: timestamp("issueDate")?,
+                next_update: timestamp("nextUpdate")?,
+            },
+        ))
+    }
+}
+
+/// Migrates contracts that were instantiated before the admin and the issue and next update dates of
+/// the TCB infos were stored. The stored TCB infos are re-parsed and added to the history.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    let version = get_contract_version(deps.storage)?;
+    if version.contract != CONTRACT_NAME {
+        return Err(ContractError::InvalidMigration(version.contract));
+    }
+
+    match msg.admin {
+        Some(admin) => ADMIN.save(deps.storage, &deps.api.addr_validate(&admin)?)?,
+        None if !ADMIN.exists(deps.storage) => return Err(ContractError::MissingAdmin),
+        None => {}
+    }
+
+    let stored = migrate::LEGACY_DATABASE
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (stored_fmspc, legacy) in stored {
+        let (fmspc, tcb_info) = execute::parse_tcb_info(&legacy.info)?;
+        if fmspc != stored_fmspc {
+            return Err(ContractError::TcbInfoReadError);
         }
+        DATABASE.save(deps.storage, fmspc, &tcb_info)?;
+        HISTORY.save(
+            deps.storage,
+            (fmspc.as_slice(), tcb_info.issue_date.seconds()),
+            &tcb_info,
+        )?;
     }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    Ok(Response::default()
+        .add_attribute("action", "migrate")
+        .add_attribute("from_version", version.version))
+}
+
+pub mod migrate {
+    use cw_storage_plus::Map;
+    use serde::{Deserialize, Serialize};
+
+    use crate::state::Fmspc;
+
+    /// The TCB info as stored before the issue and next update dates were added. Unknown fields
+    /// are ignored, so this also reads TCB infos in the current layout.
+    #[derive(Serialize, Deserialize)]
+    pub struct LegacyTcbInfo {
+        pub info: String,
+    }
+
+    /// A view of `DATABASE` with the legacy TCB info layout
+    pub const LEGACY_DATABASE: Map<Fmspc, LegacyTcbInfo> = Map::new("state");
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
-        QueryMsg::GetTcbInfo { fmspc } => to_json_binary(&query::get_info(deps, fmspc)?),
+        QueryMsg::GetTcbInfo { fmspc } => to_json_binary(&query::get_info(deps, &env, fmspc)?),
+        QueryMsg::GetTcbInfoHistory {
+            fmspc,
+            start_before,
+            limit,
+        } => to_json_binary(&query::get_history(deps, &env, fmspc, start_before, limit)?),
     }
 }
 
 pub mod query {
+    use cw_storage_plus::Bound;
+
     use super::*;
 
-    pub fn get_info(deps: Deps, fmspc: String) -> StdResult<GetTcbInfoResponse> {
-        let key: [u8; 6] = hex::decode(fmspc)
-            .unwrap()
-            .try_into()
-            .expect("invalid fmspc");
-        let tcb_info = DATABASE.load(deps.storage, key)?;
-        Ok(GetTcbInfoResponse {
+    const DEFAULT_LIMIT: u32 = 10;
+    const MAX_LIMIT: u32 = 30;
+
+    fn parse_fmspc(fmspc: String) -> StdResult<Fmspc> {
+        hex::decode(fmspc)
+            .ok()
+            .and_then(|fmspc| fmspc.try_into().ok())
+            .ok_or_else(|| StdError::generic_err("invalid fmspc"))
+    }
+
+    fn to_response(env: &Env, tcb_info: TcbInfo) -> GetTcbInfoResponse {
+        GetTcbInfoResponse {
+            stale: env.block.time >= tcb_info.next_update,
             tcb_info: tcb_info.info,
-        })
+            issue_date: tcb_info.issue_date,
+            next_update: tcb_info.next_update,
+        }
+    }
+
+    pub fn get_info(deps: Deps, env: &Env, fmspc: String) -> StdResult<GetTcbInfoResponse> {
+        let tcb_info = DATABASE.load(deps.storage, parse_fmspc(fmspc)?)?;
+        Ok(to_response(env, tcb_info))
+    }
+
+    pub fn get_history(
+        deps: Deps,
+        env: &Env,
+        fmspc: String,
+        start_before: Option<Timestamp>,
+        limit: Option<u32>,
+    ) -> StdResult<GetTcbInfoHistoryResponse> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let max = start_before.map(|time| Bound::exclusive(time.seconds()));
+        let tcb_infos = HISTORY
+            .prefix(&parse_fmspc(fmspc)?)
+            .range(deps.storage, None, max, Order::Descending)
+            .take(limit)
+            .map(|item| item.map(|(_, tcb_info)| to_response(env, tcb_info)))
+            .collect::<StdResult<_>>()?;
+        Ok(GetTcbInfoHistoryResponse { tcb_infos })
     }
 }
 
@@ -155,24 +280,59 @@ pub mod query {
 mod tests {
     use cosmwasm_std::{
         coins,
-        testing::{message_info, mock_dependencies, mock_env},
+        testing::{message_info, mock_dependencies, mock_env, MockApi, MockQuerier, MockStorage},
+        OwnedDeps,
     };
+    use der::Encode;
+    use quartz_tee_ra::intel_sgx::dcap::synthetic::SyntheticDcap;
 
     use super::*;
     const TCB_SIGNER: &str = include_str!("../data/tcb_signer.pem");
     const ROOT_CA: &str = include_str!("../data/root_ca.pem");
     const TCB_INFO: &str = include_str!("../data/tcbinfo.json");
     const FMSPC: &str = "00606a000000";
-    // const TIME: &str = "2024-07-15T15:19:13Z";
+    const TIME: &str = "2024-07-11T15:19:13Z";
+
+    fn env_at(time: &str) -> Env {
+        let time = time.parse::<DateTime>().expect("valid time");
+        let mut env = mock_env();
+        env.block.time = Timestamp::from_seconds(time.unix_duration().as_secs());
+        env
+    }
+
+    fn exec_msg(dcap: &SyntheticDcap) -> ExecuteMsg {
+        ExecuteMsg {
+            tcb_info: dcap.tcb_info(),
+            certificate: dcap.tcb_signer_pem(),
+            root_crl: None,
+        }
+    }
+
+    /// Instantiates the contract with the synthetic root CA (and the returned sender as admin).
+    fn synthetic_setup(
+        dcap: &SyntheticDcap,
+    ) -> (OwnedDeps<MockStorage, MockApi, MockQuerier>, MessageInfo) {
+        let mut deps = mock_dependencies();
+        let creator = deps.api.addr_make("creator");
+
+        let info = message_info(&creator, &[]);
+        let init_msg = InstantiateMsg {
+            root_cert: dcap.root_ca_pem(),
+            admin: None,
+        };
+        assert!(instantiate(deps.as_mut(), mock_env(), info.clone(), init_msg).is_ok());
+        (deps, info)
+    }
+
     #[test]
     fn verify_init_and_exec() {
-        let time = "2024-07-11T15:19:13Z";
         let deps = mock_dependencies();
         let creator = deps.api.addr_make("creator");
 
         let info = message_info(&creator, &coins(1000, "earth"));
         let init_msg = InstantiateMsg {
             root_cert: ROOT_CA.to_string(),
+            admin: None,
         };
         let mut deps = mock_dependencies();
         let res = instantiate(deps.as_mut(), mock_env(), info, init_msg);
@@ -181,14 +341,14 @@ mod tests {
         let exec_msg = ExecuteMsg {
             tcb_info: TCB_INFO.to_string(),
             certificate: TCB_SIGNER.to_string(),
-            time: Some(time.to_string()),
+            root_crl: None,
         };
         let info = message_info(&creator, &coins(1000, "earth"));
-        let exec = execute(deps.as_mut(), mock_env(), info, exec_msg);
+        let exec = execute(deps.as_mut(), env_at(TIME), info, exec_msg);
         assert!(exec.is_ok());
         let query = query(
             deps.as_ref(),
-            mock_env(),
+            env_at(TIME),
             QueryMsg::GetTcbInfo {
                 fmspc: FMSPC.to_string(),
             },
@@ -196,4 +356,243 @@ mod tests {
         assert!(query.is_ok());
         println!("{:?}", query.unwrap());
     }
+
+    #[test]
+    fn verify_synthetic_tcb_info() {
+        let dcap = SyntheticDcap::new();
+        let (mut deps, info) = synthetic_setup(&dcap);
+
+        assert!(execute(deps.as_mut(), env_at(TIME), info, exec_msg(&dcap)).is_ok());
+
+        let res = query::get_info(deps.as_ref(), &env_at(TIME), hex::encode(dcap.fmspc()))
+            .expect("synthetic TCB info must be stored");
+        assert_eq!(res.tcb_info, dcap.tcb_info());
+        assert!(!res.stale);
+    }
+
+    #[test]
+    fn revoked_tcb_signer_is_rejected() {
+        let dcap = SyntheticDcap::new();
+        let (mut deps, info) = synthetic_setup(&dcap);
+
+        let exec_msg = |crl: &CertificateList| ExecuteMsg {
+            root_crl: Some(hex::encode(crl.to_der().expect("valid CRL"))),
+            ..exec_msg(&dcap)
+        };
+
+        let crl = dcap.root_ca_crl();
+        assert!(execute(deps.as_mut(), env_at(TIME), info.clone(), exec_msg(&crl)).is_ok());
+
+        let crl = dcap.clone().with_revoked_tcb_signer().root_ca_crl();
+        assert!(matches!(
+            execute(deps.as_mut(), env_at(TIME), info.clone(), exec_msg(&crl)),
+            Err(ContractError::CertificateVerificationError)
+        ));
+
+        let exec_msg = ExecuteMsg {
+            root_crl: Some("not a CRL".to_string()),
+            ..exec_msg(&crl)
+        };
+        assert!(matches!(
+            execute(deps.as_mut(), env_at(TIME), info, exec_msg),
+            Err(ContractError::CrlReadError)
+        ));
+    }
+
+    #[test]
+    fn only_admin_can_submit() {
+        let dcap = SyntheticDcap::new();
+        let (mut deps, _) = synthetic_setup(&dcap);
+
+        let other = message_info(&deps.api.addr_make("other"), &[]);
+        assert!(matches!(
+            execute(deps.as_mut(), env_at(TIME), other, exec_msg(&dcap)),
+            Err(ContractError::Unauthorized {})
+        ));
+
+        // the admin can be set explicitly
+        let mut deps = mock_dependencies();
+        let creator = message_info(&deps.api.addr_make("creator"), &[]);
+        let admin = message_info(&deps.api.addr_make("admin"), &[]);
+        let init_msg = InstantiateMsg {
+            root_cert: dcap.root_ca_pem(),
+            admin: Some(admin.sender.to_string()),
+        };
+        assert!(instantiate(deps.as_mut(), mock_env(), creator.clone(), init_msg).is_ok());
+        assert!(matches!(
+            execute(deps.as_mut(), env_at(TIME), creator, exec_msg(&dcap)),
+            Err(ContractError::Unauthorized {})
+        ));
+        assert!(execute(deps.as_mut(), env_at(TIME), admin, exec_msg(&dcap)).is_ok());
+    }
+
+    #[test]
+    fn tcb_info_must_be_valid_at_block_time() {
+        let dcap = SyntheticDcap::new().with_tcb_info_dates(TIME, "2024-08-10T15:19:13Z");
+        let (mut deps, info) = synthetic_setup(&dcap);
+
+        for time in ["2024-07-11T15:19:12Z", "2024-08-10T15:19:13Z"] {
+            assert!(matches!(
+                execute(deps.as_mut(), env_at(time), info.clone(), exec_msg(&dcap)),
+                Err(ContractError::TcbInfoExpired)
+            ));
+        }
+        assert!(execute(deps.as_mut(), env_at(TIME), info, exec_msg(&dcap)).is_ok());
+
+        let fmspc = hex::encode(dcap.fmspc());
+        let res = query::get_info(
+            deps.as_ref(),
+            &env_at("2024-08-10T15:19:12Z"),
+            fmspc.clone(),
+        )
+        .expect("TCB info must be stored");
+        assert!(!res.stale);
+        let res = query::get_info(deps.as_ref(), &env_at("2024-08-10T15:19:13Z"), fmspc)
+            .expect("TCB info must be stored");
+        assert!(res.stale);
+        assert_eq!(res.issue_date, env_at(TIME).block.time);
+        assert_eq!(res.next_update, env_at("2024-08-10T15:19:13Z").block.time);
+    }
+
+    #[test]
+    fn older_tcb_info_is_rejected_and_history_is_kept() {
+        let dcap = SyntheticDcap::new();
+        let (mut deps, info) = synthetic_setup(&dcap);
+        let issued = |issue_date: &str| {
+            dcap.clone()
+                .with_tcb_info_dates(issue_date, "2049-12-31T23:59:59Z")
+        };
+        let (older, old, new) = (
+            issued("2024-01-01T00:00:00Z"),
+            issued("2024-02-01T00:00:00Z"),
+            issued("2024-03-01T00:00:00Z"),
+        );
+
+        let env = env_at(TIME);
+        assert!(execute(deps.as_mut(), env.clone(), info.clone(), exec_msg(&old)).is_ok());
+        for outdated in [&older, &old] {
+            assert!(matches!(
+                execute(deps.as_mut(), env.clone(), info.clone(), exec_msg(outdated)),
+                Err(ContractError::OutdatedTcbInfo)
+            ));
+        }
+        assert!(execute(deps.as_mut(), env.clone(), info, exec_msg(&new)).is_ok());
+
+        let fmspc = hex::encode(dcap.fmspc());
+        let res = query::get_info(deps.as_ref(), &env, fmspc.clone()).expect("stored TCB info");
+        assert_eq!(res.tcb_info, new.tcb_info());
+
+        let history = |start_before, limit| {
+            query::get_history(deps.as_ref(), &env, fmspc.clone(), start_before, limit)
+                .expect("stored TCB infos")
+                .tcb_infos
+                .into_iter()
+                .map(|res| res.tcb_info)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(history(None, None), [new.tcb_info(), old.tcb_info()]);
+        assert_eq!(history(None, Some(1)), [new.tcb_info()]);
+        let start_before = query::get_info(deps.as_ref(), &env, fmspc.clone())
+            .expect("stored TCB info")
+            .issue_date;
+        assert_eq!(history(Some(start_before), None), [old.tcb_info()]);
+    }
+
+    #[test]
+    fn invalid_input_is_rejected() {
+        let dcap = SyntheticDcap::new();
+        let (mut deps, info) = synthetic_setup(&dcap);
+
+        let init_msg = InstantiateMsg {
+            root_cert: dcap.root_ca_pem(),
+            admin: None,
+        };
+        assert!(matches!(
+            instantiate(deps.as_mut(), mock_env(), info.clone(), init_msg),
+            Err(ContractError::AlreadyInstantiated)
+        ));
+
+        let mut fresh = mock_dependencies();
+        let init_msg = InstantiateMsg {
+            root_cert: "not a PEM".to_string(),
+            admin: None,
+        };
+        assert!(matches!(
+            instantiate(fresh.as_mut(), mock_env(), info.clone(), init_msg),
+            Err(ContractError::CertificateReadError)
+        ));
+
+        let exec = |deps: DepsMut, msg| execute(deps, env_at(TIME), info.clone(), msg);
+        let invalid_tcb_info = ExecuteMsg {
+            tcb_info: "{}".to_string(),
+            ..exec_msg(&dcap)
+        };
+        assert!(matches!(
+            exec(deps.as_mut(), invalid_tcb_info),
+            Err(ContractError::TcbInfoReadError)
+        ));
+        let invalid_certificate = ExecuteMsg {
+            certificate: "not a PEM".to_string(),
+            ..exec_msg(&dcap)
+        };
+        assert!(matches!(
+            exec(deps.as_mut(), invalid_certificate),
+            Err(ContractError::CertificateReadError)
+        ));
+    }
+
+    #[test]
+    fn legacy_tcb_infos_are_migrated() {
+        let dcap = SyntheticDcap::new();
+        let mut deps = mock_dependencies();
+        let admin = message_info(&deps.api.addr_make("admin"), &[]);
+
+        // the layout of a contract that stored neither the admin nor the TCB info dates
+        set_contract_version(&mut deps.storage, CONTRACT_NAME, "0.1.0").expect("version stored");
+        ROOT_CERTIFICATE
+            .save(&mut deps.storage, &dcap.root_ca_pem())
+            .expect("root stored");
+        let legacy = migrate::LegacyTcbInfo {
+            info: dcap.tcb_info(),
+        };
+        migrate::LEGACY_DATABASE
+            .save(&mut deps.storage, dcap.fmspc(), &legacy)
+            .expect("TCB info stored");
+
+        let no_admin = MigrateMsg { admin: None };
+        assert!(matches!(
+            migrate(deps.as_mut(), mock_env(), no_admin),
+            Err(ContractError::MissingAdmin)
+        ));
+        let migrate_msg = MigrateMsg {
+            admin: Some(admin.sender.to_string()),
+        };
+        assert!(migrate(deps.as_mut(), mock_env(), migrate_msg).is_ok());
+
+        let fmspc = hex::encode(dcap.fmspc());
+        let env = env_at(TIME);
+        let res = query::get_info(deps.as_ref(), &env, fmspc.clone()).expect("stored TCB info");
+        assert_eq!(res.tcb_info, dcap.tcb_info());
+        let history =
+            query::get_history(deps.as_ref(), &env, fmspc, None, None).expect("stored TCB infos");
+        assert_eq!(history.tcb_infos, [res]);
+        assert_eq!(
+            get_contract_version(&deps.storage)
+                .expect("version stored")
+                .version,
+            CONTRACT_VERSION
+        );
+
+        // only the migrated admin may submit, and migrating again keeps it
+        assert!(migrate(deps.as_mut(), mock_env(), MigrateMsg { admin: None }).is_ok());
+        let other = message_info(&deps.api.addr_make("other"), &[]);
+        assert!(matches!(
+            execute(deps.as_mut(), env.clone(), other, exec_msg(&dcap)),
+            Err(ContractError::Unauthorized {})
+        ));
+        assert!(matches!(
+            execute(deps.as_mut(), env, admin, exec_msg(&dcap)),
+            Err(ContractError::OutdatedTcbInfo)
+        ));
+    }
 }
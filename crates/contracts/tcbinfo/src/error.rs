@@ -20,4 +20,18 @@ pub enum ContractError {
     DateTimeReadError,
     #[error("invalid tcbinfo")]
     TcbInfoReadError,
+    #[error("invalid CRL")]
+    CrlReadError,
+    #[error("tcbinfo is not valid at the block time")]
+    TcbInfoExpired,
+    #[error("tcbinfo is not newer than the stored one")]
+    OutdatedTcbInfo,
+    #[error("invalid certificate")]
+    CertificateReadError,
+    #[error("contract is already instantiated")]
+    AlreadyInstantiated,
+    #[error("cannot migrate from {0}")]
+    InvalidMigration(String),
+    #[error("an admin must be set for the migration")]
+    MissingAdmin,
 }
@@ -50,6 +50,7 @@ mod tests {
 
         let msg = InstantiateMsg {
             root_cert: ROOT_CA.to_string(),
+            admin: None,
         };
         let cw_template_contract_addr = app
             .instantiate_contract(
@@ -68,6 +69,8 @@ mod tests {
     }
 
     mod add_tcbinfo {
+        use cosmwasm_std::Timestamp;
+        use der::DateTime;
         use quartz_tcbinfo_msgs::ExecuteMsg;
 
         use super::*;
@@ -79,10 +82,14 @@ mod tests {
             let msg = ExecuteMsg {
                 tcb_info: TCB_INFO.to_string(),
                 certificate: TCB_SIGNER.to_string(),
-                time: Some(TIME.to_string()),
+                root_crl: None,
             };
             let cosmos_msg = cw_template_contract.call(msg).unwrap();
-            app.execute(Addr::unchecked(USER), cosmos_msg).unwrap();
+            app.update_block(|block| {
+                let time = TIME.parse::<DateTime>().unwrap();
+                block.time = Timestamp::from_seconds(time.unix_duration().as_secs());
+            });
+            app.execute(Addr::unchecked(ADMIN), cosmos_msg).unwrap();
         }
     }
 }
@@ -1,4 +1,5 @@
 use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Timestamp};
 use cw_storage_plus::{Item, Map};
 
 pub type Fmspc = [u8; 6];
@@ -6,8 +7,13 @@ pub type Fmspc = [u8; 6];
 #[cw_serde]
 pub struct TcbInfo {
     pub info: String,
-    //  pub certificate: String,
+    pub issue_date: Timestamp,
+    pub next_update: Timestamp,
 }
 
+/// The latest TCB info per FMSPC
 pub const DATABASE: Map<Fmspc, TcbInfo> = Map::new("state");
+/// All stored TCB infos per FMSPC, by issue date (in seconds)
+pub const HISTORY: Map<(&[u8], u64), TcbInfo> = Map::new("history");
 pub const ROOT_CERTIFICATE: Item<String> = Item::new("root_certificate");
+pub const ADMIN: Item<Addr> = Item::new("admin");
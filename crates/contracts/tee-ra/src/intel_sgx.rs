@@ -10,4 +10,10 @@ pub enum Error {
     MrEnclaveMismatch,
     #[error("DCAP specific error: {0:?}")]
     Dcap(dcap::VerificationOutput<dcap::DcapVerifierOutput>),
+    #[error("Invalid DCAP evidence: {0:?}")]
+    DcapEvidence(mc_attestation_verifier::Error),
+    #[error("DCAP verification failed: {0:?}")]
+    DcapPolicy(dcap::DcapPolicyVerifierOutput),
+    #[error("Failed to extract the FMSPC from the PCK certificate: {0}")]
+    Fmspc(mc_sgx_dcap_types::TcbError),
 }
@@ -1,25 +1,77 @@
 pub mod certificate_chain;
 pub mod mc_attest_verifier;
+pub mod policy;
+#[cfg(any(test, feature = "synthetic"))]
+pub mod synthetic;
 
+use der::DateTime;
 use mc_attestation_verifier::Evidence;
 pub use mc_attestation_verifier::{
     TrustedIdentity, TrustedMrEnclaveIdentity, TrustedMrSignerIdentity, VerificationOutput,
 };
-pub use mc_sgx_dcap_types::{Collateral, Quote3, Quote3Error};
+use mc_sgx_dcap_types::TcbInfo as PckTcbInfo;
+pub use mc_sgx_dcap_types::{Collateral, Quote3, Quote3Error, FMSPC_SIZE};
+use x509_cert::Certificate;
 
-use self::mc_attest_verifier::dcap::DcapVerifier;
-pub use self::mc_attest_verifier::dcap::DcapVerifierOutput;
+use self::{
+    certificate_chain::TlsCertificateChainVerifier, mc_attest_verifier::dcap::DcapVerifier,
+};
+pub use self::{
+    mc_attest_verifier::dcap::DcapVerifierOutput,
+    policy::{
+        AttestationPolicy, DcapEvidenceSummary, DcapPolicyVerifier, DcapPolicyVerifierOutput,
+        TcbStatus,
+    },
+};
+use crate::intel_sgx::Error;
 
+/// Verifies the attestation against the certificate chains rooted in `root_ca`.
 pub fn verify(
     quote: Quote3<Vec<u8>>,
     collateral: Collateral,
     identities: &[TrustedIdentity],
+    root_ca: Certificate,
 ) -> VerificationOutput<DcapVerifierOutput> {
     let evidence = Evidence::new(quote, collateral).expect("Failed to get evidence");
-    let verifier = DcapVerifier::new(identities, None);
+    let certificate_verifier = TlsCertificateChainVerifier::new(root_ca);
+    let verifier = DcapVerifier::new(certificate_verifier, identities, None);
     verifier.verify(&evidence)
 }
 
+/// Verifies the attestation like [`verify`], but checks the TCB status and advisories of the
+/// platform against `policy` instead of the advisories of the `identities`. If `time` is
+/// specified, all certificates must be valid at that time.
+pub fn verify_with_policy(
+    quote: Quote3<Vec<u8>>,
+    collateral: Collateral,
+    identities: &[TrustedIdentity],
+    policy: AttestationPolicy,
+    root_ca: Certificate,
+    time: Option<DateTime>,
+) -> Result<(), Error> {
+    let certificate_verifier = TlsCertificateChainVerifier::new(root_ca);
+    let verifier = DcapPolicyVerifier::new(certificate_verifier, identities, policy, time);
+    let output = verifier
+        .verify(quote, collateral)
+        .map_err(Error::DcapEvidence)?;
+
+    if output.is_success() {
+        Ok(())
+    } else {
+        Err(Error::DcapPolicy(output))
+    }
+}
+
+/// Extracts the FMSPC of the platform that generated the quote from the SGX extension of the PCK
+/// certificate in the quote's certification data.
+///
+/// The certificate is only parsed here, its chain is checked when the quote is verified.
+pub fn fmspc(quote: &Quote3<Vec<u8>>) -> Result<[u8; FMSPC_SIZE], Error> {
+    PckTcbInfo::try_from(quote)
+        .map(|tcb_info| *tcb_info.fmspc())
+        .map_err(Error::Fmspc)
+}
+
 #[cfg(test)]
 mod tests {
     use hex::FromHex;
@@ -29,7 +81,9 @@ mod tests {
     use mc_sgx_dcap_sys_types::sgx_ql_qve_collateral_t;
     use mc_sgx_dcap_types::{Collateral, Quote3};
 
-    use crate::intel_sgx::dcap::certificate_chain::TlsCertificateChainVerifier;
+    use crate::intel_sgx::dcap::{
+        certificate_chain::TlsCertificateChainVerifier, synthetic::SyntheticDcap,
+    };
 
     const TCB_INFO_JSON: &str = include_str!("../../data/fmspc_00906ED50000_2023_07_12.json");
     const QE_IDENTITY_JSON: &str = include_str!("../../data/qe_identity.json");
@@ -81,8 +135,7 @@ mod tests {
 
     #[test]
     fn evidence_verifier_succeeds_with_tls_x509_verifier() {
-        let root_ca = include_str!("../../data/root_ca.pem");
-        let certificate_verifier = TlsCertificateChainVerifier::new(root_ca);
+        let certificate_verifier = TlsCertificateChainVerifier::intel();
         let identities = [TrustedMrEnclaveIdentity::new(
             MrEnclave::from_hex("840d61b0585dc8b4dc90f53af293c760fda06bee75978a6a86263ffb296423f4")
                 .expect("malformed MRENCLAVE hex"),
@@ -105,6 +158,23 @@ mod tests {
         // println!("\n{displayable}");
     }
 
+    #[test]
+    fn test_fmspc_from_pck_certificate() {
+        let quote_bytes = include_bytes!("../../data/hw_quote.dat");
+        let quote = Quote3::try_from(quote_bytes.to_vec()).expect("Failed to parse quote");
+        assert_eq!(
+            super::fmspc(&quote).expect("Failed to extract FMSPC"),
+            hex!("00906ED50000")
+        );
+
+        let dcap = SyntheticDcap::new().with_fmspc(hex!("00a06f000000"));
+        let quote = dcap.quote([1; 32], [2; 64]);
+        assert_eq!(
+            super::fmspc(&quote).expect("Failed to extract FMSPC"),
+            dcap.fmspc()
+        );
+    }
+
     #[test]
     fn test_quote_parse() {
         let quote_bytes = hex!(
@@ -1,15 +1,42 @@
-use der::{DateTime, Encode};
+use der::{DateTime, DecodePem, Encode};
 use mc_attestation_verifier::{CertificateChainVerifier, CertificateChainVerifierError};
 use x509_cert::{crl::CertificateList, Certificate};
-use x509_parser::parse_x509_certificate;
+use x509_parser::{
+    certificate::X509Certificate, parse_x509_certificate,
+    revocation_list::CertificateRevocationList, time::ASN1Time,
+};
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, Eq, PartialEq)]
-pub struct TlsCertificateChainVerifier;
+/// Intel's SGX root CA certificate.
+pub const INTEL_SGX_ROOT_CA_PEM: &str = include_str!("../../../data/root_ca.pem");
+
+/// A certificate chain verifier that only trusts chains that end in its pinned root CA.
+///
+/// Every certificate in the chain (leaf first, the root may be omitted) must be signed by the next
+/// one and, if a time is specified, must be valid at that time. Certificates are checked against
+/// the CRLs that were issued (and signed) by their issuer, which must be current at that time too.
+/// Every intermediate CA in the chain must have a CRL, the root CA's CRL is optional. CRLs of
+/// other issuers are ignored.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TlsCertificateChainVerifier {
+    root_ca: Certificate,
+}
 
 impl TlsCertificateChainVerifier {
-    pub fn new(_root_ca: &str) -> Self {
-        // FIXME(hu55a1n1)
-        Self
+    pub fn new(root_ca: Certificate) -> Self {
+        Self { root_ca }
+    }
+
+    pub fn from_pem(root_ca: &str) -> Result<Self, der::Error> {
+        Certificate::from_pem(root_ca).map(Self::new)
+    }
+
+    /// Creates a verifier that trusts Intel's SGX root CA.
+    pub fn intel() -> Self {
+        Self::from_pem(INTEL_SGX_ROOT_CA_PEM).expect("embedded Intel SGX root CA must be valid")
+    }
+
+    pub fn root_ca(&self) -> &Certificate {
+        &self.root_ca
     }
 }
 
@@ -17,51 +44,127 @@ impl CertificateChainVerifier for TlsCertificateChainVerifier {
     fn verify_certificate_chain<'a, 'b>(
         &self,
         certificate_chain: impl IntoIterator<Item = &'a Certificate>,
-        _crls: impl IntoIterator<Item = &'b CertificateList>,
-        _time: impl Into<Option<DateTime>>,
+        crls: impl IntoIterator<Item = &'b CertificateList>,
+        time: impl Into<Option<DateTime>>,
     ) -> Result<(), CertificateChainVerifierError> {
-        let enc_certs = certificate_chain
+        let mut enc_certs = certificate_chain
             .into_iter()
             .map(|cert| cert.to_der())
             .collect::<Result<Vec<_>, _>>()
             .map_err(|_| CertificateChainVerifierError::GeneralCertificateError)?;
+        let enc_root = self
+            .root_ca
+            .to_der()
+            .map_err(|_| CertificateChainVerifierError::GeneralCertificateError)?;
+        if enc_certs.is_empty() {
+            return Err(CertificateChainVerifierError::GeneralCertificateError);
+        }
+        if enc_certs.last() != Some(&enc_root) {
+            enc_certs.push(enc_root);
+        }
 
         let cert_chain = enc_certs
             .iter()
-            .map(|der| parse_x509_certificate(der))
+            .map(|der| parse_x509_certificate(der).map(|(_, cert)| cert))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| CertificateChainVerifierError::GeneralCertificateError)?;
+
+        let enc_crls = crls
+            .into_iter()
+            .map(|crl| crl.to_der())
             .collect::<Result<Vec<_>, _>>()
             .map_err(|_| CertificateChainVerifierError::GeneralCertificateError)?;
-        // Skip applying the Certificate Revocation List entirely
-        /*
-           let enc_crls = crls
-               .into_iter()
-               .map(|crl| der_encode(crl))
-               .collect::<Result<Vec<_>, _>>()
-               .map_err(|_| CertificateChainVerifierError::GeneralCertificateError)?;
-           let _crls = enc_crls
-               .iter()
-               .map(|enc_crl| parse_x509_crl(enc_crl.as_ref()))
-               .collect::<Result<Vec<_>, _>>();
-           .map_err(|_| CertificateChainVerifierError::GeneralCertificateError)?;
-        */
-
-        let v: Vec<_> = cert_chain.to_vec();
-        let mut issuers: Vec<usize> = (1..v.len()).collect();
-        issuers.push(v.len() - 1);
-        let subjects: Vec<usize> = (0..v.len()).collect();
-        for (i, s) in core::iter::zip(issuers, subjects) {
-            let r = v[s].1.verify_signature(Some(v[i].1.public_key()));
-            r.map_err(|_| CertificateChainVerifierError::SignatureVerification)?
+        let crls = enc_crls
+            .iter()
+            .map(|der| {
+                x509_parser::parse_x509_crl(der)
+                    .map(|(_, crl)| crl)
+                    .map_err(|_| CertificateChainVerifierError::GeneralCertificateError)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let time = time
+            .into()
+            .map(|time| {
+                let secs = i64::try_from(time.unix_duration().as_secs())
+                    .map_err(|_| CertificateChainVerifierError::GeneralCertificateError)?;
+                ASN1Time::from_timestamp(secs)
+                    .map_err(|_| CertificateChainVerifierError::GeneralCertificateError)
+            })
+            .transpose()?;
+
+        // the (pinned) root is its own issuer
+        let root_idx = cert_chain.len() - 1;
+        let issuers = cert_chain.iter().skip(1).chain(cert_chain.last());
+        for (idx, (cert, issuer)) in cert_chain.iter().zip(issuers).enumerate() {
+            cert.verify_signature(Some(issuer.public_key()))
+                .map_err(|_| CertificateChainVerifierError::SignatureVerification)?;
+
+            if let Some(time) = time {
+                let validity = cert.validity();
+                if time < validity.not_before {
+                    return Err(CertificateChainVerifierError::CertificateNotYetValid);
+                }
+                if time > validity.not_after {
+                    return Err(CertificateChainVerifierError::CertificateExpired);
+                }
+            }
+
+            let is_issued_by_root = idx + 1 >= root_idx;
+            check_revocation(cert, issuer, !is_issued_by_root, &crls, time)?;
         }
+
         Ok(())
     }
 }
 
+/// Checks that `cert` isn't listed in any of the `crls` issued by its `issuer`, and that those
+/// CRLs are current at `time` (if specified). Fails if `crl_required` but there's no such CRL.
+fn check_revocation(
+    cert: &X509Certificate<'_>,
+    issuer: &X509Certificate<'_>,
+    crl_required: bool,
+    crls: &[CertificateRevocationList<'_>],
+    time: Option<ASN1Time>,
+) -> Result<(), CertificateChainVerifierError> {
+    let mut issuer_crls = crls
+        .iter()
+        .filter(|crl| crl.issuer().as_raw() == issuer.subject().as_raw())
+        .peekable();
+    if crl_required && issuer_crls.peek().is_none() {
+        return Err(CertificateChainVerifierError::GeneralCertificateError);
+    }
+
+    for crl in issuer_crls {
+        crl.verify_signature(issuer.public_key())
+            .map_err(|_| CertificateChainVerifierError::SignatureVerification)?;
+
+        if let Some(time) = time {
+            let is_stale = time < crl.last_update()
+                || crl
+                    .next_update()
+                    .is_some_and(|next_update| time > next_update);
+            if is_stale {
+                return Err(CertificateChainVerifierError::GeneralCertificateError);
+            }
+        }
+
+        if crl
+            .iter_revoked_certificates()
+            .any(|revoked| revoked.raw_serial() == cert.raw_serial())
+        {
+            return Err(CertificateChainVerifierError::CertificateRevoked);
+        }
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod test {
     use der::{Decode, DecodePem};
 
     use super::*;
+    use crate::intel_sgx::dcap::synthetic::SyntheticDcap;
 
     const LEAF_CERT: &str = include_str!("../../../data/leaf_cert.pem");
     const PROCESSOR_CA: &str = include_str!("../../../data/processor_ca.pem");
@@ -69,35 +172,201 @@ mod test {
     const PROCESSOR_CRL: &[u8] = include_bytes!("../../../data/processor_crl.der");
     const ROOT_CRL: &[u8] = include_bytes!("../../../data/root_crl.der");
 
-    #[test]
-    fn verify_valid_cert_chain() {
-        let chain = [LEAF_CERT, PROCESSOR_CA, ROOT_CA]
-            .iter()
+    fn certs(pems: &[&str]) -> Vec<Certificate> {
+        pems.iter()
             .map(|cert| Certificate::from_pem(cert).expect("failed to parse cert"))
-            .collect::<Vec<_>>();
-        let crls = [ROOT_CRL, PROCESSOR_CRL]
+            .collect()
+    }
+
+    fn crls() -> Vec<CertificateList> {
+        [ROOT_CRL, PROCESSOR_CRL]
             .iter()
             .map(|crl| CertificateList::from_der(crl).expect("failed to parse CRL"))
-            .collect::<Vec<_>>();
-        let verifier = TlsCertificateChainVerifier::new(ROOT_CA);
+            .collect()
+    }
+
+    fn time(time: &str) -> DateTime {
+        time.parse().expect("valid time")
+    }
+
+    #[test]
+    fn verify_valid_cert_chain() {
+        let chain = certs(&[LEAF_CERT, PROCESSOR_CA, ROOT_CA]);
+        let verifier = TlsCertificateChainVerifier::intel();
         assert!(verifier
-            .verify_certificate_chain(chain.iter(), crls.iter(), None)
+            .verify_certificate_chain(chain.iter(), crls().iter(), None)
+            .is_ok());
+
+        // the pinned root may be omitted
+        assert!(verifier
+            .verify_certificate_chain(chain[..2].iter(), crls().iter(), None)
             .is_ok());
     }
 
     #[test]
     fn invalid_cert_chain() {
-        let chain = [LEAF_CERT, ROOT_CA]
-            .iter()
-            .map(|cert| Certificate::from_pem(cert).expect("failed to parse cert"))
-            .collect::<Vec<_>>();
-        let crls = [ROOT_CRL, PROCESSOR_CRL]
-            .iter()
-            .map(|crl| CertificateList::from_der(crl).expect("failed to parse CRL"))
-            .collect::<Vec<_>>();
-        let verifier = TlsCertificateChainVerifier::new(ROOT_CA);
+        let chain = certs(&[LEAF_CERT, ROOT_CA]);
+        let verifier = TlsCertificateChainVerifier::intel();
+        assert_eq!(
+            verifier.verify_certificate_chain(chain.iter(), crls().iter(), None),
+            Err(CertificateChainVerifierError::SignatureVerification)
+        );
+    }
+
+    #[test]
+    fn cert_chain_must_end_in_pinned_root() {
+        let chain = certs(&[LEAF_CERT, PROCESSOR_CA, ROOT_CA]);
+        let verifier = TlsCertificateChainVerifier::new(SyntheticDcap::new().root_ca());
         assert_eq!(
-            verifier.verify_certificate_chain(chain.iter(), crls.iter(), None),
+            verifier.verify_certificate_chain(chain.iter(), crls().iter(), None),
+            Err(CertificateChainVerifierError::SignatureVerification)
+        );
+    }
+
+    #[test]
+    fn cert_chain_must_be_valid_at_time() {
+        let chain = certs(&[LEAF_CERT, PROCESSOR_CA, ROOT_CA]);
+        let verifier = TlsCertificateChainVerifier::intel();
+
+        // i.e. while the processor CRL is current
+        assert!(verifier
+            .verify_certificate_chain(chain.iter(), crls().iter(), time("2023-05-01T00:00:00Z"))
+            .is_ok());
+        assert_eq!(
+            verifier.verify_certificate_chain(
+                chain.iter(),
+                crls().iter(),
+                time("2000-01-01T00:00:00Z")
+            ),
+            Err(CertificateChainVerifierError::CertificateNotYetValid)
+        );
+        assert_eq!(
+            verifier.verify_certificate_chain(
+                chain.iter(),
+                crls().iter(),
+                time("2099-01-01T00:00:00Z")
+            ),
+            Err(CertificateChainVerifierError::CertificateExpired)
+        );
+
+        // certificates are valid from `notBefore` to `notAfter` (inclusive)
+        let dcap =
+            SyntheticDcap::new().with_crl_dates("2020-01-01T00:00:00Z", "2049-12-31T23:59:59Z");
+        let verifier = TlsCertificateChainVerifier::new(dcap.root_ca());
+        let chain = [dcap.pck_cert(), dcap.pck_ca(), dcap.root_ca()];
+        let crls = [dcap.root_ca_crl(), dcap.pck_crl()];
+        let validity = &chain[0].tbs_certificate.validity;
+        for time in [validity.not_before, validity.not_after] {
+            assert!(verifier
+                .verify_certificate_chain(chain.iter(), crls.iter(), time.to_date_time())
+                .is_ok());
+        }
+    }
+
+    #[test]
+    fn crls_are_required_for_intermediate_cas() {
+        let dcap = SyntheticDcap::new();
+        let verifier = TlsCertificateChainVerifier::new(dcap.root_ca());
+        let pck_chain = [dcap.pck_cert(), dcap.pck_ca(), dcap.root_ca()];
+
+        // the PCK certificate's revocation status is unknown without the PCK CRL
+        assert_eq!(
+            verifier.verify_certificate_chain(pck_chain.iter(), [dcap.root_ca_crl()].iter(), None),
+            Err(CertificateChainVerifierError::GeneralCertificateError)
+        );
+        // a CRL of another issuer doesn't count
+        let other_issuer = SyntheticDcap::from_seed(b"other").root_ca_crl();
+        assert_eq!(
+            verifier.verify_certificate_chain(pck_chain.iter(), [other_issuer].iter(), None),
+            Err(CertificateChainVerifierError::GeneralCertificateError)
+        );
+        assert!(verifier
+            .verify_certificate_chain(pck_chain.iter(), [dcap.pck_crl()].iter(), None)
+            .is_ok());
+
+        // certificates issued by the root CA don't require the root CA's CRL
+        let tcb_chain = [dcap.tcb_signer(), dcap.root_ca()];
+        assert!(verifier
+            .verify_certificate_chain(tcb_chain.iter(), [].iter(), None)
+            .is_ok());
+    }
+
+    #[test]
+    fn stale_crls_are_rejected() {
+        let dcap =
+            SyntheticDcap::new().with_crl_dates("2024-01-01T00:00:00Z", "2025-01-01T00:00:00Z");
+        let verifier = TlsCertificateChainVerifier::new(dcap.root_ca());
+        let chain = [dcap.pck_cert(), dcap.pck_ca(), dcap.root_ca()];
+        let crls = [dcap.root_ca_crl(), dcap.pck_crl()];
+        let verify = |at| verifier.verify_certificate_chain(chain.iter(), crls.iter(), time(at));
+
+        assert!(verify("2024-01-01T00:00:00Z").is_ok());
+        assert!(verify("2025-01-01T00:00:00Z").is_ok());
+        // the CRLs weren't issued yet, or should have been superseded
+        assert_eq!(
+            verify("2023-12-31T23:59:59Z"),
+            Err(CertificateChainVerifierError::GeneralCertificateError)
+        );
+        assert_eq!(
+            verify("2025-01-01T00:00:01Z"),
+            Err(CertificateChainVerifierError::GeneralCertificateError)
+        );
+
+        // without a time, the CRLs' dates can't be checked
+        assert!(verifier
+            .verify_certificate_chain(chain.iter(), crls.iter(), None)
+            .is_ok());
+    }
+
+    #[test]
+    fn revoked_certs_are_rejected() {
+        let dcap = SyntheticDcap::new();
+        let verifier = TlsCertificateChainVerifier::new(dcap.root_ca());
+        let pck_chain = [dcap.pck_cert(), dcap.pck_ca(), dcap.root_ca()];
+        let tcb_chain = [dcap.tcb_signer(), dcap.root_ca()];
+        let verify = |dcap: &SyntheticDcap, chain: &[Certificate]| {
+            let crls = [dcap.root_ca_crl(), dcap.pck_crl()];
+            verifier.verify_certificate_chain(chain, crls.iter(), None)
+        };
+
+        assert!(verify(&dcap, &pck_chain).is_ok());
+        assert!(verify(&dcap, &tcb_chain).is_ok());
+
+        let revoked = dcap.clone().with_revoked_pck_cert();
+        assert_eq!(
+            verify(&revoked, &pck_chain),
+            Err(CertificateChainVerifierError::CertificateRevoked)
+        );
+        assert!(verify(&revoked, &tcb_chain).is_ok());
+
+        let revoked = dcap.clone().with_revoked_pck_ca();
+        assert_eq!(
+            verify(&revoked, &pck_chain),
+            Err(CertificateChainVerifierError::CertificateRevoked)
+        );
+
+        let revoked = dcap.clone().with_revoked_tcb_signer();
+        assert!(verify(&revoked, &pck_chain).is_ok());
+        assert_eq!(
+            verify(&revoked, &tcb_chain),
+            Err(CertificateChainVerifierError::CertificateRevoked)
+        );
+    }
+
+    #[test]
+    fn crls_must_be_signed_by_issuer() {
+        let dcap = SyntheticDcap::new();
+        let verifier = TlsCertificateChainVerifier::new(dcap.root_ca());
+        let chain = [dcap.pck_cert(), dcap.pck_ca(), dcap.root_ca()];
+
+        // a PCK CRL for the same issuer name, but signed by another PKI's PCK CA
+        let forged = SyntheticDcap::from_seed(b"other").pck_crl();
+        assert_eq!(
+            verifier.verify_certificate_chain(
+                chain.iter(),
+                [dcap.root_ca_crl(), forged].iter(),
+                None
+            ),
             Err(CertificateChainVerifierError::SignatureVerification)
         );
     }
@@ -105,17 +374,10 @@ mod test {
     #[test]
     #[ignore]
     fn unordered_cert_chain_succeeds() {
-        let chain = [PROCESSOR_CA, ROOT_CA, LEAF_CERT]
-            .iter()
-            .map(|cert| Certificate::from_pem(cert).expect("failed to parse cert"))
-            .collect::<Vec<_>>();
-        let crls = [ROOT_CRL, PROCESSOR_CRL]
-            .iter()
-            .map(|crl| CertificateList::from_der(crl).expect("failed to parse CRL"))
-            .collect::<Vec<_>>();
-        let verifier = TlsCertificateChainVerifier::new(ROOT_CA);
+        let chain = certs(&[PROCESSOR_CA, ROOT_CA, LEAF_CERT]);
+        let verifier = TlsCertificateChainVerifier::intel();
         assert!(verifier
-            .verify_certificate_chain(chain.iter(), crls.iter(), None)
+            .verify_certificate_chain(chain.iter(), crls().iter(), None)
             .is_ok());
     }
 
@@ -20,18 +20,22 @@ impl DcapVerifier {
     /// Create a new instance of the DcapVerifier.
     ///
     /// # Arguments
+    /// * `certificate_verifier` - The verifier for the certificate chains, i.e. the pinned root CA.
     /// * `trusted_identities` - The allowed identities that can be used in an
     ///   enclave. Verification will succeed if any of these match.
     /// * `time` - The time to use to verify the validity of the certificates
     ///   and collateral. If time is provided, verification will fail if this
     ///   time is before or after any of the validity periods. Otherwise, time
     ///   validation of certificates will be skipped.
-    pub fn new<I, ID>(trusted_identities: I, time: impl Into<Option<DateTime>>) -> Self
+    pub fn new<I, ID>(
+        certificate_verifier: TlsCertificateChainVerifier,
+        trusted_identities: I,
+        time: impl Into<Option<DateTime>>,
+    ) -> Self
     where
         I: IntoIterator<Item = ID>,
         ID: Into<TrustedIdentity>,
     {
-        let certificate_verifier = TlsCertificateChainVerifier;
         let verifier = EvidenceVerifier::new(certificate_verifier, trusted_identities, time);
         Self { verifier }
     }
@@ -0,0 +1,580 @@
+//! Attestation policy for the TCB status and advisories of the attesting platform.
+//!
+//! The [`TrustedIdentity`] advisories in `mc-attestation-verifier` can only express a minimum TCB
+//! status, and never one that is out of date. [`DcapPolicyVerifier`] performs the same checks as the
+//! `EvidenceVerifier`, but checks the platform's TCB status and advisories against an
+//! [`AttestationPolicy`] instead. It also reports which check failed and what the evidence says
+//! about the platform and enclave (see [`DcapEvidenceSummary`]).
+
+use std::{collections::BTreeSet, fmt, str::FromStr};
+
+use der::{DateTime, DecodePem};
+use mc_attestation_verifier::{
+    Accessor, Advisories, AdvisoriesVerifier, AdvisoryStatus, CertificateChainVerifier, Evidence,
+    QeIdentity, QeReportBodyVerifier, Quote3Verifier, SignedQeIdentity, SignedQeIdentityVerifier,
+    SignedTcbInfoVerifier, TrustedIdentitiesVerifier, TrustedIdentity, Verifier,
+};
+use mc_sgx_core_types::{IsvProductId, IsvSvn, MrEnclave, MrSigner, ReportData};
+use mc_sgx_dcap_types::{CertificationData, Collateral, Quote3};
+use p256::ecdsa::VerifyingKey;
+use serde::{Deserialize, Serialize};
+use x509_cert::{crl::CertificateList, Certificate};
+
+use super::certificate_chain::TlsCertificateChainVerifier;
+
+/// The TCB status of a platform's TCB level, as named in Intel's TCB info. A revoked TCB level is
+/// never acceptable and so has no variant here.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum TcbStatus {
+    UpToDate,
+    #[serde(rename = "SWHardeningNeeded")]
+    SwHardeningNeeded,
+    ConfigurationNeeded,
+    #[serde(rename = "ConfigurationAndSWHardeningNeeded")]
+    ConfigurationAndSwHardeningNeeded,
+    OutOfDate,
+    OutOfDateConfigurationNeeded,
+}
+
+impl TcbStatus {
+    const ALL: [Self; 6] = [
+        Self::UpToDate,
+        Self::SwHardeningNeeded,
+        Self::ConfigurationNeeded,
+        Self::ConfigurationAndSwHardeningNeeded,
+        Self::OutOfDate,
+        Self::OutOfDateConfigurationNeeded,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::UpToDate => "UpToDate",
+            Self::SwHardeningNeeded => "SWHardeningNeeded",
+            Self::ConfigurationNeeded => "ConfigurationNeeded",
+            Self::ConfigurationAndSwHardeningNeeded => "ConfigurationAndSWHardeningNeeded",
+            Self::OutOfDate => "OutOfDate",
+            Self::OutOfDateConfigurationNeeded => "OutOfDateConfigurationNeeded",
+        }
+    }
+}
+
+impl fmt::Display for TcbStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for TcbStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|status| status.as_str() == s)
+            .ok_or_else(|| format!("unknown TCB status: {s}"))
+    }
+}
+
+impl From<TcbStatus> for AdvisoryStatus {
+    fn from(value: TcbStatus) -> Self {
+        match value {
+            TcbStatus::UpToDate => Self::UpToDate,
+            TcbStatus::SwHardeningNeeded => Self::SWHardeningNeeded,
+            TcbStatus::ConfigurationNeeded => Self::ConfigurationNeeded,
+            TcbStatus::ConfigurationAndSwHardeningNeeded => Self::ConfigurationAndSWHardeningNeeded,
+            TcbStatus::OutOfDate => Self::OutOfDate,
+            TcbStatus::OutOfDateConfigurationNeeded => Self::OutOfDateConfigurationNeeded,
+        }
+    }
+}
+
+/// The TCB statuses and advisory IDs that are acceptable for an attesting platform.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttestationPolicy {
+    tcb_statuses: BTreeSet<TcbStatus>,
+    advisory_ids: BTreeSet<String>,
+}
+
+impl Default for AttestationPolicy {
+    /// Accepts up-to-date platforms and those that only need SW hardening against
+    /// `INTEL-SA-00334` and `INTEL-SA-00615`, which enclaves built with Gramine mitigate.
+    fn default() -> Self {
+        Self::new(
+            [TcbStatus::UpToDate, TcbStatus::SwHardeningNeeded],
+            ["INTEL-SA-00334", "INTEL-SA-00615"],
+        )
+    }
+}
+
+impl AttestationPolicy {
+    pub fn new<'a>(
+        tcb_statuses: impl IntoIterator<Item = TcbStatus>,
+        advisory_ids: impl IntoIterator<Item = &'a str>,
+    ) -> Self {
+        Self {
+            tcb_statuses: tcb_statuses.into_iter().collect(),
+            advisory_ids: advisory_ids.into_iter().map(ToString::to_string).collect(),
+        }
+    }
+
+    pub fn tcb_statuses(&self) -> impl Iterator<Item = TcbStatus> + '_ {
+        self.tcb_statuses.iter().copied()
+    }
+
+    pub fn advisory_ids(&self) -> impl Iterator<Item = &str> {
+        self.advisory_ids.iter().map(String::as_str)
+    }
+
+    /// Returns `true` if the platform's TCB status is one of the accepted ones and all of its
+    /// advisories are accepted.
+    pub fn accepts(&self, evidence: &impl Accessor<Advisories>) -> bool {
+        // `Advisories` can't be inspected directly, the verifier only tells us if the actual
+        // status is at least as good as an expected one and the actual IDs are a subset of the
+        // expected ones. So we probe for the actual status, best first.
+        let at_least = |status: AdvisoryStatus| -> bool {
+            let expected = Advisories::new(&self.advisory_ids, status);
+            AdvisoriesVerifier::new(expected)
+                .verify(evidence)
+                .is_success()
+                .into()
+        };
+
+        if !at_least(AdvisoryStatus::Revoked) {
+            // some advisory isn't accepted
+            return false;
+        }
+
+        let status = TcbStatus::ALL
+            .into_iter()
+            .find(|status| at_least((*status).into()));
+
+        // `None` means the TCB level is revoked
+        status.is_some_and(|status| self.tcb_statuses.contains(&status))
+    }
+}
+
+/// The outcome of each of the checks performed by [`DcapPolicyVerifier`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DcapPolicyVerifierOutput {
+    pub tcb_signing_chain: bool,
+    pub qe_identity_signing_chain: bool,
+    pub quote_signing_chain: bool,
+    pub tcb_info: bool,
+    pub qe_identity: bool,
+    pub qe_report_body: bool,
+    pub quote: bool,
+    pub trusted_identities: bool,
+    pub policy: bool,
+}
+
+impl DcapPolicyVerifierOutput {
+    /// Returns the name of the first check that failed, if any.
+    pub fn failed_check(&self) -> Option<&'static str> {
+        [
+            ("tcb_signing_chain", self.tcb_signing_chain),
+            ("qe_identity_signing_chain", self.qe_identity_signing_chain),
+            ("quote_signing_chain", self.quote_signing_chain),
+            ("tcb_info", self.tcb_info),
+            ("qe_identity", self.qe_identity),
+            ("qe_report_body", self.qe_report_body),
+            ("quote", self.quote),
+            ("trusted_identities", self.trusted_identities),
+            ("policy", self.policy),
+        ]
+        .into_iter()
+        .find_map(|(check, is_success)| (!is_success).then_some(check))
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.tcb_signing_chain
+            && self.qe_identity_signing_chain
+            && self.quote_signing_chain
+            && self.tcb_info
+            && self.qe_identity
+            && self.qe_report_body
+            && self.quote
+            && self.trusted_identities
+            && self.policy
+    }
+}
+
+/// What the evidence says about the attesting platform and enclave. This is only trustworthy if
+/// the verification succeeded.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DcapEvidenceSummary {
+    /// The TCB status of the platform's TCB level, or `None` if it is revoked.
+    pub tcb_status: Option<TcbStatus>,
+    pub advisory_ids: Vec<String>,
+    pub mr_enclave: [u8; 32],
+    pub mr_signer: [u8; 32],
+    pub isv_prod_id: u16,
+    pub isv_svn: u16,
+    pub report_data: [u8; 64],
+}
+
+impl DcapEvidenceSummary {
+    fn new(evidence: &Evidence<Vec<u8>>) -> Result<Self, mc_attestation_verifier::Error> {
+        // `Advisories` can't be inspected directly (see `AttestationPolicy::accepts()`), but it
+        // serializes its IDs and status
+        #[derive(Deserialize)]
+        struct RawAdvisories {
+            ids: Vec<String>,
+            status: String,
+        }
+        let advisories: Advisories = evidence.get();
+        let advisories: RawAdvisories =
+            serde_json::to_value(advisories).and_then(serde_json::from_value)?;
+
+        let mr_enclave: MrEnclave = evidence.get();
+        let mr_signer: MrSigner = evidence.get();
+        let isv_prod_id: IsvProductId = evidence.get();
+        let isv_svn: IsvSvn = evidence.get();
+        let report_data: ReportData = evidence.get();
+
+        Ok(Self {
+            tcb_status: advisories.status.parse().ok(),
+            advisory_ids: advisories.ids,
+            mr_enclave: to_array(mr_enclave.as_ref()),
+            mr_signer: to_array(mr_signer.as_ref()),
+            isv_prod_id: isv_prod_id.into(),
+            isv_svn: isv_svn.into(),
+            report_data: to_array(report_data.as_ref()),
+        })
+    }
+}
+
+fn to_array<const N: usize>(bytes: &[u8]) -> [u8; N] {
+    bytes
+        .try_into()
+        .expect("SGX measurements have a fixed size")
+}
+
+#[derive(Debug)]
+pub struct DcapPolicyVerifier {
+    certificate_verifier: TlsCertificateChainVerifier,
+    trusted_identities: Vec<TrustedIdentity>,
+    policy: Option<AttestationPolicy>,
+    time: Option<DateTime>,
+}
+
+impl DcapPolicyVerifier {
+    /// Create a new instance of the DcapPolicyVerifier.
+    ///
+    /// # Arguments
+    /// * `certificate_verifier` - The verifier for the certificate chains, i.e. the pinned root CA.
+    /// * `trusted_identities` - The allowed identities that can be used in an enclave. Verification
+    ///   will succeed if any of these match. Their advisories are ignored in favour of `policy`.
+    /// * `policy` - The acceptable TCB statuses and advisories of the platform. If `None`, the
+    ///   advisories of the `trusted_identities` are checked instead (like the `EvidenceVerifier`).
+    /// * `time` - The time to use to verify the validity of the certificates. If `None`, time
+    ///   validation is skipped. The TCB info and QE identity aren't checked for freshness, that's
+    ///   up to whoever provides them (e.g. the tcbinfo contract).
+    pub fn new<I, ID>(
+        certificate_verifier: TlsCertificateChainVerifier,
+        trusted_identities: I,
+        policy: impl Into<Option<AttestationPolicy>>,
+        time: impl Into<Option<DateTime>>,
+    ) -> Self
+    where
+        I: IntoIterator<Item = ID>,
+        ID: Into<TrustedIdentity>,
+    {
+        Self {
+            certificate_verifier,
+            trusted_identities: trusted_identities.into_iter().map(Into::into).collect(),
+            policy: policy.into(),
+            time: time.into(),
+        }
+    }
+
+    pub fn verify(
+        &self,
+        quote: Quote3<Vec<u8>>,
+        collateral: Collateral,
+    ) -> Result<DcapPolicyVerifierOutput, mc_attestation_verifier::Error> {
+        self.verify_with_summary(quote, collateral)
+            .map(|(output, _)| output)
+    }
+
+    /// Verifies like [`Self::verify`], but also returns what the evidence says about the platform
+    /// and enclave.
+    pub fn verify_with_summary(
+        &self,
+        quote: Quote3<Vec<u8>>,
+        collateral: Collateral,
+    ) -> Result<(DcapPolicyVerifierOutput, DcapEvidenceSummary), mc_attestation_verifier::Error>
+    {
+        let (tcb_key, tcb_signing_chain) = self
+            .verify_certificate_chain(collateral.tcb_issuer_chain(), [collateral.root_ca_crl()]);
+        let (qe_key, qe_identity_signing_chain) = self.verify_certificate_chain(
+            collateral.qe_identity_issuer_chain(),
+            [collateral.root_ca_crl()],
+        );
+        let (quote_key, quote_signing_chain) = match pck_certificate_chain(&quote) {
+            Some(chain) => self
+                .verify_certificate_chain(&chain, [collateral.root_ca_crl(), collateral.pck_crl()]),
+            None => (None, false),
+        };
+
+        let evidence = Evidence::new(quote, collateral)?;
+        let signed_qe_identity: SignedQeIdentity = evidence.get();
+        let qe_identity = QeIdentity::try_from(&signed_qe_identity)?;
+
+        let tcb_info = SignedTcbInfoVerifier::new(tcb_key, None).verify(&evidence);
+        let qe_identity_verification =
+            SignedQeIdentityVerifier::new(qe_key, None).verify(&evidence);
+        let qe_report_body = QeReportBodyVerifier::new(qe_identity).verify(&evidence);
+        let quote_verification = Quote3Verifier::<Vec<u8>>::new(quote_key).verify(&evidence);
+        let trusted_identities = TrustedIdentitiesVerifier::new(&self.trusted_identities);
+        let trusted_identities = match &self.policy {
+            Some(_) => trusted_identities.verify(&IdentityEvidence(&evidence)),
+            None => trusted_identities.verify(&evidence),
+        };
+
+        let output = DcapPolicyVerifierOutput {
+            tcb_signing_chain,
+            qe_identity_signing_chain,
+            quote_signing_chain,
+            tcb_info: tcb_info.is_success().into(),
+            qe_identity: qe_identity_verification.is_success().into(),
+            qe_report_body: qe_report_body.is_success().into(),
+            quote: quote_verification.is_success().into(),
+            trusted_identities: trusted_identities.is_success().into(),
+            policy: self
+                .policy
+                .as_ref()
+                .map_or(true, |policy| policy.accepts(&evidence)),
+        };
+
+        Ok((output, DcapEvidenceSummary::new(&evidence)?))
+    }
+
+    fn verify_certificate_chain<'c>(
+        &self,
+        chain: &[Certificate],
+        crls: impl IntoIterator<Item = &'c CertificateList>,
+    ) -> (Option<VerifyingKey>, bool) {
+        let is_success = self
+            .certificate_verifier
+            .verify_certificate_chain(chain, crls, self.time)
+            .is_ok();
+
+        // like the `EvidenceVerifier`, use the leaf's key even if the chain verification failed so
+        // that the signature checks report meaningful results
+        let key = chain.first().and_then(|cert| {
+            let key_bytes = cert
+                .tbs_certificate
+                .subject_public_key_info
+                .subject_public_key
+                .as_bytes()?;
+            VerifyingKey::from_sec1_bytes(key_bytes).ok()
+        });
+
+        (key, is_success)
+    }
+}
+
+/// Returns the PCK certificate chain embedded in the quote's certification data, leaf first.
+pub fn pck_certificate_chain(quote: &Quote3<Vec<u8>>) -> Option<Vec<Certificate>> {
+    let signature_data = quote.signature_data();
+    let CertificationData::PckCertificateChain(pem_chain) = signature_data.certification_data()
+    else {
+        return None;
+    };
+
+    pem_chain
+        .into_iter()
+        .map(Certificate::from_pem)
+        .collect::<Result<Vec<_>, _>>()
+        .ok()
+}
+
+/// The evidence as seen by the trusted identities check. The platform advisories are reported as
+/// up to date since they are checked against the policy instead.
+struct IdentityEvidence<'a>(&'a Evidence<Vec<u8>>);
+
+macro_rules! identity_evidence_accessor {
+    ($($field_type:ty;)*) => {$(
+        impl Accessor<$field_type> for IdentityEvidence<'_> {
+            fn get(&self) -> $field_type {
+                self.0.get()
+            }
+        }
+    )*}
+}
+
+identity_evidence_accessor! {
+    MrEnclave;
+    MrSigner;
+    IsvProductId;
+    IsvSvn;
+}
+
+impl Accessor<Advisories> for IdentityEvidence<'_> {
+    fn get(&self) -> Advisories {
+        Advisories::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mc_attestation_verifier::TrustedMrEnclaveIdentity;
+
+    use super::*;
+    use crate::intel_sgx::dcap::synthetic::SyntheticDcap;
+
+    const MR_ENCLAVE: [u8; 32] = [0xab; 32];
+    const USER_DATA: [u8; 64] = [0xcd; 64];
+
+    fn verify(dcap: &SyntheticDcap, policy: AttestationPolicy) -> DcapPolicyVerifierOutput {
+        let identity = TrustedMrEnclaveIdentity::new(MrEnclave::from(MR_ENCLAVE), [""; 0], [""; 0]);
+        DcapPolicyVerifier::new(
+            TlsCertificateChainVerifier::new(dcap.root_ca()),
+            [identity],
+            policy,
+            None,
+        )
+        .verify(dcap.quote(MR_ENCLAVE, USER_DATA), dcap.collateral())
+        .expect("synthetic evidence must be valid")
+    }
+
+    #[test]
+    fn default_policy_accepts_mitigated_advisories() {
+        let output = verify(&SyntheticDcap::new(), AttestationPolicy::default());
+        assert!(output.is_success(), "{output:?}");
+
+        let dcap = SyntheticDcap::new().with_tcb_status("SWHardeningNeeded", &["INTEL-SA-00615"]);
+        let output = verify(&dcap, AttestationPolicy::default());
+        assert!(output.is_success(), "{output:?}");
+
+        let dcap = SyntheticDcap::new().with_tcb_status("SWHardeningNeeded", &["INTEL-SA-00657"]);
+        let output = verify(&dcap, AttestationPolicy::default());
+        // only the policy check fails
+        assert!(!output.policy);
+        assert!(DcapPolicyVerifierOutput {
+            policy: true,
+            ..output
+        }
+        .is_success());
+    }
+
+    #[test]
+    fn out_of_date_platform_must_be_allowed() {
+        let dcap = SyntheticDcap::new().with_tcb_status("OutOfDate", &["INTEL-SA-00615"]);
+        assert!(!verify(&dcap, AttestationPolicy::default()).policy);
+
+        let policy = AttestationPolicy::new(
+            [TcbStatus::UpToDate, TcbStatus::OutOfDate],
+            ["INTEL-SA-00615"],
+        );
+        let output = verify(&dcap, policy);
+        assert!(output.is_success(), "{output:?}");
+    }
+
+    #[test]
+    fn revoked_platform_is_never_accepted() {
+        let dcap = SyntheticDcap::new().with_tcb_status("Revoked", &[]);
+        let policy = AttestationPolicy::new(TcbStatus::ALL, [""; 0]);
+        assert!(!verify(&dcap, policy).policy);
+    }
+
+    #[test]
+    fn policy_must_match_the_trusted_identity() {
+        let dcap = SyntheticDcap::new();
+        let output = DcapPolicyVerifier::new(
+            TlsCertificateChainVerifier::new(dcap.root_ca()),
+            [TrustedMrEnclaveIdentity::new(
+                MrEnclave::from([0x01; 32]),
+                [""; 0],
+                [""; 0],
+            )],
+            AttestationPolicy::default(),
+            None,
+        )
+        .verify(dcap.quote(MR_ENCLAVE, USER_DATA), dcap.collateral())
+        .expect("synthetic evidence must be valid");
+        assert!(!output.trusted_identities);
+        assert!(output.policy);
+    }
+
+    #[test]
+    fn revoked_or_expired_certificates_are_rejected() {
+        let dcap = SyntheticDcap::new().with_revoked_pck_cert();
+        let output = verify(&dcap, AttestationPolicy::default());
+        assert!(!output.quote_signing_chain);
+        assert!(!output.is_success());
+
+        let dcap = SyntheticDcap::new().with_revoked_tcb_signer();
+        let output = verify(&dcap, AttestationPolicy::default());
+        assert!(!output.tcb_signing_chain);
+        assert!(!output.qe_identity_signing_chain);
+        assert!(!output.is_success());
+
+        let dcap = SyntheticDcap::new();
+        let identity = TrustedMrEnclaveIdentity::new(MrEnclave::from(MR_ENCLAVE), [""; 0], [""; 0]);
+        let expired = "2050-01-01T00:00:00Z"
+            .parse::<DateTime>()
+            .expect("valid time");
+        let output = DcapPolicyVerifier::new(
+            TlsCertificateChainVerifier::new(dcap.root_ca()),
+            [identity],
+            AttestationPolicy::default(),
+            expired,
+        )
+        .verify(dcap.quote(MR_ENCLAVE, USER_DATA), dcap.collateral())
+        .expect("synthetic evidence must be valid");
+        assert!(!output.tcb_signing_chain);
+        assert!(!output.quote_signing_chain);
+        assert!(!output.is_success());
+    }
+
+    #[test]
+    fn failed_check_and_summary_are_reported() {
+        let dcap = SyntheticDcap::new().with_tcb_status("SWHardeningNeeded", &["INTEL-SA-00657"]);
+        let verifier = |policy: Option<AttestationPolicy>| {
+            let identity =
+                TrustedMrEnclaveIdentity::new(MrEnclave::from(MR_ENCLAVE), [""; 0], [""; 0]);
+            DcapPolicyVerifier::new(
+                TlsCertificateChainVerifier::new(dcap.root_ca()),
+                [identity],
+                policy,
+                None,
+            )
+        };
+
+        let (output, summary) = verifier(Some(AttestationPolicy::default()))
+            .verify_with_summary(dcap.quote(MR_ENCLAVE, USER_DATA), dcap.collateral())
+            .expect("synthetic evidence must be valid");
+        assert_eq!(output.failed_check(), Some("policy"));
+        assert_eq!(summary.tcb_status, Some(TcbStatus::SwHardeningNeeded));
+        assert_eq!(summary.advisory_ids, ["INTEL-SA-00657"]);
+        assert_eq!(summary.mr_enclave, MR_ENCLAVE);
+        assert_eq!(summary.report_data, USER_DATA);
+
+        // without a policy, the identity's (i.e. no) advisories are checked instead
+        let (output, _) = verifier(None)
+            .verify_with_summary(dcap.quote(MR_ENCLAVE, USER_DATA), dcap.collateral())
+            .expect("synthetic evidence must be valid");
+        assert_eq!(output.failed_check(), Some("trusted_identities"));
+
+        let dcap = SyntheticDcap::new();
+        let (output, summary) = verifier(None)
+            .verify_with_summary(dcap.quote(MR_ENCLAVE, USER_DATA), dcap.collateral())
+            .expect("synthetic evidence must be valid");
+        assert_eq!(output.failed_check(), None);
+        assert_eq!(summary.tcb_status, Some(TcbStatus::UpToDate));
+        assert!(summary.advisory_ids.is_empty());
+    }
+
+    #[test]
+    fn tcb_status_round_trips() {
+        for status in TcbStatus::ALL {
+            assert_eq!(status.to_string().parse::<TcbStatus>(), Ok(status));
+            assert_eq!(
+                serde_json::to_string(&status).expect("infallible serializer"),
+                format!("\"{status}\"")
+            );
+        }
+        assert!("Revoked".parse::<TcbStatus>().is_err());
+    }
+}
@@ -0,0 +1,844 @@
+//! Synthetic DCAP quotes and collateral for hardware-free testing.
+//!
+//! [`SyntheticDcap`] generates a complete (deterministic) DCAP PKI - a root CA, a PCK platform CA,
+//! a PCK leaf certificate, a TCB signing certificate and a QE attestation key - and uses it to
+//! build structurally valid `Quote3`s and matching `Collateral` for any MRENCLAVE and user data.
+//! The output passes the exact same verification as quotes generated on real SGX hardware, as long
+//! as the verifier trusts the synthetic root CA (see [`SyntheticDcap::root_ca_pem`]).
+//!
+//! The synthetic root CA must NEVER be trusted outside of tests.
+
+use core::str::FromStr;
+
+use der::{
+    asn1::{BitString, OctetString, UtcTime},
+    oid::ObjectIdentifier,
+    pem::LineEnding,
+    Any, DateTime, Encode, EncodePem, Tag,
+};
+use mc_sgx_dcap_sys_types::sgx_ql_qve_collateral_t;
+use p256::ecdsa::{signature::Signer, Signature, SigningKey, VerifyingKey};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use x509_cert::{
+    attr::AttributeTypeAndValue,
+    certificate::{TbsCertificate, Version},
+    crl::{CertificateList, RevokedCert, TbsCertList},
+    ext::{pkix::BasicConstraints, Extension},
+    name::Name,
+    serial_number::SerialNumber,
+    spki::{AlgorithmIdentifierOwned, SubjectPublicKeyInfoOwned},
+    time::{Time, Validity},
+    Certificate,
+};
+
+use super::{Collateral, Quote3};
+
+/// The FMSPC that synthetic PCK certificates are issued for by default.
+pub const DEFAULT_FMSPC: [u8; 6] = [0x00, 0x60, 0x6a, 0x00, 0x00, 0x00];
+
+/// The MRSIGNER of the synthetic quoting enclave.
+pub const QE_MR_SIGNER: [u8; 32] = [0x51; 32];
+
+const DEFAULT_SEED: &[u8] = b"quartz-synthetic-dcap";
+
+const ID_EC_PUBLIC_KEY: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.2.1");
+const SECP256R1: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.3.1.7");
+const ECDSA_WITH_SHA256: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.10045.4.3.2");
+const BASIC_CONSTRAINTS: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.5.29.19");
+
+const SGX_EXTENSIONS_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113741.1.13.1");
+const TCB_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113741.1.13.1.2");
+const PCE_SVN_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113741.1.13.1.2.17");
+const CPU_SVN_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113741.1.13.1.2.18");
+const PCE_ID_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113741.1.13.1.3");
+const FMSPC_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.840.113741.1.13.1.4");
+
+const ROOT_CA_NAME: &str = "CN=Quartz Synthetic SGX Root CA,O=Quartz,C=US";
+const PCK_CA_NAME: &str = "CN=Quartz Synthetic SGX PCK Platform CA,O=Quartz,C=US";
+const PCK_NAME: &str = "CN=Quartz Synthetic SGX PCK Certificate,O=Quartz,C=US";
+const TCB_SIGNER_NAME: &str = "CN=Quartz Synthetic SGX TCB Signing,O=Quartz,C=US";
+
+const ROOT_CA_SERIAL: u8 = 1;
+const PCK_CA_SERIAL: u8 = 2;
+const PCK_SERIAL: u8 = 3;
+const TCB_SIGNER_SERIAL: u8 = 4;
+
+const ISSUE_DATE: &str = "2024-01-01T00:00:00Z";
+const NEXT_UPDATE: &str = "2049-12-31T23:59:59Z";
+
+// The TCB of the synthetic platform, i.e. the SVNs in the PCK certificate and the (only) TCB level
+const TCB_COMPONENT_SVNS: [u32; 16] = [20, 20, 2, 4, 1, 128, 14, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+const PCE_SVN: u16 = 13;
+const QE_SVN: u16 = 8;
+const QE_PROD_ID: u16 = 1;
+const QE_ATTRIBUTES: [u8; 16] = [0x11, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+const QE_VENDOR_ID: [u8; 16] = [
+    0x93, 0x9a, 0x72, 0x33, 0xf7, 0x9c, 0x4c, 0xa9, 0x94, 0x0a, 0x0d, 0xb3, 0x95, 0x7f, 0x06, 0x07,
+];
+const ECDSA_P256_ATTESTATION_KEY: u16 = 2;
+const PCK_CERT_CHAIN_CERTIFICATION_DATA: u16 = 5;
+
+const REPORT_BODY_SIZE: usize = 384;
+
+/// The enclave report that a synthetic quote attests to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SyntheticReport {
+    pub mr_enclave: [u8; 32],
+    pub mr_signer: [u8; 32],
+    pub isv_prod_id: u16,
+    pub isv_svn: u16,
+    pub report_data: [u8; 64],
+}
+
+impl SyntheticReport {
+    pub fn new(mr_enclave: [u8; 32], report_data: [u8; 64]) -> Self {
+        Self {
+            mr_enclave,
+            mr_signer: [0; 32],
+            isv_prod_id: 0,
+            isv_svn: 0,
+            report_data,
+        }
+    }
+
+    fn to_bytes(&self) -> [u8; REPORT_BODY_SIZE] {
+        report_body(ReportBody {
+            attributes: [0; 16],
+            mr_enclave: self.mr_enclave,
+            mr_signer: self.mr_signer,
+            isv_prod_id: self.isv_prod_id,
+            isv_svn: self.isv_svn,
+            report_data: self.report_data,
+        })
+    }
+}
+
+/// A generator for synthetic DCAP quotes and collateral (only meant for testing purposes).
+#[derive(Clone, Debug)]
+pub struct SyntheticDcap {
+    root_key: SigningKey,
+    pck_ca_key: SigningKey,
+    pck_key: SigningKey,
+    tcb_signer_key: SigningKey,
+    attestation_key: SigningKey,
+    fmspc: [u8; 6],
+    tcb_status: String,
+    advisory_ids: Vec<String>,
+    tcb_info_dates: (String, String),
+    crl_dates: (String, String),
+    revoked: Vec<u8>,
+}
+
+impl Default for SyntheticDcap {
+    fn default() -> Self {
+        Self::from_seed(DEFAULT_SEED)
+    }
+}
+
+impl SyntheticDcap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Derives all keys from the specified `seed`, i.e. the same seed always yields the same PKI.
+    pub fn from_seed(seed: &[u8]) -> Self {
+        let key = |label: &str| {
+            let secret = Sha256::new()
+                .chain_update(seed)
+                .chain_update(label)
+                .finalize();
+            SigningKey::from_slice(&secret).expect("SHA-256 digest is a valid P-256 scalar")
+        };
+
+        Self {
+            root_key: key("root-ca"),
+            pck_ca_key: key("pck-ca"),
+            pck_key: key("pck"),
+            tcb_signer_key: key("tcb-signer"),
+            attestation_key: key("qe-attestation-key"),
+            fmspc: DEFAULT_FMSPC,
+            tcb_status: "UpToDate".into(),
+            advisory_ids: vec![],
+            tcb_info_dates: (ISSUE_DATE.into(), NEXT_UPDATE.into()),
+            crl_dates: (ISSUE_DATE.into(), NEXT_UPDATE.into()),
+            revoked: vec![],
+        }
+    }
+
+    /// Issues the PCK certificate (and TCB info) for a different FMSPC.
+    pub fn with_fmspc(mut self, fmspc: [u8; 6]) -> Self {
+        self.fmspc = fmspc;
+        self
+    }
+
+    /// Sets the status (and advisories) of the platform's TCB level in the TCB info, e.g.
+    /// `("SWHardeningNeeded", &["INTEL-SA-00615"])`.
+    pub fn with_tcb_status(mut self, tcb_status: &str, advisory_ids: &[&str]) -> Self {
+        self.tcb_status = tcb_status.into();
+        self.advisory_ids = advisory_ids.iter().map(|&id| id.into()).collect();
+        self
+    }
+
+    /// Sets the `issueDate` and `nextUpdate` of the TCB info, e.g. to issue a newer or expired one.
+    pub fn with_tcb_info_dates(mut self, issue_date: &str, next_update: &str) -> Self {
+        self.tcb_info_dates = (issue_date.into(), next_update.into());
+        self
+    }
+
+    /// Sets the `thisUpdate` and `nextUpdate` of the CRLs, e.g. to issue stale ones.
+    pub fn with_crl_dates(mut self, this_update: &str, next_update: &str) -> Self {
+        self.crl_dates = (this_update.into(), next_update.into());
+        self
+    }
+
+    /// Lists the PCK certificate in the PCK CRL.
+    pub fn with_revoked_pck_cert(self) -> Self {
+        self.with_revoked(PCK_SERIAL)
+    }
+
+    /// Lists the PCK CA certificate in the root CA CRL.
+    pub fn with_revoked_pck_ca(self) -> Self {
+        self.with_revoked(PCK_CA_SERIAL)
+    }
+
+    /// Lists the TCB signer certificate in the root CA CRL.
+    pub fn with_revoked_tcb_signer(self) -> Self {
+        self.with_revoked(TCB_SIGNER_SERIAL)
+    }
+
+    fn with_revoked(mut self, serial_number: u8) -> Self {
+        self.revoked.push(serial_number);
+        self
+    }
+
+    pub fn fmspc(&self) -> [u8; 6] {
+        self.fmspc
+    }
+
+    pub fn root_ca(&self) -> Certificate {
+        let extensions = vec![basic_constraints(true)];
+        certificate(
+            ROOT_CA_SERIAL,
+            ROOT_CA_NAME,
+            ROOT_CA_NAME,
+            &self.root_key,
+            &self.root_key,
+            extensions,
+        )
+    }
+
+    pub fn root_ca_pem(&self) -> String {
+        to_pem(&self.root_ca())
+    }
+
+    pub fn pck_ca(&self) -> Certificate {
+        let extensions = vec![basic_constraints(true)];
+        certificate(
+            PCK_CA_SERIAL,
+            PCK_CA_NAME,
+            ROOT_CA_NAME,
+            &self.pck_ca_key,
+            &self.root_key,
+            extensions,
+        )
+    }
+
+    pub fn pck_cert(&self) -> Certificate {
+        let extensions = vec![basic_constraints(false), sgx_extensions(self.fmspc)];
+        certificate(
+            PCK_SERIAL,
+            PCK_NAME,
+            PCK_CA_NAME,
+            &self.pck_key,
+            &self.pck_ca_key,
+            extensions,
+        )
+    }
+
+    pub fn tcb_signer(&self) -> Certificate {
+        let extensions = vec![basic_constraints(false)];
+        certificate(
+            TCB_SIGNER_SERIAL,
+            TCB_SIGNER_NAME,
+            ROOT_CA_NAME,
+            &self.tcb_signer_key,
+            &self.root_key,
+            extensions,
+        )
+    }
+
+    pub fn tcb_signer_pem(&self) -> String {
+        to_pem(&self.tcb_signer())
+    }
+
+    pub fn root_ca_crl(&self) -> CertificateList {
+        let revoked = self
+            .revoked
+            .iter()
+            .filter(|&&serial_number| serial_number != PCK_SERIAL);
+        crl(ROOT_CA_NAME, &self.root_key, &self.crl_dates, revoked)
+    }
+
+    pub fn pck_crl(&self) -> CertificateList {
+        let revoked = self
+            .revoked
+            .iter()
+            .filter(|&&serial_number| serial_number == PCK_SERIAL);
+        crl(PCK_CA_NAME, &self.pck_ca_key, &self.crl_dates, revoked)
+    }
+
+    /// The signed TCB info for the synthetic platform's FMSPC (as served by the PCS).
+    pub fn tcb_info(&self) -> String {
+        let tcb_components = TCB_COMPONENT_SVNS
+            .iter()
+            .map(|svn| json!({ "svn": svn }))
+            .collect::<Vec<_>>();
+        let tcb_info = json!({
+            "id": "SGX",
+            "version": 3,
+            "issueDate": self.tcb_info_dates.0,
+            "nextUpdate": self.tcb_info_dates.1,
+            "fmspc": hex::encode_upper(self.fmspc),
+            "pceId": "0000",
+            "tcbType": 0,
+            "tcbEvaluationDataNumber": 16,
+            "tcbLevels": [{
+                "tcb": {
+                    "sgxtcbcomponents": tcb_components,
+                    "pcesvn": PCE_SVN,
+                },
+                "tcbDate": ISSUE_DATE,
+                "tcbStatus": self.tcb_status,
+                "advisoryIDs": self.advisory_ids,
+            }],
+        })
+        .to_string();
+
+        signed_json("tcbInfo", &tcb_info, &self.tcb_signer_key)
+    }
+
+    /// The signed identity of the synthetic quoting enclave (as served by the PCS).
+    pub fn qe_identity(&self) -> String {
+        let qe_identity = json!({
+            "id": "QE",
+            "version": 2,
+            "issueDate": ISSUE_DATE,
+            "nextUpdate": NEXT_UPDATE,
+            "tcbEvaluationDataNumber": 16,
+            "miscselect": "00000000",
+            "miscselectMask": "FFFFFFFF",
+            "attributes": hex::encode_upper(QE_ATTRIBUTES),
+            "attributesMask": "FBFFFFFFFFFFFFFF0000000000000000",
+            "mrsigner": hex::encode_upper(QE_MR_SIGNER),
+            "isvprodid": QE_PROD_ID,
+            "tcbLevels": [{
+                "tcb": { "isvsvn": QE_SVN },
+                "tcbDate": ISSUE_DATE,
+                "tcbStatus": "UpToDate",
+            }],
+        })
+        .to_string();
+
+        signed_json("enclaveIdentity", &qe_identity, &self.tcb_signer_key)
+    }
+
+    /// Generates a quote for the specified MRENCLAVE and user (report) data.
+    pub fn quote(&self, mr_enclave: [u8; 32], user_data: [u8; 64]) -> Quote3<Vec<u8>> {
+        self.quote_for(&SyntheticReport::new(mr_enclave, user_data))
+    }
+
+    /// Generates a quote for the specified enclave report.
+    ///
+    /// The quote is structured exactly like the ones generated by Intel's QE, i.e. the app report
+    /// is signed with the QE attestation key, which in turn is bound to the QE report signed by the
+    /// PCK key (whose certificate chain is embedded as certification data).
+    pub fn quote_for(&self, report: &SyntheticReport) -> Quote3<Vec<u8>> {
+        let mut quote = Vec::new();
+
+        // header
+        quote.extend_from_slice(&3u16.to_le_bytes());
+        quote.extend_from_slice(&ECDSA_P256_ATTESTATION_KEY.to_le_bytes());
+        quote.extend_from_slice(&[0; 4]);
+        quote.extend_from_slice(&QE_SVN.to_le_bytes());
+        quote.extend_from_slice(&PCE_SVN.to_le_bytes());
+        quote.extend_from_slice(&QE_VENDOR_ID);
+        quote.extend_from_slice(&[0; 20]);
+
+        quote.extend_from_slice(&report.to_bytes());
+        let isv_enclave_signature: Signature = self.attestation_key.sign(&quote);
+
+        let attestation_key = untagged_point(self.attestation_key.verifying_key());
+        let authentication_data: Vec<u8> = (0..32).collect();
+        let qe_report_data = {
+            let digest = Sha256::new()
+                .chain_update(&attestation_key)
+                .chain_update(&authentication_data)
+                .finalize();
+            let mut report_data = [0; 64];
+            report_data[..digest.len()].copy_from_slice(&digest);
+            report_data
+        };
+        let qe_report = report_body(ReportBody {
+            attributes: QE_ATTRIBUTES,
+            mr_signer: QE_MR_SIGNER,
+            isv_prod_id: QE_PROD_ID,
+            isv_svn: QE_SVN,
+            report_data: qe_report_data,
+            mr_enclave: [0; 32],
+        });
+        let qe_report_signature: Signature = self.pck_key.sign(&qe_report);
+
+        let mut certification_data = [self.pck_cert(), self.pck_ca(), self.root_ca()]
+            .iter()
+            .map(to_pem)
+            .collect::<String>()
+            .into_bytes();
+        certification_data.push(0);
+
+        let mut signature_data = Vec::new();
+        signature_data.extend_from_slice(&isv_enclave_signature.to_bytes());
+        signature_data.extend_from_slice(&attestation_key);
+        signature_data.extend_from_slice(&qe_report);
+        signature_data.extend_from_slice(&qe_report_signature.to_bytes());
+        signature_data.extend_from_slice(&len_u16(&authentication_data).to_le_bytes());
+        signature_data.extend_from_slice(&authentication_data);
+        signature_data.extend_from_slice(&PCK_CERT_CHAIN_CERTIFICATION_DATA.to_le_bytes());
+        signature_data.extend_from_slice(&len_u32(&certification_data).to_le_bytes());
+        signature_data.extend_from_slice(&certification_data);
+
+        quote.extend_from_slice(&len_u32(&signature_data).to_le_bytes());
+        quote.extend_from_slice(&signature_data);
+
+        Quote3::try_from(quote).expect("synthetic quote must be well-formed")
+    }
+
+    /// The full collateral for verifying synthetic quotes.
+    pub fn collateral(&self) -> Collateral {
+        self.collateral_with_tcb_info(&self.tcb_info())
+    }
+
+    /// The collateral for verifying synthetic quotes with the specified `tcb_info` instead of the
+    /// synthetic platform's TCB info, e.g. the FMSPC (hex) for attestations that are verified
+    /// on-chain, where the TCB info is read from the tcbinfo contract instead.
+    pub fn collateral_with_tcb_info(&self, tcb_info: &str) -> Collateral {
+        let mut sgx_collateral = sgx_ql_qve_collateral_t::default();
+
+        // SAFETY: Version is a union which is inherently unsafe
+        #[allow(unsafe_code)]
+        let version = unsafe { sgx_collateral.__bindgen_anon_1.__bindgen_anon_1.as_mut() };
+        version.major_version = 3;
+        version.minor_version = 1;
+
+        let root_ca = self.root_ca_pem();
+
+        let mut root_crl = self
+            .root_ca_crl()
+            .to_der()
+            .expect("infallible DER encoding");
+        root_crl.push(0);
+        sgx_collateral.root_ca_crl = root_crl.as_ptr() as _;
+        sgx_collateral.root_ca_crl_size = len_u32(&root_crl);
+
+        let mut pck_crl_chain = [to_pem(&self.pck_ca()), root_ca.clone()]
+            .concat()
+            .into_bytes();
+        pck_crl_chain.push(0);
+        sgx_collateral.pck_crl_issuer_chain = pck_crl_chain.as_ptr() as _;
+        sgx_collateral.pck_crl_issuer_chain_size = len_u32(&pck_crl_chain);
+
+        let mut pck_crl = self.pck_crl().to_der().expect("infallible DER encoding");
+        pck_crl.push(0);
+        sgx_collateral.pck_crl = pck_crl.as_ptr() as _;
+        sgx_collateral.pck_crl_size = len_u32(&pck_crl);
+
+        let mut tcb_chain = [self.tcb_signer_pem(), root_ca].concat().into_bytes();
+        tcb_chain.push(0);
+        sgx_collateral.tcb_info_issuer_chain = tcb_chain.as_ptr() as _;
+        sgx_collateral.tcb_info_issuer_chain_size = len_u32(&tcb_chain);
+
+        sgx_collateral.tcb_info = tcb_info.as_ptr() as _;
+        sgx_collateral.tcb_info_size = len_u32(tcb_info.as_bytes());
+
+        // Like for live data, the QE identity uses the same chain as the TCB info
+        sgx_collateral.qe_identity_issuer_chain = tcb_chain.as_ptr() as _;
+        sgx_collateral.qe_identity_issuer_chain_size = len_u32(&tcb_chain);
+
+        let qe_identity = self.qe_identity();
+        sgx_collateral.qe_identity = qe_identity.as_ptr() as _;
+        sgx_collateral.qe_identity_size = len_u32(qe_identity.as_bytes());
+
+        Collateral::try_from(&sgx_collateral).expect("synthetic collateral must be well-formed")
+    }
+}
+
+struct ReportBody {
+    attributes: [u8; 16],
+    mr_enclave: [u8; 32],
+    mr_signer: [u8; 32],
+    isv_prod_id: u16,
+    isv_svn: u16,
+    report_data: [u8; 64],
+}
+
+/// Serializes an `sgx_report_body_t` (all other fields are zeroed).
+fn report_body(body: ReportBody) -> [u8; REPORT_BODY_SIZE] {
+    let mut bytes = [0u8; REPORT_BODY_SIZE];
+    bytes[48..64].copy_from_slice(&body.attributes);
+    bytes[64..96].copy_from_slice(&body.mr_enclave);
+    bytes[128..160].copy_from_slice(&body.mr_signer);
+    bytes[256..258].copy_from_slice(&body.isv_prod_id.to_le_bytes());
+    bytes[258..260].copy_from_slice(&body.isv_svn.to_le_bytes());
+    bytes[320..384].copy_from_slice(&body.report_data);
+    bytes
+}
+
+/// Wraps the `body` JSON in the PCS format, i.e. `{"<name>":<body>,"signature":"<hex>"}`.
+fn signed_json(name: &str, body: &str, key: &SigningKey) -> String {
+    let signature: Signature = key.sign(body.as_bytes());
+    let signature = hex::encode(signature.to_bytes());
+    [
+        "{\"",
+        name,
+        "\":",
+        body,
+        ",\"signature\":\"",
+        &signature,
+        "\"}",
+    ]
+    .concat()
+}
+
+fn certificate(
+    serial_number: u8,
+    subject: &str,
+    issuer: &str,
+    subject_key: &SigningKey,
+    issuer_key: &SigningKey,
+    extensions: Vec<Extension>,
+) -> Certificate {
+    let tbs_certificate = TbsCertificate {
+        version: Version::V3,
+        serial_number: SerialNumber::new(&[serial_number]).expect("valid serial number"),
+        signature: signature_algorithm(),
+        issuer: name(issuer),
+        validity: Validity {
+            not_before: utc_time(2020, 1, 1, 0, 0, 0),
+            not_after: utc_time(2049, 12, 31, 23, 59, 59),
+        },
+        subject: name(subject),
+        subject_public_key_info: subject_public_key_info(subject_key.verifying_key()),
+        issuer_unique_id: None,
+        subject_unique_id: None,
+        extensions: Some(extensions),
+    };
+
+    let signature = sign_der(&tbs_certificate, issuer_key);
+    Certificate {
+        tbs_certificate,
+        signature_algorithm: signature_algorithm(),
+        signature,
+    }
+}
+
+fn crl<'a>(
+    issuer: &str,
+    issuer_key: &SigningKey,
+    (this_update, next_update): &(String, String),
+    revoked: impl Iterator<Item = &'a u8>,
+) -> CertificateList {
+    let revoked_certificates = revoked
+        .map(|&serial_number| RevokedCert {
+            serial_number: SerialNumber::new(&[serial_number]).expect("valid serial number"),
+            revocation_date: utc_time(2024, 1, 1, 0, 0, 0),
+            crl_entry_extensions: None,
+        })
+        .collect::<Vec<_>>();
+    let tbs_cert_list = TbsCertList {
+        version: Version::V2,
+        signature: signature_algorithm(),
+        issuer: name(issuer),
+        this_update: parse_utc_time(this_update),
+        next_update: Some(parse_utc_time(next_update)),
+        revoked_certificates: (!revoked_certificates.is_empty()).then_some(revoked_certificates),
+        crl_extensions: None,
+    };
+
+    let signature = sign_der(&tbs_cert_list, issuer_key);
+    CertificateList {
+        tbs_cert_list,
+        signature_algorithm: signature_algorithm(),
+        signature,
+    }
+}
+
+fn basic_constraints(ca: bool) -> Extension {
+    let basic_constraints = BasicConstraints {
+        ca,
+        path_len_constraint: None,
+    };
+    extension(BASIC_CONSTRAINTS, true, &basic_constraints)
+}
+
+/// The SGX extensions of a PCK certificate (only the ones required for verification).
+fn sgx_extensions(fmspc: [u8; 6]) -> Extension {
+    let attribute = |oid: &str, value: Any| AttributeTypeAndValue {
+        oid: ObjectIdentifier::new(oid).expect("valid SGX OID"),
+        value,
+    };
+
+    let mut tcb = TCB_COMPONENT_SVNS
+        .iter()
+        .enumerate()
+        .map(|(i, svn)| {
+            attribute(
+                &[TCB_OID.to_string(), (i + 1).to_string()].join("."),
+                Any::encode_from(svn).expect("infallible DER encoding"),
+            )
+        })
+        .collect::<Vec<_>>();
+    tcb.push(AttributeTypeAndValue {
+        oid: PCE_SVN_OID,
+        value: Any::encode_from(&u32::from(PCE_SVN)).expect("infallible DER encoding"),
+    });
+    tcb.push(AttributeTypeAndValue {
+        oid: CPU_SVN_OID,
+        value: octet_string(&[0; 16]),
+    });
+
+    let sgx_extensions = vec![
+        AttributeTypeAndValue {
+            oid: TCB_OID,
+            value: Any::encode_from(&tcb).expect("infallible DER encoding"),
+        },
+        AttributeTypeAndValue {
+            oid: PCE_ID_OID,
+            value: octet_string(&[0; 2]),
+        },
+        AttributeTypeAndValue {
+            oid: FMSPC_OID,
+            value: octet_string(&fmspc),
+        },
+    ];
+    extension(SGX_EXTENSIONS_OID, false, &sgx_extensions)
+}
+
+fn extension(extn_id: ObjectIdentifier, critical: bool, value: &impl Encode) -> Extension {
+    let value = value.to_der().expect("infallible DER encoding");
+    Extension {
+        extn_id,
+        critical,
+        extn_value: OctetString::new(value).expect("valid extension value"),
+    }
+}
+
+fn octet_string(bytes: &[u8]) -> Any {
+    Any::new(Tag::OctetString, bytes).expect("valid octet string")
+}
+
+fn name(name: &str) -> Name {
+    Name::from_str(name).expect("valid distinguished name")
+}
+
+fn utc_time(year: u16, month: u8, day: u8, hour: u8, minutes: u8, seconds: u8) -> Time {
+    let date_time =
+        DateTime::new(year, month, day, hour, minutes, seconds).expect("valid date time");
+    Time::UtcTime(UtcTime::from_date_time(date_time).expect("valid UTC time"))
+}
+
+fn parse_utc_time(time: &str) -> Time {
+    let date_time = DateTime::from_str(time).expect("valid date time");
+    Time::UtcTime(UtcTime::from_date_time(date_time).expect("valid UTC time"))
+}
+
+fn signature_algorithm() -> AlgorithmIdentifierOwned {
+    AlgorithmIdentifierOwned {
+        oid: ECDSA_WITH_SHA256,
+        parameters: None,
+    }
+}
+
+fn subject_public_key_info(key: &VerifyingKey) -> SubjectPublicKeyInfoOwned {
+    SubjectPublicKeyInfoOwned {
+        algorithm: AlgorithmIdentifierOwned {
+            oid: ID_EC_PUBLIC_KEY,
+            parameters: Some(Any::encode_from(&SECP256R1).expect("infallible DER encoding")),
+        },
+        subject_public_key: BitString::from_bytes(key.to_encoded_point(false).as_bytes())
+            .expect("valid public key"),
+    }
+}
+
+fn sign_der(tbs: &impl Encode, key: &SigningKey) -> BitString {
+    let tbs = tbs.to_der().expect("infallible DER encoding");
+    let signature: Signature = key.sign(&tbs);
+    BitString::from_bytes(signature.to_der().as_bytes()).expect("valid signature")
+}
+
+/// The public key as it appears in quotes, i.e. an uncompressed point without the SEC1 tag.
+fn untagged_point(key: &VerifyingKey) -> Vec<u8> {
+    key.to_encoded_point(false).as_bytes()[1..].to_vec()
+}
+
+fn to_pem(certificate: &Certificate) -> String {
+    certificate
+        .to_pem(LineEnding::LF)
+        .expect("infallible PEM encoding")
+}
+
+fn len_u16(bytes: &[u8]) -> u16 {
+    u16::try_from(bytes.len()).expect("length must fit in a u16")
+}
+
+fn len_u32(bytes: &[u8]) -> u32 {
+    u32::try_from(bytes.len()).expect("length must fit in a u32")
+}
+
+#[cfg(test)]
+mod tests {
+    use mc_attestation_verifier::{
+        CertificateChainVerifier, Evidence, TrustedMrEnclaveIdentity, TrustedMrSignerIdentity,
+    };
+    use mc_sgx_core_types::{MrEnclave, MrSigner, ReportData};
+
+    use super::*;
+    use crate::intel_sgx::dcap::{certificate_chain::TlsCertificateChainVerifier, verify};
+
+    const MR_ENCLAVE: [u8; 32] = [0xab; 32];
+    const USER_DATA: [u8; 64] = [0xcd; 64];
+
+    fn mr_enclave_identity(mr_enclave: [u8; 32]) -> TrustedMrEnclaveIdentity {
+        TrustedMrEnclaveIdentity::new(MrEnclave::from(mr_enclave), [""; 0], [""; 0])
+    }
+
+    #[test]
+    fn synthetic_quote_verifies() {
+        let dcap = SyntheticDcap::new();
+        let quote = dcap.quote(MR_ENCLAVE, USER_DATA);
+        assert_eq!(
+            quote.app_report_body().mr_enclave(),
+            MrEnclave::from(MR_ENCLAVE)
+        );
+        assert_eq!(
+            quote.app_report_body().report_data(),
+            ReportData::from(USER_DATA)
+        );
+
+        let verification = verify(
+            quote,
+            dcap.collateral(),
+            &[mr_enclave_identity(MR_ENCLAVE).into()],
+            dcap.root_ca(),
+        );
+        assert_eq!(verification.is_success().unwrap_u8(), 1);
+    }
+
+    #[test]
+    fn synthetic_quote_verifies_with_mr_signer_identity() {
+        let dcap = SyntheticDcap::new();
+        let report = SyntheticReport {
+            mr_signer: [0x42; 32],
+            isv_prod_id: 7,
+            isv_svn: 3,
+            ..SyntheticReport::new(MR_ENCLAVE, USER_DATA)
+        };
+        let identity = TrustedMrSignerIdentity::new(
+            MrSigner::from([0x42; 32]),
+            7.into(),
+            3.into(),
+            [""; 0],
+            [""; 0],
+        );
+
+        let verification = verify(
+            dcap.quote_for(&report),
+            dcap.collateral(),
+            &[identity.into()],
+            dcap.root_ca(),
+        );
+        assert_eq!(verification.is_success().unwrap_u8(), 1);
+    }
+
+    #[test]
+    fn synthetic_quote_for_other_mr_enclave_fails() {
+        let dcap = SyntheticDcap::new();
+        let quote = dcap.quote([0x01; 32], USER_DATA);
+        let verification = verify(
+            quote,
+            dcap.collateral(),
+            &[mr_enclave_identity(MR_ENCLAVE).into()],
+            dcap.root_ca(),
+        );
+        assert_eq!(verification.is_success().unwrap_u8(), 0);
+    }
+
+    #[test]
+    fn tampered_synthetic_quote_fails() {
+        let dcap = SyntheticDcap::new();
+        let mut quote_bytes = dcap.quote(MR_ENCLAVE, USER_DATA).as_ref().to_vec();
+        // flip a bit of the report data, which invalidates the ISV enclave signature
+        quote_bytes[48 + 320] ^= 1;
+        let quote = Quote3::try_from(quote_bytes).expect("failed to parse quote");
+
+        let verification = verify(
+            quote,
+            dcap.collateral(),
+            &[mr_enclave_identity(MR_ENCLAVE).into()],
+            dcap.root_ca(),
+        );
+        assert_eq!(verification.is_success().unwrap_u8(), 0);
+    }
+
+    #[test]
+    fn tcb_info_for_other_fmspc_is_rejected() {
+        let dcap = SyntheticDcap::new();
+        let other = SyntheticDcap::new().with_fmspc([0x00, 0x90, 0x6e, 0xd5, 0x00, 0x00]);
+        let quote = dcap.quote(MR_ENCLAVE, USER_DATA);
+
+        assert!(Evidence::new(quote, other.collateral()).is_err());
+    }
+
+    #[test]
+    fn advisories_must_be_allowed() {
+        let dcap = SyntheticDcap::new().with_tcb_status("SWHardeningNeeded", &["INTEL-SA-00615"]);
+        let quote = dcap.quote(MR_ENCLAVE, USER_DATA);
+        let verification = verify(
+            quote.clone(),
+            dcap.collateral(),
+            &[mr_enclave_identity(MR_ENCLAVE).into()],
+            dcap.root_ca(),
+        );
+        assert_eq!(verification.is_success().unwrap_u8(), 0);
+
+        let identity =
+            TrustedMrEnclaveIdentity::new(MrEnclave::from(MR_ENCLAVE), [""; 0], ["INTEL-SA-00615"]);
+        let verification = verify(quote, dcap.collateral(), &[identity.into()], dcap.root_ca());
+        assert_eq!(verification.is_success().unwrap_u8(), 1);
+    }
+
+    #[test]
+    fn pki_is_deterministic() {
+        let dcap = SyntheticDcap::new();
+        assert_eq!(dcap.root_ca_pem(), SyntheticDcap::new().root_ca_pem());
+        assert_eq!(dcap.tcb_info(), SyntheticDcap::new().tcb_info());
+        assert_ne!(
+            dcap.root_ca_pem(),
+            SyntheticDcap::from_seed(b"other").root_ca_pem()
+        );
+    }
+
+    #[test]
+    fn pki_chains_up_to_root_ca() {
+        let dcap = SyntheticDcap::new();
+        let verifier = TlsCertificateChainVerifier::new(dcap.root_ca());
+        let crls = [dcap.root_ca_crl(), dcap.pck_crl()];
+        for chain in [
+            [dcap.pck_cert(), dcap.pck_ca(), dcap.root_ca()].as_slice(),
+            [dcap.tcb_signer(), dcap.root_ca()].as_slice(),
+        ] {
+            assert!(verifier
+                .verify_certificate_chain(chain.iter(), crls.iter(), None)
+                .is_ok());
+        }
+    }
+}
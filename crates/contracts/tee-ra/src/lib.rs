@@ -18,4 +18,10 @@
 
 pub mod intel_sgx;
 
-pub use intel_sgx::{dcap::verify as verify_dcap_attestation, Error};
+pub use intel_sgx::{
+    dcap::{
+        verify as verify_dcap_attestation,
+        verify_with_policy as verify_dcap_attestation_with_policy,
+    },
+    Error,
+};
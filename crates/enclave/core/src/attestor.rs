@@ -1,25 +1,34 @@
 use std::{
-    error::Error,
     fs::{read, File},
     io::{Error as IoError, ErrorKind, Write},
 };
 
+use log::warn;
 use mc_sgx_dcap_sys_types::sgx_ql_qve_collateral_t;
 use quartz_contract_core::{
     msg::{
-        execute::attested::{
-            Attestation, DcapAttestation, HasUserData, MockAttestation, RawDcapAttestation,
-            RawMockAttestation,
+        execute::{
+            attested::{
+                Attestation, DcapAttestation, HasUserData, MockAttestation, RawDcapAttestation,
+                RawMockAttestation,
+            },
+            batched::{batch, Batched},
         },
         HasDomainType,
     },
     state::{MrEnclave, UserData},
 };
+#[cfg(feature = "synthetic-sgx")]
+use quartz_tee_ra::intel_sgx::dcap::synthetic::SyntheticDcap;
 use quartz_tee_ra::intel_sgx::dcap::{Collateral, Quote3Error};
-use reqwest::blocking::Client;
 use serde::Serialize;
 
-use crate::types::Fmspc;
+use crate::{
+    attestor::pccs::{PccsClient, PccsConfig, PccsError, PckCrl, QeIdentity},
+    types::Fmspc,
+};
+
+pub mod pccs;
 
 #[cfg(not(feature = "mock-sgx"))]
 pub type DefaultAttestor = DcapAttestor;
@@ -27,11 +36,17 @@ pub type DefaultAttestor = DcapAttestor;
 #[cfg(feature = "mock-sgx")]
 pub type DefaultAttestor = MockAttestor;
 
-const QE_IDENTITY_JSON: &str = include_str!("../data/qe_identity.json");
+/// The Intel SGX root CA that all collateral must chain up to (by default).
 const ROOT_CA: &str = include_str!("../data/root_ca.pem");
+
+// Collateral that is only used if the PCCS is unavailable
+const QE_IDENTITY_JSON: &str = include_str!("../data/qe_identity.json");
 const ROOT_CRL: &[u8] = include_bytes!("../data/root_crl.der");
 const TCB_SIGNER: &str = include_str!("../data/tcb_signer.pem");
 
+/// A single attestation for a batch of messages, along with each message and its inclusion proof.
+pub type BatchAttestation<A, M> = (A, Vec<Batched<M>>);
+
 /// The trait defines the interface for generating attestations from within an enclave.
 pub trait Attestor: Send + Sync + 'static {
     type Error: ToString;
@@ -43,12 +58,50 @@ pub trait Attestor: Send + Sync + 'static {
     fn mr_enclave(&self) -> Result<MrEnclave, Self::Error>;
 
     fn attestation(&self, user_data: impl HasUserData) -> Result<Self::Attestation, Self::Error>;
+
+    /// Generates a single attestation for all of `msgs`, i.e. for the Merkle root of their user
+    /// data, and returns it along with each message and its inclusion proof. Returns `None` if
+    /// `msgs` is empty.
+    fn batch_attestation<M: HasUserData>(
+        &self,
+        msgs: Vec<M>,
+    ) -> Result<Option<BatchAttestation<Self::Attestation, M>>, Self::Error> {
+        let Some((root, batched)) = batch(msgs) else {
+            return Ok(None);
+        };
+        Ok(Some((self.attestation(root)?, batched)))
+    }
 }
 
 /// An `Attestor` for generating DCAP attestations for Gramine based enclaves.
-#[derive(Clone, PartialEq, Debug)]
+///
+/// Collateral (i.e. the PCK CRL, QE identity, root CA CRL and their issuer chains) is fetched from
+/// the configured PCCS, verified against the pinned SGX root CA and reused across attestations
+/// (and clones) until its cache TTL expires. If the PCCS is unavailable, the QE identity and root
+/// CA CRL fall back to the ones embedded in the enclave.
+#[derive(Clone, Debug)]
 pub struct DcapAttestor {
     pub fmspc: Fmspc,
+    pccs: PccsClient,
+}
+
+impl DcapAttestor {
+    /// Creates an attestor that uses a PCCS with the default config, i.e. a local PCCS at
+    /// `https://127.0.0.1:8081`.
+    pub fn new(fmspc: Fmspc) -> Self {
+        Self::with_pccs_config(fmspc, PccsConfig::default())
+    }
+
+    pub fn with_pccs_config(fmspc: Fmspc, pccs_config: PccsConfig) -> Self {
+        Self {
+            fmspc,
+            pccs: PccsClient::new(pccs_config),
+        }
+    }
+
+    pub fn pccs_config(&self) -> &PccsConfig {
+        self.pccs.config()
+    }
 }
 
 impl Attestor for DcapAttestor {
@@ -72,31 +125,11 @@ impl Attestor for DcapAttestor {
     }
 
     fn attestation(&self, user_data: impl HasUserData) -> Result<Self::Attestation, Self::Error> {
-        fn pccs_query_pck() -> Result<(Vec<u8>, String), Box<dyn Error>> {
-            let url = "https://127.0.0.1:8081/sgx/certification/v4/pckcrl?ca=processor";
-
-            let client = Client::builder()
-                .danger_accept_invalid_certs(true) // FIXME(hu55a1n1): required?
-                .build()?;
-            let response = client.get(url).send()?;
-
-            // Parse relevant headers
-            let pck_crl_issuer_chain = response
-                .headers()
-                .get("SGX-PCK-CRL-Issuer-Chain")
-                .ok_or("Missing PCK-Issuer-Chain header")?
-                .to_str()?
-                .to_string();
-
-            let pck_crl = response.bytes()?;
-
-            Ok((pck_crl.to_vec(), pck_crl_issuer_chain))
-        }
-
         fn collateral(
             tcb_info: &str,
-            pck_crl: Vec<u8>,
-            pck_crl_issuer_chain: String,
+            pck_crl: PckCrl,
+            qe_identity: QeIdentity,
+            root_crl: Vec<u8>,
         ) -> Collateral {
             let mut sgx_collateral = sgx_ql_qve_collateral_t::default();
 
@@ -106,22 +139,28 @@ impl Attestor for DcapAttestor {
             version.major_version = 3;
             version.minor_version = 1;
 
-            let mut root_crl = ROOT_CRL.to_vec();
+            let mut root_crl = root_crl;
             root_crl.push(0);
             sgx_collateral.root_ca_crl = root_crl.as_ptr() as _;
             sgx_collateral.root_ca_crl_size = root_crl.len() as u32;
 
-            let mut pck_crl = hex::decode(pck_crl).unwrap();
+            let PckCrl {
+                crl: mut pck_crl,
+                issuer_chain: pck_crl_issuer_chain,
+            } = pck_crl;
             pck_crl.push(0);
             sgx_collateral.pck_crl = pck_crl.as_ptr() as _;
             sgx_collateral.pck_crl_size = pck_crl.len() as u32;
 
-            let pck_crl_issuer_chain = urlencoding::decode(&pck_crl_issuer_chain).unwrap();
             // pck_crl_issuer_chain.push(0);
             sgx_collateral.pck_crl_issuer_chain = pck_crl_issuer_chain.as_ptr() as _;
             sgx_collateral.pck_crl_issuer_chain_size = pck_crl_issuer_chain.len() as u32;
 
-            let mut tcb_chain = [TCB_SIGNER, ROOT_CA].join("\n").as_bytes().to_vec();
+            let QeIdentity {
+                qe_identity,
+                issuer_chain,
+            } = qe_identity;
+            let mut tcb_chain = issuer_chain.into_bytes();
             tcb_chain.push(0);
             sgx_collateral.tcb_info_issuer_chain = tcb_chain.as_ptr() as _;
             sgx_collateral.tcb_info_issuer_chain_size = tcb_chain.len() as u32;
@@ -133,18 +172,44 @@ impl Attestor for DcapAttestor {
             sgx_collateral.qe_identity_issuer_chain = tcb_chain.as_ptr() as _;
             sgx_collateral.qe_identity_issuer_chain_size = tcb_chain.len() as u32;
 
-            sgx_collateral.qe_identity = QE_IDENTITY_JSON.as_ptr() as _;
-            sgx_collateral.qe_identity_size = QE_IDENTITY_JSON.len() as u32;
+            sgx_collateral.qe_identity = qe_identity.as_ptr() as _;
+            sgx_collateral.qe_identity_size = qe_identity.len() as u32;
 
             Collateral::try_from(&sgx_collateral).expect("Failed to parse collateral")
         }
 
+        /// Falls back to the `embedded` collateral if the PCCS is unavailable, but not if it
+        /// served invalid collateral.
+        fn or_embedded<T>(
+            fetched: Result<T, PccsError>,
+            name: &str,
+            embedded: impl FnOnce() -> T,
+        ) -> Result<T, IoError> {
+            match fetched {
+                Ok(fetched) => Ok(fetched),
+                Err(e) if e.is_unavailable() => {
+                    warn!("Failed to fetch {name} from PCCS, using embedded {name}: {e}");
+                    Ok(embedded())
+                }
+                Err(e) => Err(IoError::new(ErrorKind::Other, e.to_string())),
+            }
+        }
+
         let quote = self.quote(user_data)?;
 
         let collateral = {
-            let (pck_crl, pck_crl_issuer_chain) =
-                pccs_query_pck().map_err(|e| IoError::new(ErrorKind::Other, e.to_string()))?;
-            collateral(&self.fmspc.to_string(), pck_crl, pck_crl_issuer_chain)
+            let pck_crl = self
+                .pccs
+                .pck_crl()
+                .map_err(|e| IoError::new(ErrorKind::Other, e.to_string()))?;
+            let qe_identity = or_embedded(self.pccs.qe_identity(), "QE identity", || QeIdentity {
+                qe_identity: QE_IDENTITY_JSON.to_string(),
+                issuer_chain: [TCB_SIGNER, ROOT_CA].join("\n"),
+            })?;
+            let root_crl =
+                or_embedded(self.pccs.root_ca_crl(), "root CA CRL", || ROOT_CRL.to_vec())?;
+            // the contract fills in the TCB info for the FMSPC in the quote's PCK certificate
+            collateral(&self.fmspc.to_string(), pck_crl, qe_identity, root_crl)
         };
 
         Ok(DcapAttestation::new(
@@ -156,6 +221,63 @@ impl Attestor for DcapAttestor {
     }
 }
 
+/// An `Attestor` that generates synthetic DCAP attestations for a fixed MRENCLAVE. (only meant for
+/// testing purposes)
+///
+/// Unlike the `MockAttestor`, the generated attestations are real `DcapAttestation`s that go
+/// through the full DCAP verification path, but they're signed by a test PKI (see
+/// [`SyntheticDcap`]) instead of SGX hardware. Verification only succeeds if the synthetic root CA
+/// and TCB info are trusted, e.g. by registering `SyntheticDcap::tcb_info()` with a tcbinfo
+/// contract that was instantiated with `SyntheticDcap::root_ca_pem()`.
+#[cfg(feature = "synthetic-sgx")]
+#[derive(Clone, Debug)]
+pub struct SyntheticDcapAttestor {
+    pub mr_enclave: MrEnclave,
+    dcap: SyntheticDcap,
+}
+
+#[cfg(feature = "synthetic-sgx")]
+impl SyntheticDcapAttestor {
+    pub fn new(mr_enclave: MrEnclave) -> Self {
+        Self::with_dcap(mr_enclave, SyntheticDcap::default())
+    }
+
+    pub fn with_dcap(mr_enclave: MrEnclave, dcap: SyntheticDcap) -> Self {
+        Self { mr_enclave, dcap }
+    }
+
+    pub fn dcap(&self) -> &SyntheticDcap {
+        &self.dcap
+    }
+}
+
+#[cfg(feature = "synthetic-sgx")]
+impl Attestor for SyntheticDcapAttestor {
+    type Error = String;
+    type Attestation = DcapAttestation;
+    type RawAttestation = RawDcapAttestation;
+
+    fn quote(&self, user_data: impl HasUserData) -> Result<Vec<u8>, Self::Error> {
+        let quote = self.dcap.quote(self.mr_enclave, user_data.user_data());
+        Ok(quote.as_ref().to_vec())
+    }
+
+    fn mr_enclave(&self) -> Result<MrEnclave, Self::Error> {
+        Ok(self.mr_enclave)
+    }
+
+    fn attestation(&self, user_data: impl HasUserData) -> Result<Self::Attestation, Self::Error> {
+        let quote = self.dcap.quote(self.mr_enclave, user_data.user_data());
+
+        // Like the `DcapAttestor`, we only send the FMSPC in place of the TCB info, which the
+        // contract looks up by the FMSPC in the quote's PCK certificate
+        let fmspc = Fmspc(self.dcap.fmspc());
+        let collateral = self.dcap.collateral_with_tcb_info(&fmspc.to_string());
+
+        Ok(DcapAttestation::new(quote, collateral))
+    }
+}
+
 /// A mock `Attestor` that creates a quote consisting of just the user report data. (only meant for
 /// testing purposes)
 #[derive(Clone, PartialEq, Debug, Default)]
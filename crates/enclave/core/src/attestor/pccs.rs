@@ -0,0 +1,580 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use displaydoc::Display;
+use p256::ecdsa::{signature::Verifier, Signature, VerifyingKey};
+use reqwest::{
+    blocking::{Client, Response},
+    Certificate, Url,
+};
+use serde::Deserialize;
+use serde_json::value::RawValue;
+use x509_parser::{
+    certificate::X509Certificate, parse_x509_certificate, parse_x509_crl, pem::Pem,
+    x509::SubjectPublicKeyInfo,
+};
+
+use crate::attestor::ROOT_CA;
+
+/// The PCK CRL and its issuer chain (PEM) as served by a PCCS.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PckCrl {
+    /// DER encoded CRL
+    pub crl: Vec<u8>,
+    pub issuer_chain: String,
+}
+
+/// The (signed) QE identity JSON and its issuer chain (PEM) as served by a PCCS.
+///
+/// The QE identity is signed by the TCB signing key, so the issuer chain is also the TCB info
+/// issuer chain.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QeIdentity {
+    pub qe_identity: String,
+    pub issuer_chain: String,
+}
+
+/// How the PCCS' TLS certificate is verified.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum PccsTls {
+    /// Accept any certificate, e.g. for a local PCCS with a self-signed certificate.
+    AcceptInvalidCerts,
+    /// Verify the certificate against the built-in (webpki) roots.
+    #[default]
+    BuiltInRoots,
+    /// Verify the certificate against the given PEM encoded root certificate only.
+    PinnedRoot(Vec<u8>),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PccsConfig {
+    /// Base URL of the PCCS, e.g. `https://127.0.0.1:8081`
+    pub url: Url,
+    pub tls: PccsTls,
+    /// How long fetched collateral is reused before it is fetched again
+    pub cache_ttl: Duration,
+    /// PEM encoded root certificate that all fetched collateral must chain up to (i.e. the
+    /// Intel SGX root CA)
+    pub sgx_root_ca: String,
+}
+
+impl Default for PccsConfig {
+    fn default() -> Self {
+        Self {
+            url: "https://127.0.0.1:8081".parse().expect("hardcoded URL"),
+            tls: PccsTls::default(),
+            cache_ttl: Duration::from_secs(60 * 60),
+            sgx_root_ca: ROOT_CA.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Display)]
+pub enum PccsError {
+    /// invalid PCCS URL: {0}
+    InvalidUrl(String),
+    /// invalid pinned PCCS root certificate: {0}
+    InvalidRootCertificate(reqwest::Error),
+    /// PCCS request failed: {0}
+    Http(reqwest::Error),
+    /// missing or invalid `{0}` header in PCCS response
+    InvalidHeader(&'static str),
+    /// invalid hex encoding in PCCS response: {0}
+    InvalidHex(hex::FromHexError),
+    /// invalid certificate: {0}
+    InvalidCertificate(String),
+    /// invalid CRL: {0}
+    InvalidCrl(String),
+    /// invalid QE identity: {0}
+    InvalidQeIdentity(String),
+    /// issuer chain doesn't end with the pinned SGX root CA
+    UntrustedRoot,
+    /// invalid signature on {0}
+    InvalidSignature(&'static str),
+}
+
+impl PccsError {
+    /// Returns true if the collateral couldn't be fetched at all (as opposed to being fetched and
+    /// found to be invalid).
+    pub fn is_unavailable(&self) -> bool {
+        matches!(self, Self::Http(_))
+    }
+}
+
+/// A client for fetching collateral from a PCCS (Provisioning Certificate Caching Service) that
+/// caches responses for the configured TTL.
+///
+/// All collateral is verified against the pinned SGX root CA before it is cached or returned.
+/// Clones share the same cache.
+#[derive(Clone, Debug)]
+pub struct PccsClient {
+    config: PccsConfig,
+    pck_crl: Arc<Cached<PckCrl>>,
+    qe_identity: Arc<Cached<QeIdentity>>,
+    root_ca_crl: Arc<Cached<Vec<u8>>>,
+}
+
+impl PccsClient {
+    const PCK_CRL_PATH: &'static str = "sgx/certification/v4/pckcrl";
+    const PCK_CRL_ISSUER_CHAIN_HEADER: &'static str = "SGX-PCK-CRL-Issuer-Chain";
+    const QE_IDENTITY_PATH: &'static str = "sgx/certification/v4/qe/identity";
+    const QE_IDENTITY_ISSUER_CHAIN_HEADER: &'static str = "SGX-Enclave-Identity-Issuer-Chain";
+    const ROOT_CA_CRL_PATH: &'static str = "sgx/certification/v4/rootcacrl";
+
+    pub fn new(config: PccsConfig) -> Self {
+        Self {
+            config,
+            pck_crl: Default::default(),
+            qe_identity: Default::default(),
+            root_ca_crl: Default::default(),
+        }
+    }
+
+    pub fn config(&self) -> &PccsConfig {
+        &self.config
+    }
+
+    /// Returns the processor PCK CRL, from the cache if it was fetched less than `cache_ttl` ago.
+    pub fn pck_crl(&self) -> Result<PckCrl, PccsError> {
+        self.pck_crl
+            .get_or_fetch(self.config.cache_ttl, || self.fetch_pck_crl())
+    }
+
+    /// Returns the QE identity, from the cache if it was fetched less than `cache_ttl` ago.
+    pub fn qe_identity(&self) -> Result<QeIdentity, PccsError> {
+        self.qe_identity
+            .get_or_fetch(self.config.cache_ttl, || self.fetch_qe_identity())
+    }
+
+    /// Returns the DER encoded root CA CRL, from the cache if it was fetched less than
+    /// `cache_ttl` ago.
+    pub fn root_ca_crl(&self) -> Result<Vec<u8>, PccsError> {
+        self.root_ca_crl
+            .get_or_fetch(self.config.cache_ttl, || self.fetch_root_ca_crl())
+    }
+
+    fn fetch_pck_crl(&self) -> Result<PckCrl, PccsError> {
+        let mut url = self.url(Self::PCK_CRL_PATH)?;
+        url.query_pairs_mut().append_pair("ca", "processor");
+
+        let response = self.get(url)?;
+        let issuer_chain = issuer_chain(&response, Self::PCK_CRL_ISSUER_CHAIN_HEADER)?;
+        let crl = hex::decode(response.bytes().map_err(PccsError::Http)?)
+            .map_err(PccsError::InvalidHex)?;
+
+        let chain = verify_issuer_chain(&issuer_chain, &self.config.sgx_root_ca)?;
+        verify_crl(&crl, leaf(&chain)?.public_key())?;
+
+        Ok(PckCrl { crl, issuer_chain })
+    }
+
+    fn fetch_qe_identity(&self) -> Result<QeIdentity, PccsError> {
+        let response = self.get(self.url(Self::QE_IDENTITY_PATH)?)?;
+        let issuer_chain = issuer_chain(&response, Self::QE_IDENTITY_ISSUER_CHAIN_HEADER)?;
+        let qe_identity = response.text().map_err(PccsError::Http)?;
+
+        let chain = verify_issuer_chain(&issuer_chain, &self.config.sgx_root_ca)?;
+        verify_qe_identity(&qe_identity, leaf(&chain)?.public_key())?;
+
+        Ok(QeIdentity {
+            qe_identity,
+            issuer_chain,
+        })
+    }
+
+    fn fetch_root_ca_crl(&self) -> Result<Vec<u8>, PccsError> {
+        let response = self.get(self.url(Self::ROOT_CA_CRL_PATH)?)?;
+        let crl = hex::decode(response.bytes().map_err(PccsError::Http)?)
+            .map_err(PccsError::InvalidHex)?;
+
+        let root = verify_issuer_chain(&self.config.sgx_root_ca, &self.config.sgx_root_ca)?;
+        verify_crl(&crl, leaf(&root)?.public_key())?;
+
+        Ok(crl)
+    }
+
+    fn get(&self, url: Url) -> Result<Response, PccsError> {
+        self.http_client()?
+            .get(url)
+            .send()
+            .and_then(|r| r.error_for_status())
+            .map_err(PccsError::Http)
+    }
+
+    fn url(&self, path: &str) -> Result<Url, PccsError> {
+        let base = self.config.url.as_str().trim_end_matches('/');
+        format!("{base}/{path}")
+            .parse::<Url>()
+            .map_err(|e| PccsError::InvalidUrl(e.to_string()))
+    }
+
+    // The (blocking) client is built on demand rather than kept around, as it must not be dropped
+    // from within an async context.
+    fn http_client(&self) -> Result<Client, PccsError> {
+        let builder = Client::builder();
+        let builder = match &self.config.tls {
+            PccsTls::AcceptInvalidCerts => builder.danger_accept_invalid_certs(true),
+            PccsTls::BuiltInRoots => builder,
+            PccsTls::PinnedRoot(pem) => {
+                let root = Certificate::from_pem(pem).map_err(PccsError::InvalidRootCertificate)?;
+                builder
+                    .tls_built_in_root_certs(false)
+                    .add_root_certificate(root)
+            }
+        };
+        builder.build().map_err(PccsError::Http)
+    }
+}
+
+/// A value that is reused until it is older than the TTL.
+#[derive(Debug)]
+struct Cached<T>(Mutex<Option<(Instant, T)>>);
+
+impl<T> Default for Cached<T> {
+    fn default() -> Self {
+        Self(Mutex::new(None))
+    }
+}
+
+impl<T: Clone> Cached<T> {
+    fn get_or_fetch(
+        &self,
+        ttl: Duration,
+        fetch: impl FnOnce() -> Result<T, PccsError>,
+    ) -> Result<T, PccsError> {
+        let mut cached = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((fetched_at, value)) = cached.as_ref() {
+            if fetched_at.elapsed() < ttl {
+                return Ok(value.clone());
+            }
+        }
+
+        let value = fetch()?;
+        *cached = Some((Instant::now(), value.clone()));
+        Ok(value)
+    }
+}
+
+fn issuer_chain(response: &Response, header: &'static str) -> Result<String, PccsError> {
+    Ok(response
+        .headers()
+        .get(header)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| urlencoding::decode(h).ok())
+        .ok_or(PccsError::InvalidHeader(header))?
+        .into_owned())
+}
+
+/// Verifies that every certificate in the PEM encoded `issuer_chain` is signed by the next one and
+/// that the last one is the pinned `root_ca`. Returns the DER encoded certificates.
+fn verify_issuer_chain(issuer_chain: &str, root_ca: &str) -> Result<Vec<Vec<u8>>, PccsError> {
+    fn pem_to_der(pem: &str) -> Result<Vec<Vec<u8>>, PccsError> {
+        Pem::iter_from_buffer(pem.as_bytes())
+            .map(|pem| pem.map(|pem| pem.contents))
+            .collect::<Result<_, _>>()
+            .map_err(|e| PccsError::InvalidCertificate(e.to_string()))
+    }
+
+    let chain = pem_to_der(issuer_chain)?;
+    let root = pem_to_der(root_ca)?;
+    if root.len() != 1 || chain.last() != root.first() {
+        return Err(PccsError::UntrustedRoot);
+    }
+
+    let certs = chain
+        .iter()
+        .map(|der| parse_certificate(der))
+        .collect::<Result<Vec<_>, _>>()?;
+    for pair in certs.windows(2) {
+        pair[0]
+            .verify_signature(Some(pair[1].public_key()))
+            .map_err(|_| PccsError::InvalidSignature("certificate"))?;
+    }
+
+    Ok(chain)
+}
+
+fn parse_certificate(der: &[u8]) -> Result<X509Certificate<'_>, PccsError> {
+    parse_x509_certificate(der)
+        .map(|(_, cert)| cert)
+        .map_err(|e| PccsError::InvalidCertificate(e.to_string()))
+}
+
+fn leaf(chain: &[Vec<u8>]) -> Result<X509Certificate<'_>, PccsError> {
+    let der = chain.first().ok_or(PccsError::UntrustedRoot)?;
+    parse_certificate(der)
+}
+
+fn verify_crl(crl: &[u8], issuer: &SubjectPublicKeyInfo<'_>) -> Result<(), PccsError> {
+    let (_, crl) = parse_x509_crl(crl).map_err(|e| PccsError::InvalidCrl(e.to_string()))?;
+    crl.verify_signature(issuer)
+        .map_err(|_| PccsError::InvalidSignature("CRL"))
+}
+
+/// Verifies the signature (i.e. a hex encoded raw ECDSA P-256 signature) over the exact bytes of
+/// the `enclaveIdentity` object.
+fn verify_qe_identity(
+    qe_identity: &str,
+    issuer: &SubjectPublicKeyInfo<'_>,
+) -> Result<(), PccsError> {
+    #[derive(Deserialize)]
+    struct SignedQeIdentity<'a> {
+        #[serde(borrow, rename = "enclaveIdentity")]
+        enclave_identity: &'a RawValue,
+        signature: String,
+    }
+
+    let signed: SignedQeIdentity<'_> = serde_json::from_str(qe_identity)
+        .map_err(|e| PccsError::InvalidQeIdentity(e.to_string()))?;
+    let signature = hex::decode(&signed.signature)
+        .map_err(PccsError::InvalidHex)
+        .and_then(|s| {
+            Signature::from_slice(&s).map_err(|e| PccsError::InvalidQeIdentity(e.to_string()))
+        })?;
+    let key = VerifyingKey::from_sec1_bytes(&issuer.subject_public_key.data)
+        .map_err(|e| PccsError::InvalidCertificate(e.to_string()))?;
+
+    key.verify(signed.enclave_identity.get().as_bytes(), &signature)
+        .map_err(|_| PccsError::InvalidSignature("QE identity"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        io::{BufRead, BufReader, Write},
+        net::TcpListener,
+        sync::atomic::{AtomicUsize, Ordering},
+        thread,
+    };
+
+    use super::*;
+
+    const PCK_CRL: &[u8] = include_bytes!("../../data/processor_crl.der");
+    const PROCESSOR_CA: &str = include_str!("../../data/processor_ca.pem");
+    const QE_IDENTITY: &str = include_str!("../../data/qe_identity.json");
+    const ROOT_CA: &str = include_str!("../../data/root_ca.pem");
+    const ROOT_CRL: &[u8] = include_bytes!("../../data/root_crl.der");
+    const TCB_SIGNER: &str = include_str!("../../data/tcb_signer.pem");
+
+    /// Response (issuer chain header and body) served by the PCCS stand-in for a request line
+    type Routes = HashMap<&'static str, (Option<(&'static str, String)>, String)>;
+
+    fn routes() -> Routes {
+        HashMap::from([
+            (
+                "GET /sgx/certification/v4/pckcrl?ca=processor ",
+                (
+                    Some(("SGX-PCK-CRL-Issuer-Chain", chain(&[PROCESSOR_CA, ROOT_CA]))),
+                    hex::encode(PCK_CRL),
+                ),
+            ),
+            (
+                "GET /sgx/certification/v4/qe/identity ",
+                (
+                    Some((
+                        "SGX-Enclave-Identity-Issuer-Chain",
+                        chain(&[TCB_SIGNER, ROOT_CA]),
+                    )),
+                    QE_IDENTITY.to_string(),
+                ),
+            ),
+            (
+                "GET /sgx/certification/v4/rootcacrl ",
+                (None, hex::encode(ROOT_CRL)),
+            ),
+        ])
+    }
+
+    fn chain(certs: &[&str]) -> String {
+        urlencoding::encode(&certs.join("\n")).into_owned()
+    }
+
+    /// Spawns a minimal PCCS stand-in that serves the given routes and returns its base URL along
+    /// with a counter of the requests it has served.
+    fn spawn_pccs(routes: Routes) -> (Url, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+        let url = format!("http://{}", listener.local_addr().expect("local addr"))
+            .parse()
+            .expect("valid url");
+        let requests = Arc::new(AtomicUsize::new(0));
+
+        let served = requests.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.expect("incoming connection");
+
+                let mut request_line = String::new();
+                let mut reader = BufReader::new(&stream);
+                reader.read_line(&mut request_line).expect("request line");
+                while reader.read_line(&mut String::new()).expect("header") > 2 {}
+                served.fetch_add(1, Ordering::SeqCst);
+
+                let route = routes
+                    .iter()
+                    .find(|(prefix, _)| request_line.starts_with(*prefix));
+                let response = match route {
+                    Some((_, (header, body))) => {
+                        let header = header
+                            .as_ref()
+                            .map(|(name, value)| format!("{name}: {value}\r\n"))
+                            .unwrap_or_default();
+                        format!(
+                            "HTTP/1.1 200 OK\r\n{header}Content-Length: {}\r\n\
+                             Connection: close\r\n\r\n{body}",
+                            body.len()
+                        )
+                    }
+                    None => {
+                        "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                            .to_string()
+                    }
+                };
+                stream
+                    .write_all(response.as_bytes())
+                    .expect("write response");
+            }
+        });
+
+        (url, requests)
+    }
+
+    fn pccs_client(url: Url, cache_ttl: Duration) -> PccsClient {
+        PccsClient::new(PccsConfig {
+            url,
+            tls: PccsTls::BuiltInRoots,
+            cache_ttl,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_fetch_pck_crl() {
+        let (url, _) = spawn_pccs(routes());
+        let pck_crl = pccs_client(url, Duration::from_secs(60))
+            .pck_crl()
+            .expect("pck crl");
+
+        assert_eq!(pck_crl.crl, PCK_CRL);
+        assert_eq!(pck_crl.issuer_chain, [PROCESSOR_CA, ROOT_CA].join("\n"));
+    }
+
+    #[test]
+    fn test_pck_crl_is_cached_across_clones() {
+        let (url, requests) = spawn_pccs(routes());
+        let client = pccs_client(url, Duration::from_secs(60));
+
+        let first = client.pck_crl().expect("pck crl");
+        let second = client.clone().pck_crl().expect("cached pck crl");
+
+        assert_eq!(first, second);
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_pck_crl_is_refetched_after_ttl() {
+        let (url, requests) = spawn_pccs(routes());
+        let client = pccs_client(url, Duration::ZERO);
+
+        client.pck_crl().expect("pck crl");
+        client.pck_crl().expect("refetched pck crl");
+
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_pck_crl_error_is_not_cached() {
+        let (url, requests) = spawn_pccs(routes());
+        let client = pccs_client(
+            url.join("/unknown/").expect("valid url"),
+            Duration::from_secs(60),
+        );
+
+        assert!(matches!(client.pck_crl(), Err(e) if e.is_unavailable()));
+        assert!(matches!(client.pck_crl(), Err(e) if e.is_unavailable()));
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_pck_crl_signed_by_wrong_issuer_is_rejected() {
+        let mut routes = routes();
+        routes
+            .get_mut("GET /sgx/certification/v4/pckcrl?ca=processor ")
+            .expect("pck crl route")
+            .0 = Some(("SGX-PCK-CRL-Issuer-Chain", chain(&[TCB_SIGNER, ROOT_CA])));
+        let (url, _) = spawn_pccs(routes);
+
+        assert!(matches!(
+            pccs_client(url, Duration::from_secs(60)).pck_crl(),
+            Err(PccsError::InvalidSignature("CRL"))
+        ));
+    }
+
+    #[test]
+    fn test_fetch_qe_identity() {
+        let (url, _) = spawn_pccs(routes());
+        let qe_identity = pccs_client(url, Duration::from_secs(60))
+            .qe_identity()
+            .expect("qe identity");
+
+        assert_eq!(qe_identity.qe_identity, QE_IDENTITY);
+        assert_eq!(qe_identity.issuer_chain, [TCB_SIGNER, ROOT_CA].join("\n"));
+    }
+
+    #[test]
+    fn test_tampered_qe_identity_is_rejected() {
+        let mut routes = routes();
+        routes
+            .get_mut("GET /sgx/certification/v4/qe/identity ")
+            .expect("qe identity route")
+            .1 = QE_IDENTITY.replacen("\"isvprodid\":1", "\"isvprodid\":2", 1);
+        let (url, _) = spawn_pccs(routes);
+
+        assert!(matches!(
+            pccs_client(url, Duration::from_secs(60)).qe_identity(),
+            Err(PccsError::InvalidSignature("QE identity"))
+        ));
+    }
+
+    #[test]
+    fn test_qe_identity_chain_must_end_with_pinned_root() {
+        let mut routes = routes();
+        routes
+            .get_mut("GET /sgx/certification/v4/qe/identity ")
+            .expect("qe identity route")
+            .0 = Some(("SGX-Enclave-Identity-Issuer-Chain", chain(&[TCB_SIGNER])));
+        let (url, _) = spawn_pccs(routes);
+
+        assert!(matches!(
+            pccs_client(url, Duration::from_secs(60)).qe_identity(),
+            Err(PccsError::UntrustedRoot)
+        ));
+    }
+
+    #[test]
+    fn test_fetch_root_ca_crl() {
+        let (url, _) = spawn_pccs(routes());
+        let root_ca_crl = pccs_client(url, Duration::from_secs(60))
+            .root_ca_crl()
+            .expect("root ca crl");
+
+        assert_eq!(root_ca_crl, ROOT_CRL);
+    }
+
+    #[test]
+    fn test_root_ca_crl_signed_by_wrong_issuer_is_rejected() {
+        let mut routes = routes();
+        routes
+            .get_mut("GET /sgx/certification/v4/rootcacrl ")
+            .expect("root ca crl route")
+            .1 = hex::encode(PCK_CRL);
+        let (url, _) = spawn_pccs(routes);
+
+        assert!(matches!(
+            pccs_client(url, Duration::from_secs(60)).root_ca_crl(),
+            Err(PccsError::InvalidSignature("CRL"))
+        ));
+    }
+}
@@ -1,6 +1,8 @@
 use std::fmt::Display;
 
+use quartz_cw_proof::proof::key::StorageKey;
 use serde::{de::DeserializeOwned, Serialize};
+use tendermint::{block::Height, Hash};
 
 pub mod default;
 
@@ -19,12 +21,24 @@ pub trait ChainClient: Send + Sync + 'static {
         query: impl Into<Self::Query> + Send,
     ) -> Result<R, Self::Error>;
 
+    /// Sets the block (by height and hash) that subsequent proofs are generated from, e.g. the
+    /// latest block verified by the enclave.
+    async fn set_trusted_block(&self, height: Height, hash: Hash) -> Result<(), Self::Error>;
+
     async fn existence_proof(
         &self,
         contract: &Self::Contract,
         storage_key: &str,
     ) -> Result<Self::Proof, Self::Error>;
 
+    /// Returns a proof for all `storage_keys` at the same height (e.g. for verification with a
+    /// `MultiProofOfPublication`).
+    async fn existence_proofs(
+        &self,
+        contract: &Self::Contract,
+        storage_keys: &[StorageKey],
+    ) -> Result<Self::Proof, Self::Error>;
+
     async fn send_tx<T: Serialize + Send + Sync>(
         &self,
         contract: &Self::Contract,
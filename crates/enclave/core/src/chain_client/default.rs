@@ -1,7 +1,8 @@
 use anyhow::anyhow;
 use cosmrs::{crypto::secp256k1::SigningKey, AccountId};
-use cw_client::{CwClient, GrpcClient};
+use cw_client::{grpc::TxResult, CwClient, GrpcClient};
 use futures_util::StreamExt;
+use quartz_cw_proof::proof::key::StorageKey;
 use quartz_tm_prover::{
     config::{Config as TmProverConfig, ProofOutput},
     prover::prove,
@@ -11,6 +12,7 @@ use serde::{de::DeserializeOwned, Serialize};
 use serde_json::{json, Value};
 use tendermint::{block::Height, chain::Id as TmChainId, Hash};
 use tendermint_rpc::{query::EventType, SubscriptionClient, WebSocketClient};
+use tokio::sync::RwLock;
 
 use crate::chain_client::ChainClient;
 
@@ -19,8 +21,7 @@ pub struct DefaultChainClient {
     grpc_client: GrpcClient,
     node_url: Url,
     ws_url: Url,
-    trusted_height: Height,
-    trusted_hash: Hash,
+    trusted_block: RwLock<(Height, Hash)>,
 }
 
 impl DefaultChainClient {
@@ -38,8 +39,7 @@ impl DefaultChainClient {
             grpc_client: GrpcClient::new(signer, grpc_url),
             node_url,
             ws_url,
-            trusted_height,
-            trusted_hash,
+            trusted_block: RwLock::new((trusted_height, trusted_hash)),
         }
     }
 }
@@ -68,7 +68,7 @@ impl ChainClient for DefaultChainClient {
     type Proof = ProofOutput;
     type Query = Query;
     type TxConfig = DefaultTxConfig;
-    type TxOutput = String;
+    type TxOutput = TxResult;
 
     async fn query_contract<R: DeserializeOwned + Default + Send>(
         &self,
@@ -81,19 +81,40 @@ impl ChainClient for DefaultChainClient {
         }
     }
 
+    async fn set_trusted_block(&self, height: Height, hash: Hash) -> Result<(), Self::Error> {
+        *self.trusted_block.write().await = (height, hash);
+        Ok(())
+    }
+
     async fn existence_proof(
         &self,
         contract: &Self::Contract,
         storage_key: &str,
     ) -> Result<Self::Proof, Self::Error> {
+        self.existence_proofs(contract, &[StorageKey::new(storage_key, None)])
+            .await
+    }
+
+    async fn existence_proofs(
+        &self,
+        contract: &Self::Contract,
+        storage_keys: &[StorageKey],
+    ) -> Result<Self::Proof, Self::Error> {
+        let Some((storage_key, additional_storage_keys)) = storage_keys.split_first() else {
+            return Err(anyhow!("no storage keys to prove"));
+        };
+
+        let (trusted_height, trusted_hash) = *self.trusted_block.read().await;
         let prover_config = TmProverConfig {
             primary: self.node_url.as_str().parse()?,
             witnesses: self.node_url.as_str().parse()?,
-            trusted_height: self.trusted_height,
-            trusted_hash: self.trusted_hash,
+            trusted_height,
+            trusted_hash,
             verbose: "1".parse()?,
             contract_address: contract.clone(),
-            storage_key: storage_key.to_string(),
+            storage_key: storage_key.key.clone(),
+            storage_namespace: storage_key.namespace.clone(),
+            additional_storage_keys: additional_storage_keys.to_vec(),
             chain_id: self.chain_id.to_string(),
             ..Default::default()
         };
@@ -118,14 +139,7 @@ impl ChainClient for DefaultChainClient {
         config: Self::TxConfig,
     ) -> Result<Self::TxOutput, Self::Error> {
         self.grpc_client
-            .tx_execute(
-                contract,
-                &self.chain_id,
-                config.gas,
-                "",
-                json!(tx),
-                &config.amount,
-            )
+            .submit_tx(contract, &self.chain_id, config.gas, json!(tx))
             .await
     }
 
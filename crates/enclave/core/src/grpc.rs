@@ -1,12 +1,16 @@
 use cosmrs::AccountId;
 use quartz_proto::quartz::{
     core_server::Core, InstantiateRequest, InstantiateResponse, SessionCreateRequest,
-    SessionCreateResponse, SessionSetPubKeyRequest, SessionSetPubKeyResponse,
+    SessionCreateResponse, SessionHandoverAcceptRequest, SessionHandoverAcceptResponse,
+    SessionHandoverCompleteRequest, SessionHandoverCompleteResponse, SessionHandoverRequestRequest,
+    SessionHandoverRequestResponse, SessionRotatePubKeyRequest, SessionRotatePubKeyResponse,
+    SessionSetPubKeyRequest, SessionSetPubKeyResponse,
 };
 use tonic::{Request, Response, Status};
 
 use crate::{
-    attestor::Attestor, handler::Handler, key_manager::KeyManager, store::Store, DefaultEnclave,
+    attestor::Attestor, handler::Handler, key_manager::HandoverKeyManager, store::Store,
+    DefaultEnclave,
 };
 
 #[async_trait::async_trait]
@@ -30,7 +34,7 @@ impl<C, A, K, S> Core for DefaultEnclave<C, A, K, S>
 where
     C: Send + Sync + 'static,
     A: Attestor + Clone,
-    K: KeyManager + Clone,
+    K: HandoverKeyManager + Clone,
     S: Store<Contract = AccountId> + Clone,
 {
     async fn instantiate(
@@ -53,4 +57,32 @@ where
     ) -> Result<Response<SessionSetPubKeyResponse>, Status> {
         request.handle(self).await
     }
+
+    async fn session_rotate_pub_key(
+        &self,
+        request: Request<SessionRotatePubKeyRequest>,
+    ) -> Result<Response<SessionRotatePubKeyResponse>, Status> {
+        request.handle(self).await
+    }
+
+    async fn session_handover_request(
+        &self,
+        request: Request<SessionHandoverRequestRequest>,
+    ) -> Result<Response<SessionHandoverRequestResponse>, Status> {
+        request.handle(self).await
+    }
+
+    async fn session_handover_complete(
+        &self,
+        request: Request<SessionHandoverCompleteRequest>,
+    ) -> Result<Response<SessionHandoverCompleteResponse>, Status> {
+        request.handle(self).await
+    }
+
+    async fn session_handover_accept(
+        &self,
+        request: Request<SessionHandoverAcceptRequest>,
+    ) -> Result<Response<SessionHandoverAcceptResponse>, Status> {
+        request.handle(self).await
+    }
 }
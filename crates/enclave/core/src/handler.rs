@@ -2,17 +2,29 @@ use cosmrs::AccountId;
 use k256::ecdsa::VerifyingKey;
 use quartz_proto::quartz::{
     InstantiateRequest, InstantiateResponse, SessionCreateRequest, SessionCreateResponse,
-    SessionSetPubKeyRequest, SessionSetPubKeyResponse,
+    SessionHandoverAcceptRequest, SessionHandoverAcceptResponse, SessionHandoverCompleteRequest,
+    SessionHandoverCompleteResponse, SessionHandoverRequestRequest, SessionHandoverRequestResponse,
+    SessionRotatePubKeyRequest, SessionRotatePubKeyResponse, SessionSetPubKeyRequest,
+    SessionSetPubKeyResponse,
 };
 use tonic::Status;
 
-use crate::{attestor::Attestor, key_manager::KeyManager, store::Store, Enclave};
+use crate::{
+    attestor::Attestor,
+    key_manager::{HandoverKeyManager, KeyManager},
+    store::Store,
+    Enclave,
+};
 
 pub type A<E> = <<E as Enclave>::Attestor as Attestor>::Attestation;
 pub type RA<E> = <<E as Enclave>::Attestor as Attestor>::RawAttestation;
 
 pub mod instantiate;
 pub mod session_create;
+pub mod session_handover_accept;
+pub mod session_handover_complete;
+pub mod session_handover_request;
+pub mod session_rotate_pubkey;
 pub mod session_set_pubkey;
 
 #[async_trait::async_trait]
@@ -28,6 +40,10 @@ pub enum CoreEnclaveRequest {
     Instantiate(InstantiateRequest),
     SessionCreate(SessionCreateRequest),
     SessionSetPubKey(SessionSetPubKeyRequest),
+    SessionRotatePubKey(SessionRotatePubKeyRequest),
+    SessionHandoverRequest(SessionHandoverRequestRequest),
+    SessionHandoverComplete(SessionHandoverCompleteRequest),
+    SessionHandoverAccept(SessionHandoverAcceptRequest),
 }
 
 #[derive(Clone, Debug)]
@@ -35,13 +51,17 @@ pub enum CoreEnclaveResponse {
     Instantiate(InstantiateResponse),
     SessionCreate(SessionCreateResponse),
     SessionSetPubKey(SessionSetPubKeyResponse),
+    SessionRotatePubKey(SessionRotatePubKeyResponse),
+    SessionHandoverRequest(SessionHandoverRequestResponse),
+    SessionHandoverComplete(SessionHandoverCompleteResponse),
+    SessionHandoverAccept(SessionHandoverAcceptResponse),
 }
 
 #[async_trait::async_trait]
 impl<E: Enclave> Handler<E> for CoreEnclaveRequest
 where
     E: Enclave,
-    E::KeyManager: KeyManager<PubKey = VerifyingKey>,
+    E::KeyManager: HandoverKeyManager + KeyManager<PubKey = VerifyingKey>,
     E::Store: Store<Contract = AccountId>,
 {
     type Error = Status;
@@ -60,6 +80,22 @@ where
                 .handle(ctx)
                 .await
                 .map(CoreEnclaveResponse::SessionSetPubKey),
+            CoreEnclaveRequest::SessionRotatePubKey(req) => req
+                .handle(ctx)
+                .await
+                .map(CoreEnclaveResponse::SessionRotatePubKey),
+            CoreEnclaveRequest::SessionHandoverRequest(req) => req
+                .handle(ctx)
+                .await
+                .map(CoreEnclaveResponse::SessionHandoverRequest),
+            CoreEnclaveRequest::SessionHandoverComplete(req) => req
+                .handle(ctx)
+                .await
+                .map(CoreEnclaveResponse::SessionHandoverComplete),
+            CoreEnclaveRequest::SessionHandoverAccept(req) => req
+                .handle(ctx)
+                .await
+                .map(CoreEnclaveResponse::SessionHandoverAccept),
         }
     }
 }
@@ -83,3 +119,171 @@ pub fn ensure_seq_num_consistency(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use k256::ecdsa::SigningKey;
+    use quartz_contract_core::state::{
+        Config, MrEnclave, Nonce, PendingHandover, Session, PENDING_HANDOVER_KEY, SESSION_KEY,
+    };
+    use quartz_cw_proof::proof::key::CwAbciKey;
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::{
+        attestor::MockAttestor,
+        key_manager::{default::DefaultKeyManager, shared::SharedKeyManager, RotatingKeyManager},
+        proof_of_publication::tests::{cw_proofs, trusted_light_block, trusting_light_client_opts},
+        store::default::DefaultStore,
+        types::SessionHandoverState,
+        DefaultEnclave,
+    };
+
+    type TestEnclave =
+        DefaultEnclave<(), MockAttestor, SharedKeyManager<DefaultKeyManager>, DefaultStore>;
+
+    fn enclave() -> TestEnclave {
+        DefaultEnclave {
+            attestor: MockAttestor,
+            key_manager: SharedKeyManager::wrapping(DefaultKeyManager::default()),
+            store: DefaultStore::default(),
+            ctx: (),
+        }
+    }
+
+    async fn pub_key(enclave: &TestEnclave) -> Vec<u8> {
+        enclave.key_manager.pub_key().await.into()
+    }
+
+    #[tokio::test]
+    async fn session_handover_moves_the_session_key_to_the_new_enclave() {
+        let contract = AccountId::new("wasm", &[1; 20]).expect("valid contract address");
+        let nonce: Nonce = [7; 32];
+        let mr_enclave: MrEnclave = MockAttestor.mr_enclave().expect("mock mr_enclave");
+
+        let (old, new) = (enclave(), enclave());
+
+        // the contract's state once the new enclave's handover request was executed (which all
+        // steps are proven against for simplicity)
+        let session = Session::create(nonce)
+            .with_pub_key(nonce, pub_key(&old).await)
+            .expect("valid session transition")
+            .with_holder(mr_enclave);
+        let pending = PendingHandover::new(mr_enclave, pub_key(&new).await);
+        let (app_hash, proofs) = cw_proofs([
+            (
+                CwAbciKey::new(contract.clone(), SESSION_KEY.to_string(), None),
+                serde_json::to_vec(&session).expect("infallible serializer"),
+            ),
+            (
+                CwAbciKey::new(contract.clone(), PENDING_HANDOVER_KEY.to_string(), None),
+                serde_json::to_vec(&pending).expect("infallible serializer"),
+            ),
+        ]);
+        let light_block = trusted_light_block(app_hash);
+        let config = Config::new(
+            mr_enclave,
+            trusting_light_client_opts(&light_block),
+            None,
+            None,
+        );
+        for enclave in [&old, &new] {
+            enclave
+                .store
+                .set_config(config.clone())
+                .await
+                .expect("infallible store");
+        }
+        let session_proof = json!({
+            "light_client_proof": [light_block],
+            "merkle_proof": proofs[0],
+            "msg": null,
+        });
+        let multi_proof = json!({
+            "light_client_proof": [light_block],
+            "merkle_proof": proofs[0],
+            "additional_merkle_proofs": [proofs[1]],
+            "msg": null,
+        })
+        .to_string();
+
+        // the old enclave holds the session
+        old.store
+            .set_contract(contract.clone())
+            .await
+            .expect("infallible store");
+        old.store.set_nonce(nonce).await.expect("infallible store");
+        old.store.inc_seq_num(3).await.expect("infallible store");
+
+        // 1. the new enclave adopts the session and requests the handover
+        let response = SessionHandoverRequestRequest {
+            message: json!({ "contract": contract, "proof": session_proof }).to_string(),
+        }
+        .handle(&new)
+        .await
+        .expect("handover request");
+        let request: Value = serde_json::from_str(&response.message).expect("valid json");
+        assert_eq!(request["msg"]["pub_key"], hex::encode(pub_key(&new).await));
+        assert_eq!(request["msg"]["contract"], contract.to_string());
+        assert_eq!(request["msg"]["chain_id"], "testing");
+        assert_eq!(
+            new.store.get_nonce().await.expect("infallible store"),
+            Some(nonce)
+        );
+
+        // 2. the old enclave hands the session over
+        let response = SessionHandoverCompleteRequest {
+            message: multi_proof.clone(),
+        }
+        .handle(&old)
+        .await
+        .expect("handover complete");
+        let complete: Value = serde_json::from_str(&response.message).expect("valid json");
+        assert_eq!(complete["msg"]["epoch"], "1");
+        assert_eq!(complete["msg"]["pub_key"], hex::encode(pub_key(&new).await));
+
+        // a state that wasn't encrypted with the session key is rejected
+        let forged = SessionHandoverState {
+            sk: hex::encode(SigningKey::random(&mut rand::thread_rng()).to_bytes()),
+            seq_num: 3,
+        };
+        let forged = ecies::encrypt(
+            &pub_key(&new).await,
+            &serde_json::to_vec(&forged).expect("infallible serializer"),
+        )
+        .expect("valid pub key");
+        let err = SessionHandoverAcceptRequest {
+            message: multi_proof.clone(),
+            state: forged,
+        }
+        .handle(&new)
+        .await
+        .expect_err("forged state");
+        assert_eq!(err.code(), tonic::Code::Unauthenticated);
+
+        // 3. the new enclave takes the session key over
+        SessionHandoverAcceptRequest {
+            message: multi_proof.clone(),
+            state: response.state.clone(),
+        }
+        .handle(&new)
+        .await
+        .expect("handover accept");
+        assert_eq!(new.key_manager.epoch().await, 1);
+        assert_eq!(
+            new.key_manager.prev_pub_key().await.map(Vec::<u8>::from),
+            Some(pub_key(&old).await)
+        );
+        assert_eq!(new.store.get_seq_num().await.expect("infallible store"), 3);
+
+        // the state can only be accepted once
+        let err = SessionHandoverAcceptRequest {
+            message: multi_proof,
+            state: response.state,
+        }
+        .handle(&new)
+        .await
+        .expect_err("already accepted");
+        assert_eq!(err.code(), tonic::Code::AlreadyExists);
+    }
+}
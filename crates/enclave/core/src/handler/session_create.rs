@@ -54,8 +54,18 @@ where
             return Err(Status::already_exists("nonce already exists".to_string()));
         }
 
+        // bind the session to the chain that the light client follows
+        let config = ctx
+            .store()
+            .await
+            .get_config()
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?
+            .ok_or_else(|| Status::not_found("config not found"))?;
+        let chain_id = config.light_client_opts().chain_id().clone();
+
         // create `SessionCreate` msg and attest to it
-        let msg = SessionCreate::new(nonce, deployed_contract.to_string());
+        let msg = SessionCreate::new(nonce, deployed_contract.to_string(), chain_id);
         let attestation = ctx
             .attestor()
             .await
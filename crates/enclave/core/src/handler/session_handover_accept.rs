@@ -0,0 +1,147 @@
+use cosmrs::AccountId;
+use k256::ecdsa::{SigningKey, VerifyingKey};
+use quartz_contract_core::state::{PendingHandover, Session, PENDING_HANDOVER_KEY, SESSION_KEY};
+use quartz_cw_proof::proof::key::StorageKey;
+use quartz_proto::quartz::{
+    SessionHandoverAcceptRequest as RawSessionHandoverAcceptRequest,
+    SessionHandoverAcceptResponse as RawSessionHandoverAcceptResponse,
+};
+use tonic::Status;
+
+use crate::{
+    attestor::Attestor,
+    handler::Handler,
+    key_manager::{HandoverKeyManager, KeyManager, RotatingKeyManager},
+    proof_of_publication::MultiProofOfPublication,
+    store::Store,
+    types::SessionHandoverState,
+    Enclave,
+};
+
+/// Handled by the enclave that takes over the session, with the state handed over by the enclave
+/// that held it. The handed over key becomes the enclave's previous key, so that data encrypted to
+/// the session pub key before the handover can still be decrypted.
+///
+/// The state must be accepted while the handover is still pending, i.e. before the
+/// `SessionHandoverComplete` msg is executed by the contract.
+#[async_trait::async_trait]
+impl<E> Handler<E> for RawSessionHandoverAcceptRequest
+where
+    E: Enclave,
+    E::KeyManager: HandoverKeyManager,
+    E::Store: Store<Contract = AccountId>,
+{
+    type Error = Status;
+    type Response = RawSessionHandoverAcceptResponse;
+
+    async fn handle(self, ctx: &E) -> Result<Self::Response, Self::Error> {
+        // verify proof of publication of the session and the pending handover
+        let proof: MultiProofOfPublication<Option<()>> = serde_json::from_str(&self.message)
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+        let contract = ctx
+            .store()
+            .await
+            .get_contract()
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?
+            .ok_or_else(|| Status::not_found("contract not found"))?;
+        let config = ctx
+            .store()
+            .await
+            .get_config()
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?
+            .ok_or_else(|| Status::not_found("config not found"))?;
+        let session_key = StorageKey::new(SESSION_KEY, None);
+        let pending_handover_key = StorageKey::new(PENDING_HANDOVER_KEY, None);
+        let (values, _msg) = proof
+            .verify(
+                ctx.store().await,
+                config.light_client_opts(),
+                contract,
+                vec![session_key.clone(), pending_handover_key.clone()],
+            )
+            .await
+            .map_err(|e| Status::failed_precondition(e.to_string()))?;
+        let session: Session = serde_json::from_slice(&values[&session_key])
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+        let pending: PendingHandover = serde_json::from_slice(&values[&pending_handover_key])
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+
+        // make sure session nonce matches what we have locally
+        let nonce = ctx
+            .store()
+            .await
+            .get_nonce()
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?
+            .ok_or_else(|| Status::not_found("nonce not found"))?;
+        if session.nonce() != nonce {
+            return Err(Status::unauthenticated("nonce mismatch"));
+        }
+
+        // make sure the pending handover is ours
+        let mr_enclave = ctx
+            .attestor()
+            .await
+            .mr_enclave()
+            .map_err(|e| Status::internal(e.to_string()))?;
+        let mut key_manager = ctx.key_manager().await;
+        let pk: Vec<u8> = key_manager.pub_key().await.into();
+        if pending.mr_enclave() != mr_enclave || pending.pub_key().as_slice() != pk.as_slice() {
+            return Err(Status::failed_precondition(
+                "pending handover is for another enclave",
+            ));
+        }
+
+        let epoch = session
+            .epoch()
+            .checked_add(1)
+            .ok_or_else(|| Status::out_of_range("epoch overflow"))?;
+        if key_manager.epoch().await >= epoch {
+            return Err(Status::already_exists("handover already accepted"));
+        }
+
+        // decrypt the handed over state and make sure it holds the session key, which only the
+        // enclave that held the session knows
+        let sk = key_manager.sk().await;
+        let state = ecies::decrypt(&sk.to_bytes(), &self.state)
+            .map_err(|e| Status::invalid_argument(format!("failed to decrypt state: {e}")))?;
+        let state: SessionHandoverState =
+            serde_json::from_slice(&state).map_err(|e| Status::invalid_argument(e.to_string()))?;
+        let prev_sk = hex::decode(state.sk)
+            .ok()
+            .and_then(|sk| SigningKey::from_slice(&sk).ok())
+            .ok_or_else(|| Status::invalid_argument("invalid session key"))?;
+        let prev_pk = VerifyingKey::from(&prev_sk).to_sec1_bytes();
+        if session.pub_key().map(|pk| pk.as_slice()) != Some(&prev_pk[..]) {
+            return Err(Status::unauthenticated(
+                "handed over key does not match session pub_key",
+            ));
+        }
+
+        // carry on from the sequence number of the enclave that held the session
+        let seq_num = ctx
+            .store()
+            .await
+            .get_seq_num()
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+        let seq_num_diff = state
+            .seq_num
+            .checked_sub(seq_num)
+            .ok_or_else(|| Status::failed_precondition("replay attempted"))?;
+
+        key_manager
+            .take_over(prev_sk, epoch)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+        ctx.store()
+            .await
+            .inc_seq_num(seq_num_diff as usize)
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        Ok(RawSessionHandoverAcceptResponse {})
+    }
+}
@@ -0,0 +1,130 @@
+use cosmrs::AccountId;
+use quartz_contract_core::{
+    msg::execute::{attested::Attested, session_handover_complete::SessionHandoverComplete},
+    state::{PendingHandover, Session, PENDING_HANDOVER_KEY, SESSION_KEY},
+};
+use quartz_cw_proof::proof::key::StorageKey;
+use quartz_proto::quartz::{
+    SessionHandoverCompleteRequest as RawSessionHandoverCompleteRequest,
+    SessionHandoverCompleteResponse as RawSessionHandoverCompleteResponse,
+};
+use tonic::Status;
+
+use crate::{
+    attestor::Attestor,
+    handler::{Handler, A, RA},
+    key_manager::{HandoverKeyManager, KeyManager},
+    proof_of_publication::MultiProofOfPublication,
+    store::Store,
+    types::{SessionHandoverCompleteResponse, SessionHandoverState},
+    Enclave,
+};
+
+/// Handled by the enclave that holds the session. It hands the session key and state over to the
+/// enclave whose handover request is pending, encrypted to that enclave's pub key.
+#[async_trait::async_trait]
+impl<E> Handler<E> for RawSessionHandoverCompleteRequest
+where
+    E: Enclave,
+    E::KeyManager: HandoverKeyManager,
+    E::Store: Store<Contract = AccountId>,
+{
+    type Error = Status;
+    type Response = RawSessionHandoverCompleteResponse;
+
+    async fn handle(self, ctx: &E) -> Result<Self::Response, Self::Error> {
+        // verify proof of publication of the session and the pending handover
+        let proof: MultiProofOfPublication<Option<()>> = serde_json::from_str(&self.message)
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+        let contract = ctx
+            .store()
+            .await
+            .get_contract()
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?
+            .ok_or_else(|| Status::not_found("contract not found"))?;
+        let config = ctx
+            .store()
+            .await
+            .get_config()
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?
+            .ok_or_else(|| Status::not_found("config not found"))?;
+        let session_key = StorageKey::new(SESSION_KEY, None);
+        let pending_handover_key = StorageKey::new(PENDING_HANDOVER_KEY, None);
+        let (values, _msg) = proof
+            .verify(
+                ctx.store().await,
+                config.light_client_opts(),
+                contract.clone(),
+                vec![session_key.clone(), pending_handover_key.clone()],
+            )
+            .await
+            .map_err(|e| Status::failed_precondition(e.to_string()))?;
+        let session: Session = serde_json::from_slice(&values[&session_key])
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+        let pending: PendingHandover = serde_json::from_slice(&values[&pending_handover_key])
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+
+        // make sure session nonce matches what we have locally
+        let nonce = ctx
+            .store()
+            .await
+            .get_nonce()
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?
+            .ok_or_else(|| Status::not_found("nonce not found"))?;
+        if session.nonce() != nonce {
+            return Err(Status::unauthenticated("nonce mismatch"));
+        }
+
+        // only the current session key can be handed over
+        let key_manager = ctx.key_manager().await;
+        let pk: Vec<u8> = key_manager.pub_key().await.into();
+        if session.pub_key().map(|pk| pk.as_slice()) != Some(pk.as_slice()) {
+            return Err(Status::failed_precondition(
+                "session pub_key does not match enclave key",
+            ));
+        }
+
+        // encrypt the session state to the pub key of the enclave taking over
+        let seq_num = ctx
+            .store()
+            .await
+            .get_seq_num()
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+        let state = SessionHandoverState {
+            sk: hex::encode(key_manager.sk().await.to_bytes()),
+            seq_num,
+        };
+        let state = serde_json::to_vec(&state).expect("infallible serializer");
+        let state = ecies::encrypt(pending.pub_key().as_slice(), &state)
+            .map_err(|e| Status::invalid_argument(format!("invalid handover pub_key: {e}")))?;
+
+        // create `SessionHandoverComplete` msg and attest to it
+        let epoch = session
+            .epoch()
+            .checked_add(1)
+            .ok_or_else(|| Status::out_of_range("epoch overflow"))?;
+        let msg = SessionHandoverComplete::new(
+            nonce,
+            epoch,
+            pending.mr_enclave(),
+            pending.pub_key().to_vec(),
+            contract.to_string(),
+            config.light_client_opts().chain_id().clone(),
+        );
+        let attestation = ctx
+            .attestor()
+            .await
+            .attestation(msg.clone())
+            .map_err(|e| Status::internal(e.to_string()))?;
+        let attested_msg = Attested::new(msg, attestation);
+
+        // return response with attested `SessionHandoverComplete` msg and the encrypted state
+        let response: SessionHandoverCompleteResponse<A<E>, RA<E>> =
+            SessionHandoverCompleteResponse::new(attested_msg, state);
+        Ok(response.into())
+    }
+}
@@ -0,0 +1,123 @@
+use cosmrs::AccountId;
+use quartz_contract_core::{
+    msg::execute::{attested::Attested, session_handover_request::SessionHandoverRequest},
+    state::{Session, SESSION_KEY},
+};
+use quartz_proto::quartz::{
+    SessionHandoverRequestRequest as RawSessionHandoverRequestRequest,
+    SessionHandoverRequestResponse as RawSessionHandoverRequestResponse,
+};
+use serde::{Deserialize, Serialize};
+use tonic::Status;
+
+use crate::{
+    attestor::Attestor,
+    handler::{Handler, A, RA},
+    key_manager::KeyManager,
+    proof_of_publication::ProofOfPublication,
+    store::Store,
+    types::SessionHandoverRequestResponse,
+    Enclave,
+};
+
+/// The request's message, i.e. the contract whose session the enclave takes over and a proof of
+/// publication of that session.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SessionHandoverRequestMessage {
+    pub contract: AccountId,
+    pub proof: ProofOfPublication<Option<()>>,
+}
+
+/// Handled by the enclave that takes over the session. It adopts the session (i.e. its contract
+/// and nonce) and requests the handover with its own pub key.
+#[async_trait::async_trait]
+impl<E> Handler<E> for RawSessionHandoverRequestRequest
+where
+    E: Enclave,
+    E::KeyManager: KeyManager,
+    E::Store: Store<Contract = AccountId>,
+{
+    type Error = Status;
+    type Response = RawSessionHandoverRequestResponse;
+
+    async fn handle(self, ctx: &E) -> Result<Self::Response, Self::Error> {
+        // verify proof of publication of the session we're taking over
+        let SessionHandoverRequestMessage { contract, proof } = serde_json::from_str(&self.message)
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+        let config = ctx
+            .store()
+            .await
+            .get_config()
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?
+            .ok_or_else(|| Status::not_found("config not found"))?;
+        let (value, _msg) = proof
+            .verify(
+                ctx.store().await,
+                config.light_client_opts(),
+                contract.clone(),
+                SESSION_KEY.to_string(),
+                None,
+            )
+            .await
+            .map_err(|e| Status::failed_precondition(e.to_string()))?;
+
+        // only a session whose handshake is complete has a key to hand over
+        let session: Session =
+            serde_json::from_slice(&value).map_err(|e| Status::invalid_argument(e.to_string()))?;
+        if session.pub_key().is_none() {
+            return Err(Status::failed_precondition("session pub_key not set"));
+        }
+
+        // store contract and nonce, unless we already adopted another session
+        let store = ctx.store().await;
+        let prev_contract = store
+            .get_contract()
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+        let prev_nonce = store
+            .get_nonce()
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+        if prev_contract.is_some_and(|c| c != contract)
+            || prev_nonce.is_some_and(|n| n != session.nonce())
+        {
+            return Err(Status::already_exists("another session already exists"));
+        }
+        store
+            .set_contract(contract.clone())
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+        store
+            .set_nonce(session.nonce())
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?;
+
+        // create `SessionHandoverRequest` msg and attest to it
+        let mr_enclave = ctx
+            .attestor()
+            .await
+            .mr_enclave()
+            .map_err(|e| Status::internal(e.to_string()))?;
+        let pk = ctx.key_manager().await.pub_key().await.into();
+        let chain_id = config.light_client_opts().chain_id().clone();
+        let msg = SessionHandoverRequest::new(
+            session.nonce(),
+            mr_enclave,
+            pk,
+            contract.to_string(),
+            chain_id,
+        );
+        let attestation = ctx
+            .attestor()
+            .await
+            .attestation(msg.clone())
+            .map_err(|e| Status::internal(e.to_string()))?;
+        let attested_msg = Attested::new(msg, attestation);
+
+        // return response with attested `SessionHandoverRequest` msg
+        let response: SessionHandoverRequestResponse<A<E>, RA<E>> =
+            SessionHandoverRequestResponse::new(attested_msg);
+        Ok(response.into())
+    }
+}
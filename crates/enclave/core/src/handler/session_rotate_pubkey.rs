@@ -0,0 +1,115 @@
+use cosmrs::AccountId;
+use quartz_contract_core::{
+    msg::execute::{attested::Attested, session_rotate_pub_key::SessionRotatePubKey},
+    state::{Session, SESSION_KEY},
+};
+use quartz_proto::quartz::{
+    SessionRotatePubKeyRequest as RawSessionRotatePubKeyRequest,
+    SessionRotatePubKeyResponse as RawSessionRotatePubKeyResponse,
+};
+use tonic::Status;
+
+use crate::{
+    attestor::Attestor,
+    handler::{Handler, A, RA},
+    key_manager::{KeyManager, RotatingKeyManager},
+    proof_of_publication::ProofOfPublication,
+    store::Store,
+    types::SessionRotatePubKeyResponse,
+    Enclave,
+};
+
+#[async_trait::async_trait]
+impl<E> Handler<E> for RawSessionRotatePubKeyRequest
+where
+    E: Enclave,
+    E::KeyManager: RotatingKeyManager,
+    E::Store: Store<Contract = AccountId>,
+{
+    type Error = Status;
+    type Response = RawSessionRotatePubKeyResponse;
+
+    async fn handle(self, ctx: &E) -> Result<Self::Response, Self::Error> {
+        // verify proof of publication of the current session
+        let proof: ProofOfPublication<Option<()>> = serde_json::from_str(&self.message)
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+        let contract = ctx
+            .store()
+            .await
+            .get_contract()
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?
+            .ok_or_else(|| Status::not_found("contract not found"))?;
+        let config = ctx
+            .store()
+            .await
+            .get_config()
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?
+            .ok_or_else(|| Status::not_found("config not found"))?;
+        let (value, _msg) = proof
+            .verify(
+                ctx.store().await,
+                config.light_client_opts(),
+                contract.clone(),
+                SESSION_KEY.to_string(),
+                None,
+            )
+            .await
+            .map_err(|e| Status::failed_precondition(e.to_string()))?;
+
+        // make sure session nonce matches what we have locally
+        let session: Session =
+            serde_json::from_slice(&value).map_err(|e| Status::invalid_argument(e.to_string()))?;
+        let nonce = ctx
+            .store()
+            .await
+            .get_nonce()
+            .await
+            .map_err(|e| Status::internal(e.to_string()))?
+            .ok_or_else(|| Status::not_found("nonce not found"))?;
+        if session.nonce() != nonce {
+            return Err(Status::unauthenticated("nonce mismatch"));
+        }
+
+        let on_chain_pk = session
+            .pub_key()
+            .ok_or_else(|| Status::failed_precondition("session pub_key not set"))?;
+
+        // rotate only if the on-chain key is our current key, otherwise a previous rotation is
+        // still pending (i.e. not yet published on-chain) and we re-issue it with the same key
+        let mut key_manager = ctx.key_manager().await;
+        let pk: Vec<u8> = key_manager.pub_key().await.into();
+        let prev_pk: Option<Vec<u8>> = key_manager.prev_pub_key().await.map(Into::into);
+        if on_chain_pk.as_slice() == pk.as_slice() {
+            key_manager
+                .rotate()
+                .await
+                .map_err(|e| Status::internal(e.to_string()))?;
+        } else if prev_pk.as_deref() != Some(on_chain_pk.as_slice()) {
+            return Err(Status::failed_precondition(
+                "session pub_key does not match enclave keys",
+            ));
+        }
+        let pk = key_manager.pub_key().await.into();
+
+        // create `SessionRotatePubKey` msg and attest to it
+        let epoch = session
+            .epoch()
+            .checked_add(1)
+            .ok_or_else(|| Status::out_of_range("epoch overflow"))?;
+        let chain_id = config.light_client_opts().chain_id().clone();
+        let msg = SessionRotatePubKey::new(nonce, epoch, pk, contract.to_string(), chain_id);
+        let attestation = ctx
+            .attestor()
+            .await
+            .attestation(msg.clone())
+            .map_err(|e| Status::internal(e.to_string()))?;
+        let attested_msg = Attested::new(msg, attestation);
+
+        // return response with attested `SessionRotatePubKey` msg
+        let response: SessionRotatePubKeyResponse<A<E>, RA<E>> =
+            SessionRotatePubKeyResponse::new(attested_msg);
+        Ok(response.into())
+    }
+}
@@ -49,12 +49,14 @@ where
             .ok_or_else(|| Status::not_found("config not found"))?;
         let (value, _msg) = proof
             .verify(
+                ctx.store().await,
                 config.light_client_opts(),
                 contract,
                 SESSION_KEY.to_string(),
                 None,
             )
-            .map_err(Status::failed_precondition)?;
+            .await
+            .map_err(|e| Status::failed_precondition(e.to_string()))?;
 
         // make sure session nonce matches what we have locally
         let session: Session = serde_json::from_slice(&value).unwrap();
@@ -1,15 +1,23 @@
-use std::{fmt::Display, marker::PhantomData};
+use std::{fmt::Display, future::Future, marker::PhantomData, pin::pin, sync::Arc, time::Duration};
 
 use anyhow::anyhow;
 use cosmrs::AccountId;
-use futures_util::StreamExt;
-use log::{error, info, trace};
+use futures_util::{
+    future::{try_join, try_join3},
+    stream::unfold,
+    Stream, StreamExt,
+};
+use log::{error, info, trace, warn};
 use reqwest::Url;
 use serde::Serialize;
 use tendermint_rpc::{
     event::Event as TmEvent,
     query::{EventType, Query},
-    SubscriptionClient, WebSocketClient,
+    Client, Order, Subscription, SubscriptionClient, WebSocketClient,
+};
+use tokio::{
+    sync::mpsc,
+    time::{interval, sleep},
 };
 use tonic::Status;
 
@@ -17,10 +25,15 @@ use crate::{
     chain_client::{default::DefaultChainClient, ChainClient},
     event::QuartzEvent,
     handler::Handler,
+    host::checkpoint::{
+        tx_height_and_hash, tx_to_event, Checkpoint, CheckpointStore, InMemoryCheckpointStore,
+    },
     store::Store,
     Enclave,
 };
 
+pub mod checkpoint;
+
 pub type Response<R, E> = <R as Handler<E>>::Response;
 
 #[async_trait::async_trait]
@@ -48,6 +61,9 @@ pub struct DefaultHost<R, EV, GF, E, C = DefaultChainClient> {
     enclave: E,
     chain_client: C,
     gas_fn: GF,
+    checkpoint_store: Arc<dyn CheckpointStore>,
+    event_filters: Vec<EventFilter>,
+    pipeline_config: PipelineConfig,
     _phantom: PhantomData<(R, EV)>,
 }
 
@@ -61,9 +77,34 @@ where
             enclave,
             chain_client,
             gas_fn,
+            checkpoint_store: Arc::new(InMemoryCheckpointStore::default()),
+            event_filters: vec![],
+            pipeline_config: PipelineConfig::default(),
             _phantom: Default::default(),
         }
     }
+
+    /// Sets where the host persists the last processed block, so that it can catch up on the
+    /// events it missed while disconnected (defaults to memory).
+    pub fn with_checkpoint_store(mut self, checkpoint_store: impl CheckpointStore) -> Self {
+        self.checkpoint_store = Arc::new(checkpoint_store);
+        self
+    }
+
+    /// Narrows the default subscription to the contract events that the app handles (defaults to
+    /// all of the contract's txs).
+    pub fn with_event_filters(
+        mut self,
+        event_filters: impl IntoIterator<Item = EventFilter>,
+    ) -> Self {
+        self.event_filters = event_filters.into_iter().collect();
+        self
+    }
+
+    pub fn with_pipeline_config(mut self, pipeline_config: PipelineConfig) -> Self {
+        self.pipeline_config = pipeline_config;
+        self
+    }
 }
 
 #[async_trait::async_trait]
@@ -95,54 +136,955 @@ where
             .map_err(|e| anyhow!("enclave call failed: {}", e))
     }
 
+    /// Serves the events that match `query` or, if none is specified, the events of the contract
+    /// that is stored in the enclave (narrowed by the host's event filters, if any).
     async fn serve_with_query(self, url: Url, query: Option<Query>) -> Result<(), Self::Error> {
-        let (client, driver) = WebSocketClient::new(url.as_str()).await.unwrap();
+        let mut backoff = MIN_BACKOFF;
+        loop {
+            match self
+                .serve_connection(&url, query.as_ref(), &mut backoff)
+                .await
+            {
+                Ok(()) => continue,
+                Err(HostError::Disconnected(e)) => warn!("Event subscription failed: {e}"),
+                Err(HostError::Fatal(e)) => return Err(e),
+            }
+
+            info!("Reconnecting in {}s", backoff.as_secs());
+            sleep(backoff).await;
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+    }
+}
+
+/// Limits of the host's event pipeline.
+///
+/// Proofs are generated from the enclave's latest verified block at the time, so they run ahead
+/// of the enclave calls. The enclave still accepts them as long as it verifies fewer than
+/// `RECENT_VERIFIED_BLOCKS` newer blocks in the meantime, i.e. the pipeline shouldn't hold more
+/// events than that (see `queue_size`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PipelineConfig {
+    /// Maximum number of events whose proofs are generated concurrently.
+    pub proof_concurrency: usize,
+    /// Maximum number of txs that are submitted concurrently. Note that concurrent txs from the
+    /// same account need the chain client to assign their sequence numbers.
+    pub tx_concurrency: usize,
+    /// Capacity of the queues between the stages. The host stops reading events while the
+    /// queues are full.
+    pub queue_size: usize,
+    /// Maximum number of attempts to prove an event or to submit its tx. Events that still fail
+    /// (or that the enclave rejects) are skipped.
+    pub max_attempts: usize,
+    /// Delay before the first retry, which doubles with every further attempt.
+    pub retry_backoff: Duration,
+}
+
+impl Default for PipelineConfig {
+    fn default() -> Self {
+        Self {
+            proof_concurrency: 4,
+            tx_concurrency: 1,
+            queue_size: 16,
+            max_attempts: 3,
+            retry_backoff: MIN_BACKOFF,
+        }
+    }
+}
+
+/// Narrows the host's subscription to the txs in which the contract emitted specific events.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum EventFilter {
+    /// A custom event type, e.g. `transfer` for the `wasm-transfer` events emitted via
+    /// `Event::new("transfer")`.
+    EventType(String),
+    /// The value of the `action` attribute of the contract's `wasm` event.
+    Action(String),
+}
+
+impl EventFilter {
+    pub fn matches(&self, event: &TmEvent) -> bool {
+        let Some(events) = &event.events else {
+            return false;
+        };
+        match self {
+            EventFilter::EventType(event_type) => {
+                events.contains_key(&format!("wasm-{event_type}._contract_address"))
+            }
+            EventFilter::Action(action) => events
+                .get("wasm.action")
+                .is_some_and(|actions| actions.contains(action)),
+        }
+    }
+}
+
+/// Returns the subscription query for the `contract`'s events.
+///
+/// Tendermint queries can't express disjunctions, so the query is only narrowed if there is a
+/// single filter. Otherwise, the host matches the filters itself. (Merging one subscription per
+/// filter would not preserve the order of the txs across subscriptions.)
+pub fn subscription_query(contract: &AccountId, filters: &[EventFilter]) -> Query {
+    let query =
+        Query::from(EventType::Tx).and_eq("execute._contract_address", contract.to_string());
+    match filters {
+        [EventFilter::EventType(event_type)] => query.and_eq(
+            format!("wasm-{event_type}._contract_address"),
+            contract.to_string(),
+        ),
+        [EventFilter::Action(action)] => query.and_eq("wasm.action", action.as_str()),
+        _ => query,
+    }
+}
+
+const MIN_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(64);
+
+/// Number of txs per `tx_search` page when backfilling.
+const BACKFILL_PAGE_SIZE: u8 = 100;
+
+/// How often the host checks whether the contract in the enclave's store changed.
+const CONTRACT_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Errors while serving a single websocket connection.
+#[derive(Debug)]
+enum HostError {
+    /// The connection failed and the host should reconnect.
+    Disconnected(anyhow::Error),
+    /// Reading the enclave's store or the checkpoint failed and the host should stop.
+    Fatal(anyhow::Error),
+}
+
+/// Errors while processing a single event.
+#[derive(Debug)]
+enum EventError {
+    /// Reading the enclave's store failed and the host should stop.
+    Fatal(anyhow::Error),
+    /// A chain request failed and may succeed if retried.
+    Transient(anyhow::Error),
+}
+
+impl<R, EV, GF, E, C> DefaultHost<R, EV, GF, E, C>
+where
+    E: Enclave,
+    <E as Enclave>::Store: Store<Contract = AccountId>,
+    C: ChainClient<Contract = AccountId, Error = anyhow::Error>,
+    <C as ChainClient>::TxOutput: Display,
+    R: Handler<E, Error = Status>,
+    <R as Handler<E>>::Response: Serialize + Send + Sync + 'static,
+    EV: Handler<C, Response = R, Error = anyhow::Error>,
+    EV: TryFrom<TmEvent, Error = anyhow::Error>,
+    GF: Fn(&<R as Handler<E>>::Response) -> <C as ChainClient>::TxConfig + Send + Sync + 'static,
+{
+    /// Subscribes to `query` (or the contract's events), catches up on the txs that were missed
+    /// since the last checkpoint and then processes live events until the connection drops.
+    ///
+    /// Returns `Ok(())` if the subscription must be renewed because the contract changed.
+    async fn serve_connection(
+        &self,
+        url: &Url,
+        query: Option<&Query>,
+        backoff: &mut Duration,
+    ) -> Result<(), HostError> {
+        let (contract, subscription, filters) = match query {
+            Some(query) => (
+                self.contract().await.map_err(HostError::Fatal)?,
+                query.clone(),
+                &[][..],
+            ),
+            None => {
+                let contract = self.wait_for_contract().await.map_err(HostError::Fatal)?;
+                let query = subscription_query(&contract, &self.event_filters);
+                (Some(contract), query, &self.event_filters[..])
+            }
+        };
+
+        let (client, driver) = WebSocketClient::new(url.as_str())
+            .await
+            .map_err(|e| HostError::Disconnected(e.into()))?;
         let driver_handle = tokio::spawn(async move { driver.run().await });
 
-        // TODO: default to `Query::from(EventType::Tx).and_eq("wasm._contract_address", contract)`
-        let query = query.unwrap_or(Query::from(EventType::Tx));
-        let mut subs = client.subscribe(query).await.unwrap();
-        while let Some(Ok(event)) = subs.next().await {
-            trace!("Received event");
+        // Subscribe before backfilling so that no events are lost in between. Events that were
+        // already backfilled are skipped by the checkpoint.
+        let result = async {
+            let subs = client
+                .subscribe(subscription)
+                .await
+                .map_err(|e| HostError::Disconnected(e.into()))?;
+
+            // Let the pipeline finish the events that were already queued when the intake stops,
+            // so that they don't have to be processed again after reconnecting.
+            let (events, events_rx) = mpsc::channel(self.pipeline_config.queue_size.max(1));
+            let intake = async {
+                let watch_contract = query.is_none();
+                Ok(self
+                    .intake(
+                        &client,
+                        subs,
+                        contract.as_ref(),
+                        watch_contract,
+                        backoff,
+                        events,
+                    )
+                    .await)
+            };
+            let (intake_result, ()) = try_join(intake, self.pipeline(events_rx, filters)).await?;
+            intake_result
+        }
+        .await;
+
+        let _ = client.close();
+        let _ = driver_handle.await;
+
+        result
+    }
+
+    async fn contract(&self) -> Result<Option<AccountId>, anyhow::Error> {
+        self.enclave
+            .store()
+            .await
+            .get_contract()
+            .await
+            .map_err(|_| anyhow!("contract read failure"))
+    }
+
+    async fn wait_for_contract(&self) -> Result<AccountId, anyhow::Error> {
+        let mut contract_poll = interval(CONTRACT_POLL_INTERVAL);
+        loop {
+            contract_poll.tick().await;
+            if let Some(contract) = self.contract().await? {
+                return Ok(contract);
+            }
+            trace!("Waiting for the contract to be set");
+        }
+    }
+
+    /// Queues the missed and then the live events for the pipeline.
+    async fn intake(
+        &self,
+        client: &WebSocketClient,
+        mut subs: Subscription,
+        contract: Option<&AccountId>,
+        watch_contract: bool,
+        backoff: &mut Duration,
+        events: mpsc::Sender<Job<TmEvent>>,
+    ) -> Result<(), HostError> {
+        let mut dispatched = self.backfill(client, contract, &events).await?;
+        *backoff = MIN_BACKOFF;
+
+        let mut contract_poll = interval(CONTRACT_POLL_INTERVAL);
+        loop {
+            tokio::select! {
+                event = subs.next() => {
+                    let event = event
+                        .ok_or_else(|| HostError::Disconnected(anyhow!("subscription ended")))?
+                        .map_err(|e| HostError::Disconnected(e.into()))?;
+                    dispatch(&mut dispatched, event, &events).await?;
+                }
+                _ = contract_poll.tick(), if watch_contract => {
+                    let current = self.contract().await.map_err(HostError::Fatal)?;
+                    if current.as_ref() != contract {
+                        info!("Contract changed, resubscribing");
+                        return Ok(());
+                    }
+                }
+            }
+        }
+    }
+
+    /// Queues the contract's txs between the checkpoint and the latest block.
+    ///
+    /// Without a checkpoint (i.e. on first start), there is nothing to catch up on and processing
+    /// starts with the next block. Returns the checkpoint of the queued txs.
+    async fn backfill(
+        &self,
+        client: &WebSocketClient,
+        contract: Option<&AccountId>,
+        events: &mpsc::Sender<Job<TmEvent>>,
+    ) -> Result<Checkpoint, HostError> {
+        let latest_height = client
+            .status()
+            .await
+            .map_err(|e| HostError::Disconnected(e.into()))?
+            .sync_info
+            .latest_block_height
+            .value();
+
+        let Some(mut checkpoint) = self
+            .checkpoint_store
+            .load()
+            .await
+            .map_err(HostError::Fatal)?
+        else {
+            let checkpoint = Checkpoint::starting_at(latest_height + 1);
+            self.checkpoint_store
+                .save(&checkpoint)
+                .await
+                .map_err(HostError::Fatal)?;
+            return Ok(checkpoint);
+        };
+        let Some(contract) = contract.filter(|_| checkpoint.height() <= latest_height) else {
+            return Ok(checkpoint);
+        };
+
+        info!(
+            "Backfilling events from height {} to {latest_height}",
+            checkpoint.height()
+        );
+        let query = Query::default()
+            .and_eq("execute._contract_address", contract.to_string())
+            .and_gte("tx.height", checkpoint.height())
+            .and_lte("tx.height", latest_height);
+        let mut page = 1;
+        loop {
+            let response = client
+                .tx_search(
+                    query.clone(),
+                    false,
+                    page,
+                    BACKFILL_PAGE_SIZE,
+                    Order::Ascending,
+                )
+                .await
+                .map_err(|e| HostError::Disconnected(e.into()))?;
+            let fetched = response.txs.len();
+            for tx in response.txs {
+                let event = tx_to_event(query.to_string(), tx);
+                dispatch(&mut checkpoint, event, events).await?;
+            }
+
+            if fetched < usize::from(BACKFILL_PAGE_SIZE)
+                || (page * u32::from(BACKFILL_PAGE_SIZE)) >= response.total_count
+            {
+                break;
+            }
+            page += 1;
+        }
+
+        Ok(checkpoint)
+    }
+
+    /// Processes the queued events in three stages that are connected by bounded queues -
+    /// concurrent proof generation, sequential enclave calls and tx submission.
+    ///
+    /// Every stage passes the events on in the order in which they were queued, so that the
+    /// enclave sees requests in order and the checkpoint only moves forward.
+    ///
+    /// Failing chain requests are retried, and events that still fail (or that the enclave
+    /// rejects) are logged and skipped. Only store and checkpoint failures stop the host.
+    async fn pipeline(
+        &self,
+        events: mpsc::Receiver<Job<TmEvent>>,
+        filters: &[EventFilter],
+    ) -> Result<(), HostError> {
+        let PipelineConfig {
+            proof_concurrency,
+            tx_concurrency,
+            queue_size,
+            ..
+        } = self.pipeline_config;
+        let (requests, requests_rx) = mpsc::channel(queue_size.max(1));
+        let (responses, responses_rx) = mpsc::channel(queue_size.max(1));
+
+        let prove = async move {
+            let mut jobs = pin!(receiver_stream(events)
+                .map(|job| self.prove_job(job, filters))
+                .buffered(proof_concurrency.max(1)));
+            while let Some(job) = jobs.next().await {
+                requests.send(job?).await.map_err(|_| pipeline_stopped())?;
+            }
+            Ok(())
+        };
 
-            let event = match Self::Event::try_from(event) {
-                Ok(e) => e,
+        let call_enclave = async move {
+            let mut jobs = pin!(receiver_stream(requests_rx));
+            while let Some(job) = jobs.next().await {
+                let job = self.call_enclave_job(job).await;
+                responses.send(job).await.map_err(|_| pipeline_stopped())?;
+            }
+            Ok(())
+        };
+
+        let submit = async move {
+            let mut jobs = pin!(receiver_stream(responses_rx)
+                .map(|job| self.submit_job(job))
+                .buffered(tx_concurrency.max(1)));
+            while let Some(tx) = jobs.next().await {
+                if let Some((height, tx_hash)) = tx? {
+                    self.record(height, &tx_hash)
+                        .await
+                        .map_err(HostError::Fatal)?;
+                }
+            }
+            Ok(())
+        };
+
+        try_join3(prove, call_enclave, submit).await.map(|_| ())
+    }
+
+    async fn prove_job(
+        &self,
+        Job { tx, item }: Job<TmEvent>,
+        filters: &[EventFilter],
+    ) -> Result<Job<(AccountId, R)>, HostError> {
+        let item = match item {
+            Some(event) => self
+                .retry(&tx, || self.prove(event.clone(), filters))
+                .await?
+                .flatten(),
+            None => None,
+        };
+        Ok(Job { tx, item })
+    }
+
+    /// Calls the enclave with the event's request. Requests that the enclave rejects are skipped,
+    /// as they would be rejected again.
+    async fn call_enclave_job(
+        &self,
+        Job { tx, item }: Job<(AccountId, R)>,
+    ) -> Job<(AccountId, Response<R, E>)> {
+        let item = match item {
+            Some((contract, request)) => match self.enclave_call(request).await {
+                Ok(response) => Some((contract, response)),
                 Err(e) => {
-                    trace!("Failed to decode event: {e}");
-                    continue;
+                    skip_event(&tx, e);
+                    None
                 }
+            },
+            None => None,
+        };
+        Job { tx, item }
+    }
+
+    async fn submit_job(
+        &self,
+        Job { tx, item }: Job<(AccountId, Response<R, E>)>,
+    ) -> Result<Option<(u64, String)>, HostError> {
+        if let Some((contract, response)) = item {
+            let send_tx = || async {
+                let tx_config = (self.gas_fn)(&response);
+                self.chain_client
+                    .send_tx(&contract, &response, tx_config)
+                    .await
+                    .map_err(EventError::Transient)
             };
+            if let Some(output) = self.retry(&tx, send_tx).await? {
+                info!("tx output: {output}");
+            }
+        }
+        Ok(tx)
+    }
 
-            // Make sure the contract in the event is the same as the paired contract.
-            // This check is not really required since the proof-of-publication check will check
-            // if there is a mismatch anyway, but it allows us to short-circuit here.
-            let contract = event.contract.clone();
-            let expected_contract = self
-                .enclave
-                .store()
-                .await
-                .get_contract()
+    /// Runs a step of the event's processing and retries it (with backoff) while it fails
+    /// transiently.
+    ///
+    /// Returns `None` if the event is skipped because it still fails after the configured number
+    /// of attempts. Its tx is then recorded in the checkpoint like any other, as processing it
+    /// again (e.g. when backfilling after a restart) would only fail again.
+    async fn retry<T, Fut>(
+        &self,
+        tx: &Option<(u64, String)>,
+        step: impl Fn() -> Fut,
+    ) -> Result<Option<T>, HostError>
+    where
+        Fut: Future<Output = Result<T, EventError>>,
+    {
+        let PipelineConfig {
+            max_attempts,
+            retry_backoff,
+            ..
+        } = self.pipeline_config;
+        let mut backoff = retry_backoff;
+        let mut attempt = 1;
+        loop {
+            match step().await {
+                Ok(output) => return Ok(Some(output)),
+                Err(EventError::Fatal(e)) => return Err(HostError::Fatal(e)),
+                Err(EventError::Transient(e)) if attempt < max_attempts => {
+                    warn!(
+                        "Processing event failed (attempt {attempt}/{max_attempts}), retrying in \
+                         {}ms: {e}",
+                        backoff.as_millis()
+                    );
+                    sleep(backoff).await;
+                    backoff = (backoff * 2).min(MAX_BACKOFF);
+                    attempt += 1;
+                }
+                Err(EventError::Transient(e)) => {
+                    skip_event(tx, e);
+                    return Ok(None);
+                }
+            }
+        }
+    }
+
+    async fn record(&self, height: u64, tx_hash: &str) -> Result<(), anyhow::Error> {
+        let mut checkpoint = self.checkpoint_store.load().await?.unwrap_or_default();
+        checkpoint.record(height, tx_hash);
+        self.checkpoint_store.save(&checkpoint).await
+    }
+
+    /// Decodes the event and generates the request (along with its proofs) for the enclave.
+    ///
+    /// Returns `None` for events that the host doesn't handle.
+    async fn prove(
+        &self,
+        event: TmEvent,
+        filters: &[EventFilter],
+    ) -> Result<Option<(AccountId, R)>, EventError> {
+        if !filters.is_empty() && !filters.iter().any(|filter| filter.matches(&event)) {
+            return Ok(None);
+        }
+
+        let event = match QuartzEvent::<EV>::try_from(event) {
+            Ok(e) => e,
+            Err(e) => {
+                trace!("Failed to decode event: {e}");
+                return Ok(None);
+            }
+        };
+
+        // Make sure the contract in the event is the same as the paired contract.
+        // This check is not really required since the proof-of-publication check will check
+        // if there is a mismatch anyway, but it allows us to short-circuit here.
+        let contract = event.contract.clone();
+        let expected_contract = self
+            .contract()
+            .await
+            .map_err(EventError::Fatal)?
+            .ok_or_else(|| EventError::Fatal(anyhow!("contract not set")))?;
+        if contract != expected_contract {
+            error!("contract != expected_contract");
+            return Ok(None);
+        }
+
+        // Generate proofs from the latest block verified by the enclave (if any) so that
+        // light client traces stay short and the trust anchor doesn't expire.
+        if let Some(light_block) = self
+            .enclave
+            .store()
+            .await
+            .get_latest_verified_block()
+            .await
+            .map_err(|_| EventError::Fatal(anyhow!("latest verified block read failure")))?
+        {
+            self.chain_client
+                .set_trusted_block(
+                    light_block.height(),
+                    light_block.signed_header.header.hash(),
+                )
                 .await
-                .map_err(|_| anyhow!("contract read failure"))?
-                .expect("contract must be set");
-            if contract != expected_contract {
-                error!("contract != expected_contract");
-                continue;
+                .map_err(EventError::Transient)?;
+        }
+
+        let request = event
+            .handle(&self.chain_client)
+            .await
+            .map_err(EventError::Transient)?;
+        Ok(Some((contract, request)))
+    }
+}
+
+/// An event on its way through the pipeline, along with the tx that emitted it.
+///
+/// Events that a stage skips are passed on without an item, so that their txs are still recorded
+/// in the checkpoint (in order).
+struct Job<T> {
+    tx: Option<(u64, String)>,
+    item: Option<T>,
+}
+
+/// Queues the event for the pipeline unless its tx was already queued.
+async fn dispatch(
+    dispatched: &mut Checkpoint,
+    event: TmEvent,
+    events: &mpsc::Sender<Job<TmEvent>>,
+) -> Result<(), HostError> {
+    trace!("Received event");
+
+    let tx = tx_height_and_hash(&event);
+    if let Some((height, tx_hash)) = &tx {
+        if !dispatched.record(*height, tx_hash) {
+            trace!("Skipping already processed tx {tx_hash}");
+            return Ok(());
+        }
+    }
+
+    events
+        .send(Job {
+            tx,
+            item: Some(event),
+        })
+        .await
+        .map_err(|_| pipeline_stopped())
+}
+
+fn skip_event(tx: &Option<(u64, String)>, e: anyhow::Error) {
+    match tx {
+        Some((height, tx_hash)) => error!("Skipping event of tx {tx_hash} at height {height}: {e}"),
+        None => error!("Skipping event: {e}"),
+    }
+}
+
+fn receiver_stream<T>(receiver: mpsc::Receiver<T>) -> impl Stream<Item = T> {
+    unfold(receiver, |mut receiver| async move {
+        receiver.recv().await.map(|item| (item, receiver))
+    })
+}
+
+fn pipeline_stopped() -> HostError {
+    HostError::Fatal(anyhow!("event pipeline stopped"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::BTreeMap,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Mutex,
+        },
+    };
+
+    use quartz_cw_proof::proof::key::StorageKey;
+    use serde::de::DeserializeOwned;
+    use tendermint::{block::Height, Hash};
+    use tendermint_light_client::types::LightBlock;
+    use tendermint_rpc::event::EventData;
+
+    use super::*;
+    use crate::{
+        attestor::MockAttestor,
+        key_manager::default::DefaultKeyManager,
+        proof_of_publication::{
+            tests::{light_block, light_client_opts},
+            trust_anchor,
+        },
+        store::default::DefaultStore,
+        DefaultEnclave,
+    };
+
+    type TestEnclave = DefaultEnclave<(), MockAttestor, DefaultKeyManager, DefaultStore>;
+
+    #[derive(Debug, Default)]
+    struct TestChainClient {
+        proving: AtomicUsize,
+        max_proving: AtomicUsize,
+        sent: Mutex<Vec<u64>>,
+        /// The number of times that submitting the tx for an event still fails
+        failing_txs: Mutex<BTreeMap<u64, usize>>,
+        trusted_block: Mutex<Option<Height>>,
+    }
+
+    #[async_trait::async_trait]
+    impl ChainClient for TestChainClient {
+        type Contract = AccountId;
+        type Error = anyhow::Error;
+        type Proof = ();
+        type Query = ();
+        type TxConfig = ();
+        type TxOutput = u64;
+
+        async fn query_contract<R: DeserializeOwned + Default + Send>(
+            &self,
+            _contract: &Self::Contract,
+            _query: impl Into<Self::Query> + Send,
+        ) -> Result<R, Self::Error> {
+            Err(anyhow!("unsupported"))
+        }
+
+        async fn set_trusted_block(&self, height: Height, _hash: Hash) -> Result<(), Self::Error> {
+            *self.trusted_block.lock().expect("lock") = Some(height);
+            Ok(())
+        }
+
+        async fn existence_proof(
+            &self,
+            _contract: &Self::Contract,
+            _storage_key: &str,
+        ) -> Result<Self::Proof, Self::Error> {
+            Err(anyhow!("unsupported"))
+        }
+
+        async fn existence_proofs(
+            &self,
+            _contract: &Self::Contract,
+            _storage_keys: &[StorageKey],
+        ) -> Result<Self::Proof, Self::Error> {
+            Err(anyhow!("unsupported"))
+        }
+
+        async fn send_tx<T: Serialize + Send + Sync>(
+            &self,
+            _contract: &Self::Contract,
+            tx: T,
+            _config: Self::TxConfig,
+        ) -> Result<Self::TxOutput, Self::Error> {
+            let n = serde_json::from_value(serde_json::to_value(tx)?)?;
+            if let Some(failures @ 1..) = self.failing_txs.lock().expect("lock").get_mut(&n) {
+                *failures -= 1;
+                return Err(anyhow!("tx {n} failed"));
             }
+            self.sent.lock().expect("lock").push(n);
+            Ok(n)
+        }
 
-            let request = event.handle(&self.chain_client).await?;
-            let response = self.enclave_call(request).await?;
-            let tx_config = (self.gas_fn)(&response);
-            let output = self
-                .chain_client
-                .send_tx(&contract, response, tx_config)
-                .await?;
-            info!("tx output: {output}");
+        async fn wait_for_blocks(&self, _blocks: u8) -> Result<(), Self::Error> {
+            Ok(())
         }
+    }
 
-        client.close().expect("Failed to close client");
-        let _ = driver_handle.await;
+    /// An event whose proof takes longer the earlier the event is. The proof's light client trace
+    /// starts at the chain client's trusted block and ends at height `20 + n` (or is missing for
+    /// event 9, which the enclave rejects).
+    #[derive(Clone, Debug)]
+    struct TestEvent(u64);
+
+    impl TryFrom<TmEvent> for TestEvent {
+        type Error = anyhow::Error;
+
+        fn try_from(event: TmEvent) -> Result<Self, Self::Error> {
+            let n = event
+                .events
+                .as_ref()
+                .and_then(|events| events.get("wasm.n"))
+                .and_then(|n| n.first())
+                .ok_or_else(|| anyhow!("irrelevant event"))?;
+            Ok(Self(n.parse()?))
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl Handler<TestChainClient> for TestEvent {
+        type Error = anyhow::Error;
+        type Response = TestRequest;
+
+        async fn handle(self, ctx: &TestChainClient) -> Result<Self::Response, Self::Error> {
+            let proving = ctx.proving.fetch_add(1, Ordering::SeqCst) + 1;
+            ctx.max_proving.fetch_max(proving, Ordering::SeqCst);
+            let trusted_height = ctx
+                .trusted_block
+                .lock()
+                .expect("lock")
+                .map_or(1, |h| h.value());
+            sleep(Duration::from_millis(10 * (10 - self.0))).await;
+            ctx.proving.fetch_sub(1, Ordering::SeqCst);
+            let light_client_proof = match self.0 {
+                9 => vec![],
+                n => vec![light_block(trusted_height), light_block(20 + n)],
+            };
+            Ok(TestRequest {
+                n: self.0,
+                light_client_proof,
+            })
+        }
+    }
+
+    struct TestRequest {
+        n: u64,
+        light_client_proof: Vec<LightBlock>,
+    }
+
+    /// Verifies the light client proof's trust anchor like `ProofOfPublication::verify`.
+    #[async_trait::async_trait]
+    impl Handler<TestEnclave> for TestRequest {
+        type Error = Status;
+        type Response = u64;
+
+        async fn handle(self, ctx: &TestEnclave) -> Result<Self::Response, Self::Error> {
+            if self.light_client_proof.is_empty() {
+                return Err(Status::invalid_argument("missing light client proof"));
+            }
+            let (trusted_height, trusted_hash) =
+                trust_anchor(&ctx.store, &self.light_client_proof, &light_client_opts())
+                    .await
+                    .map_err(|e| Status::failed_precondition(e.to_string()))?;
+            let (first, target) = match &self.light_client_proof[..] {
+                [first, .., target] => (first, target),
+                _ => return Err(Status::invalid_argument("short light client proof")),
+            };
+            // the trace must start at the trust anchor (see `make_provider`)
+            if first.height() != trusted_height || first.signed_header.header.hash() != trusted_hash
+            {
+                return Err(Status::failed_precondition("untrusted light client proof"));
+            }
+            ctx.store
+                .advance_latest_verified_block(target.clone())
+                .await
+                .map_err(|e| Status::internal(e.to_string()))?;
+            Ok(self.n)
+        }
+    }
 
-        Ok(())
+    fn test_event(n: u64) -> TmEvent {
+        let mut events = BTreeMap::from([(
+            "execute._contract_address".to_string(),
+            vec![CONTRACT.to_string()],
+        )]);
+        // event 3 isn't handled by the host
+        if n != 3 {
+            events.insert("wasm.n".to_string(), vec![n.to_string()]);
+        }
+        TmEvent {
+            query: String::new(),
+            data: EventData::GenericJsonEvent(Default::default()),
+            events: Some(events),
+        }
+    }
+
+    const CONTRACT: &str = "neutron1des3nftm0wd7qhxc3dwlflk46rjucg5rlrcugukdpv7r4ly2ru8scx2su4";
+
+    #[test]
+    fn subscription_is_scoped_to_contract_and_filters() {
+        let contract: AccountId = CONTRACT.parse().expect("valid address");
+        let transfer = EventFilter::EventType("transfer".to_string());
+        let ping = EventFilter::Action("ping".to_string());
+
+        assert_eq!(
+            subscription_query(&contract, &[]).to_string(),
+            format!("tm.event = 'Tx' AND execute._contract_address = '{CONTRACT}'")
+        );
+        assert_eq!(
+            subscription_query(&contract, &[transfer.clone()]).to_string(),
+            format!(
+                "tm.event = 'Tx' AND execute._contract_address = '{CONTRACT}' AND \
+                 wasm-transfer._contract_address = '{CONTRACT}'"
+            )
+        );
+        assert_eq!(
+            subscription_query(&contract, &[ping.clone()]).to_string(),
+            format!(
+                "tm.event = 'Tx' AND execute._contract_address = '{CONTRACT}' AND \
+                 wasm.action = 'ping'"
+            )
+        );
+        // several filters are matched by the host
+        assert_eq!(
+            subscription_query(&contract, &[transfer, ping]),
+            subscription_query(&contract, &[])
+        );
+    }
+
+    #[test]
+    fn event_filters_match_contract_events() {
+        let event = |key: &str, value: &str| TmEvent {
+            query: String::new(),
+            data: EventData::GenericJsonEvent(Default::default()),
+            events: Some([(key.to_string(), vec![value.to_string()])].into()),
+        };
+        let transfer = EventFilter::EventType("transfer".to_string());
+        let ping = EventFilter::Action("ping".to_string());
+
+        assert!(transfer.matches(&event("wasm-transfer._contract_address", CONTRACT)));
+        assert!(!transfer.matches(&event("wasm-query_balance._contract_address", CONTRACT)));
+        assert!(ping.matches(&event("wasm.action", "ping")));
+        assert!(!ping.matches(&event("wasm.action", "pong")));
+    }
+
+    fn test_host(
+        pipeline_config: PipelineConfig,
+    ) -> DefaultHost<TestRequest, TestEvent, impl Fn(&u64), TestEnclave, TestChainClient> {
+        let enclave = TestEnclave {
+            attestor: MockAttestor,
+            key_manager: DefaultKeyManager::default(),
+            store: DefaultStore::default(),
+            ctx: (),
+        };
+        DefaultHost::new(enclave, TestChainClient::default(), |_: &u64| ())
+            .with_pipeline_config(pipeline_config)
+    }
+
+    /// Runs the events through the host's pipeline, as the txs `TX{n}` at height `10 + n`.
+    async fn run_pipeline<GF: Fn(&u64) + Send + Sync + 'static>(
+        host: &DefaultHost<TestRequest, TestEvent, GF, TestEnclave, TestChainClient>,
+        events: impl IntoIterator<Item = u64>,
+    ) -> Result<(), HostError> {
+        let contract: AccountId = CONTRACT.parse().expect("valid address");
+        host.enclave
+            .store
+            .set_contract(contract)
+            .await
+            .expect("infallible store");
+
+        let (events_tx, events_rx) = mpsc::channel(2);
+        let intake = async move {
+            for n in events {
+                let tx = Some((10 + n, format!("TX{n}")));
+                let item = Some(test_event(n));
+                events_tx
+                    .send(Job { tx, item })
+                    .await
+                    .map_err(|_| pipeline_stopped())?;
+            }
+            Ok(())
+        };
+        try_join(intake, host.pipeline(events_rx, &[]))
+            .await
+            .map(|_| ())
+    }
+
+    #[tokio::test]
+    async fn pipeline_proves_concurrently_and_keeps_order() {
+        let host = test_host(PipelineConfig {
+            proof_concurrency: 4,
+            tx_concurrency: 1,
+            queue_size: 2,
+            ..Default::default()
+        });
+        run_pipeline(&host, 0..8)
+            .await
+            .expect("pipeline must succeed");
+
+        assert_eq!(
+            *host.chain_client.sent.lock().expect("lock"),
+            [0, 1, 2, 4, 5, 6, 7]
+        );
+        assert_eq!(host.chain_client.max_proving.load(Ordering::SeqCst), 4);
+        let latest_verified_block = host
+            .enclave
+            .store
+            .get_latest_verified_block()
+            .await
+            .expect("infallible store");
+        assert_eq!(latest_verified_block.map(|l| l.height().value()), Some(27));
+
+        let checkpoint = host
+            .checkpoint_store
+            .load()
+            .await
+            .expect("in-memory store")
+            .expect("recorded checkpoint");
+        assert_eq!(checkpoint.height(), 17);
+        assert!(checkpoint.contains(17, "TX7"));
+    }
+
+    #[tokio::test]
+    async fn pipeline_retries_transient_errors_and_skips_failing_events() {
+        let host = test_host(PipelineConfig {
+            retry_backoff: Duration::from_millis(1),
+            ..Default::default()
+        });
+        // tx 1 succeeds when retried, tx 2 fails on every attempt and event 9 is rejected by the
+        // enclave
+        *host.chain_client.failing_txs.lock().expect("lock") = BTreeMap::from([(1, 1), (2, 10)]);
+        run_pipeline(&host, [0, 1, 2, 4, 9])
+            .await
+            .expect("failing events must not stop the pipeline");
+
+        assert_eq!(*host.chain_client.sent.lock().expect("lock"), [0, 1, 4]);
+        assert_eq!(
+            *host.chain_client.failing_txs.lock().expect("lock"),
+            BTreeMap::from([(1, 0), (2, 7)])
+        );
+
+        // the skipped events are recorded, so that they aren't processed again
+        let checkpoint = host
+            .checkpoint_store
+            .load()
+            .await
+            .expect("in-memory store")
+            .expect("recorded checkpoint");
+        assert_eq!(checkpoint.height(), 19);
+        assert!(checkpoint.contains(12, "TX2"));
+        assert!(checkpoint.contains(19, "TX9"));
     }
 }
@@ -0,0 +1,237 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Debug,
+    fs::{self, File},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use tendermint_rpc::{
+    endpoint::tx,
+    event::{Event as TmEvent, EventData, TxInfo, TxResult},
+};
+use tokio::sync::RwLock;
+
+/// Tracks which txs the host has already processed.
+///
+/// All txs below `height` have been processed, as have the txs at `height` whose hashes are in
+/// `tx_hashes`. This is enough to resume after a disconnect (or restart) without skipping or
+/// replaying txs, even if the host stopped halfway through a block.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    height: u64,
+    tx_hashes: BTreeSet<String>,
+}
+
+impl Checkpoint {
+    /// A checkpoint for a host that starts processing at (and including) the block at `height`.
+    pub fn starting_at(height: u64) -> Self {
+        Self {
+            height,
+            tx_hashes: BTreeSet::new(),
+        }
+    }
+
+    /// The last processed height, i.e. the height from which missed txs must be backfilled.
+    pub fn height(&self) -> u64 {
+        self.height
+    }
+
+    pub fn contains(&self, height: u64, tx_hash: &str) -> bool {
+        height < self.height || (height == self.height && self.tx_hashes.contains(tx_hash))
+    }
+
+    /// Records the tx as processed. Returns `false` if it was already processed.
+    pub fn record(&mut self, height: u64, tx_hash: &str) -> bool {
+        if self.contains(height, tx_hash) {
+            return false;
+        }
+        if height > self.height {
+            self.height = height;
+            self.tx_hashes.clear();
+        }
+        self.tx_hashes.insert(tx_hash.to_string())
+    }
+}
+
+/// The trait defines where the host persists its `Checkpoint`.
+#[async_trait::async_trait]
+pub trait CheckpointStore: Debug + Send + Sync + 'static {
+    async fn load(&self) -> Result<Option<Checkpoint>, anyhow::Error>;
+
+    async fn save(&self, checkpoint: &Checkpoint) -> Result<(), anyhow::Error>;
+}
+
+/// A `CheckpointStore` that only lives as long as the host, i.e. it lets the host catch up after
+/// websocket disconnects but not after restarts.
+#[derive(Debug, Default)]
+pub struct InMemoryCheckpointStore {
+    checkpoint: RwLock<Option<Checkpoint>>,
+}
+
+#[async_trait::async_trait]
+impl CheckpointStore for InMemoryCheckpointStore {
+    async fn load(&self) -> Result<Option<Checkpoint>, anyhow::Error> {
+        Ok(self.checkpoint.read().await.clone())
+    }
+
+    async fn save(&self, checkpoint: &Checkpoint) -> Result<(), anyhow::Error> {
+        *self.checkpoint.write().await = Some(checkpoint.clone());
+        Ok(())
+    }
+}
+
+/// A `CheckpointStore` that persists the checkpoint as JSON so that it survives host restarts.
+///
+/// The file is rewritten atomically (write to a temporary file, sync, then rename).
+#[derive(Clone, Debug)]
+pub struct FileCheckpointStore {
+    path: PathBuf,
+}
+
+impl FileCheckpointStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+#[async_trait::async_trait]
+impl CheckpointStore for FileCheckpointStore {
+    async fn load(&self) -> Result<Option<Checkpoint>, anyhow::Error> {
+        match fs::read(&self.path) {
+            Ok(contents) => Ok(Some(serde_json::from_slice(&contents)?)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn save(&self, checkpoint: &Checkpoint) -> Result<(), anyhow::Error> {
+        let mut tmp_path = self.path.as_os_str().to_owned();
+        tmp_path.push(".tmp");
+        let tmp_path = PathBuf::from(tmp_path);
+
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(&serde_json::to_vec(checkpoint)?)?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, &self.path)?;
+
+        Ok(())
+    }
+}
+
+/// Returns the height and (hex) hash of the tx that emitted the event, if it is a tx event.
+pub fn tx_height_and_hash(event: &TmEvent) -> Option<(u64, String)> {
+    let EventData::Tx { tx_result } = &event.data else {
+        return None;
+    };
+    let height = u64::try_from(tx_result.height).ok()?;
+    let tx_hash = event.events.as_ref()?.get("tx.hash")?.first()?.clone();
+    Some((height, tx_hash))
+}
+
+/// Converts a `tx_search` result into the event that the websocket subscription would have
+/// delivered for it, so that backfilled txs go through the same path as live ones.
+pub fn tx_to_event(query: String, tx: tx::Response) -> TmEvent {
+    let mut events: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    events.insert("tm.event".to_string(), vec!["Tx".to_string()]);
+    events.insert("tx.hash".to_string(), vec![tx.hash.to_string()]);
+    events.insert("tx.height".to_string(), vec![tx.height.to_string()]);
+    for event in &tx.tx_result.events {
+        for attribute in &event.attributes {
+            if let (Ok(key), Ok(value)) = (attribute.key_str(), attribute.value_str()) {
+                events
+                    .entry(format!("{}.{}", event.kind, key))
+                    .or_default()
+                    .push(value.to_string());
+            }
+        }
+    }
+
+    TmEvent {
+        query,
+        data: EventData::Tx {
+            tx_result: TxInfo {
+                height: tx.height.into(),
+                index: Some(tx.index.into()),
+                tx: tx.tx,
+                result: TxResult {
+                    log: Some(tx.tx_result.log),
+                    gas_wanted: Some(tx.tx_result.gas_wanted.to_string()),
+                    gas_used: Some(tx.tx_result.gas_used.to_string()),
+                    events: tx.tx_result.events,
+                },
+            },
+        },
+        events: Some(events),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tendermint::{abci, Hash};
+
+    use super::*;
+
+    const CONTRACT: &str = "wasm14hj2tavq8fpesdwxxcu44rty3hh90vhujrvcmstl4zr3txmfvw9s4hmalr";
+
+    #[test]
+    fn checkpoint_deduplicates_by_height_and_hash() {
+        let mut checkpoint = Checkpoint::starting_at(10);
+        assert!(!checkpoint.contains(10, "A"));
+        assert!(checkpoint.contains(9, "A"));
+
+        assert!(checkpoint.record(10, "A"));
+        assert!(!checkpoint.record(10, "A"));
+        assert!(checkpoint.record(10, "B"));
+        assert_eq!(checkpoint.height(), 10);
+
+        // moving on to the next block forgets the hashes of the previous one
+        assert!(checkpoint.record(12, "C"));
+        assert_eq!(checkpoint.height(), 12);
+        assert!(checkpoint.contains(11, "X"));
+        assert!(checkpoint.contains(12, "C"));
+        assert!(!checkpoint.contains(12, "A"));
+        assert!(!checkpoint.record(10, "D"));
+    }
+
+    #[tokio::test]
+    async fn file_checkpoint_store_roundtrip() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let store = FileCheckpointStore::new(dir.path().join("checkpoint.json"));
+        assert_eq!(store.load().await.expect("load"), None);
+
+        let mut checkpoint = Checkpoint::starting_at(5);
+        checkpoint.record(7, "A");
+        store.save(&checkpoint).await.expect("save");
+        assert_eq!(store.load().await.expect("load"), Some(checkpoint));
+    }
+
+    #[test]
+    fn backfilled_tx_matches_subscription_event() {
+        let hash = Hash::Sha256([0xab; 32]);
+        let tx = tx::Response {
+            hash,
+            height: 42u32.into(),
+            index: 3,
+            tx_result: abci::types::ExecTxResult {
+                events: vec![abci::Event::new(
+                    "execute",
+                    [("_contract_address", CONTRACT)],
+                )],
+                ..Default::default()
+            },
+            tx: vec![1, 2, 3],
+            proof: None,
+        };
+
+        let event = tx_to_event("tm.event = 'Tx'".to_string(), tx);
+        let events = event.events.as_ref().expect("events");
+        assert_eq!(events["execute._contract_address"], [CONTRACT]);
+        assert_eq!(tx_height_and_hash(&event), Some((42, hash.to_string())));
+    }
+}
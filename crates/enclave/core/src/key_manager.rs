@@ -1,4 +1,7 @@
+use k256::ecdsa::SigningKey;
+
 pub mod default;
+pub mod sealed;
 pub mod shared;
 
 #[async_trait::async_trait]
@@ -7,3 +10,31 @@ pub trait KeyManager: Send + Sync + 'static {
 
     async fn pub_key(&self) -> Self::PubKey;
 }
+
+/// A `KeyManager` whose key can be rotated. The previous key is retained after a rotation so that
+/// data encrypted to it can still be decrypted and re-encrypted under the new key.
+#[async_trait::async_trait]
+pub trait RotatingKeyManager: KeyManager {
+    type Error: ToString + Send + Sync;
+
+    /// Epoch of the current key, starting at zero and incremented with every rotation.
+    async fn epoch(&self) -> u64;
+
+    async fn prev_pub_key(&self) -> Option<Self::PubKey>;
+
+    /// Generate a new key, retire the current one and return the new epoch.
+    async fn rotate(&mut self) -> Result<u64, Self::Error>;
+}
+
+/// A `RotatingKeyManager` whose session key can be handed over to an enclave that takes over the
+/// session (e.g. after an upgrade).
+#[async_trait::async_trait]
+pub trait HandoverKeyManager: RotatingKeyManager {
+    /// The current secret key, to be handed over to the enclave taking over the session.
+    async fn sk(&self) -> SigningKey;
+
+    /// Keep `prev_sk`, the key handed over by the enclave that held the session, as the previous
+    /// key and move to `epoch`. The current key stays in place, as it's the one the handover
+    /// publishes as the session pub key.
+    async fn take_over(&mut self, prev_sk: SigningKey, epoch: u64) -> Result<(), Self::Error>;
+}
@@ -1,16 +1,22 @@
+use std::convert::Infallible;
+
 use k256::ecdsa::{SigningKey, VerifyingKey};
 
-use crate::key_manager::KeyManager;
+use crate::key_manager::{HandoverKeyManager, KeyManager, RotatingKeyManager};
 
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub struct DefaultKeyManager {
     pub sk: SigningKey,
+    pub prev_sk: Option<SigningKey>,
+    pub epoch: u64,
 }
 
 impl Default for DefaultKeyManager {
     fn default() -> Self {
         Self {
             sk: SigningKey::random(&mut rand::thread_rng()),
+            prev_sk: None,
+            epoch: 0,
         }
     }
 }
@@ -24,6 +30,39 @@ impl KeyManager for DefaultKeyManager {
     }
 }
 
+#[async_trait::async_trait]
+impl RotatingKeyManager for DefaultKeyManager {
+    type Error = Infallible;
+
+    async fn epoch(&self) -> u64 {
+        self.epoch
+    }
+
+    async fn prev_pub_key(&self) -> Option<Self::PubKey> {
+        self.prev_sk.clone().map(|sk| PubKey(sk.into()))
+    }
+
+    async fn rotate(&mut self) -> Result<u64, Self::Error> {
+        let sk = SigningKey::random(&mut rand::thread_rng());
+        self.prev_sk = Some(std::mem::replace(&mut self.sk, sk));
+        self.epoch += 1;
+        Ok(self.epoch)
+    }
+}
+
+#[async_trait::async_trait]
+impl HandoverKeyManager for DefaultKeyManager {
+    async fn sk(&self) -> SigningKey {
+        self.sk.clone()
+    }
+
+    async fn take_over(&mut self, prev_sk: SigningKey, epoch: u64) -> Result<(), Self::Error> {
+        self.prev_sk = Some(prev_sk);
+        self.epoch = epoch;
+        Ok(())
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct PubKey(VerifyingKey);
 
@@ -38,3 +77,31 @@ impl From<PubKey> for VerifyingKey {
         value.0
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn rotate_retains_previous_key_and_increments_epoch() {
+        let mut key_manager = DefaultKeyManager::default();
+        assert_eq!(key_manager.epoch().await, 0);
+        assert!(key_manager.prev_pub_key().await.is_none());
+
+        let pk: Vec<u8> = key_manager.pub_key().await.into();
+        assert_eq!(key_manager.rotate().await, Ok(1));
+        let rotated_pk: Vec<u8> = key_manager.pub_key().await.into();
+        assert_ne!(rotated_pk, pk);
+        assert_eq!(
+            key_manager.prev_pub_key().await.map(Vec::<u8>::from),
+            Some(pk)
+        );
+
+        // only the immediately preceding key is retained
+        assert_eq!(key_manager.rotate().await, Ok(2));
+        assert_eq!(
+            key_manager.prev_pub_key().await.map(Vec::<u8>::from),
+            Some(rotated_pk)
+        );
+    }
+}
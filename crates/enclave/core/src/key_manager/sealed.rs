@@ -0,0 +1,142 @@
+use k256::ecdsa::SigningKey;
+
+use crate::{
+    key_manager::{
+        default::{DefaultKeyManager, PubKey},
+        HandoverKeyManager, KeyManager, RotatingKeyManager,
+    },
+    store::sealed::{DefaultSealer, SealedStore, SealedStoreError, Sealer},
+};
+
+/// A `RotatingKeyManager` whose keys are persisted in a `SealedStore`, so that the enclave keeps
+/// its session key (and the key it retired last) across restarts.
+pub struct SealedKeyManager<S = DefaultSealer> {
+    keys: DefaultKeyManager,
+    store: SealedStore<S>,
+}
+
+impl<S> Clone for SealedKeyManager<S> {
+    fn clone(&self) -> Self {
+        Self {
+            keys: self.keys.clone(),
+            store: self.store.clone(),
+        }
+    }
+}
+
+impl<S: Sealer> SealedKeyManager<S> {
+    /// Restores the keys persisted in `store`, or generates and persists new ones if there are
+    /// none yet.
+    pub async fn open(store: SealedStore<S>) -> Result<Self, SealedStoreError> {
+        let keys = match store.get_key_manager().await {
+            Some(keys) => keys,
+            None => {
+                let keys = DefaultKeyManager::default();
+                store.set_key_manager(keys.clone()).await?;
+                keys
+            }
+        };
+
+        Ok(Self { keys, store })
+    }
+
+    pub fn keys(&self) -> &DefaultKeyManager {
+        &self.keys
+    }
+}
+
+#[async_trait::async_trait]
+impl<S: Sealer> KeyManager for SealedKeyManager<S> {
+    type PubKey = PubKey;
+
+    async fn pub_key(&self) -> Self::PubKey {
+        self.keys.pub_key().await
+    }
+}
+
+#[async_trait::async_trait]
+impl<S: Sealer> RotatingKeyManager for SealedKeyManager<S> {
+    type Error = SealedStoreError;
+
+    async fn epoch(&self) -> u64 {
+        self.keys.epoch
+    }
+
+    async fn prev_pub_key(&self) -> Option<Self::PubKey> {
+        self.keys.prev_pub_key().await
+    }
+
+    /// The new key is only used once it has been persisted, so a failed write leaves the current
+    /// key in place.
+    async fn rotate(&mut self) -> Result<u64, Self::Error> {
+        let mut keys = self.keys.clone();
+        let epoch = keys.rotate().await.unwrap_or_else(|e| match e {});
+        self.store.set_key_manager(keys.clone()).await?;
+        self.keys = keys;
+        Ok(epoch)
+    }
+}
+
+#[async_trait::async_trait]
+impl<S: Sealer> HandoverKeyManager for SealedKeyManager<S> {
+    async fn sk(&self) -> SigningKey {
+        self.keys.sk.clone()
+    }
+
+    async fn take_over(&mut self, prev_sk: SigningKey, epoch: u64) -> Result<(), Self::Error> {
+        let mut keys = self.keys.clone();
+        keys.take_over(prev_sk, epoch)
+            .await
+            .unwrap_or_else(|e| match e {});
+        self.store.set_key_manager(keys.clone()).await?;
+        self.keys = keys;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quartz_contract_core::state::{Config, LightClientOpts};
+
+    use super::*;
+    use crate::store::sealed::AeadSealer;
+
+    fn open_store(path: &std::path::Path) -> SealedStore<AeadSealer> {
+        let light_client_opts =
+            LightClientOpts::new("testing".to_string(), 1, [0; 32], (1, 3), 1_209_600, 5, 20)
+                .expect("valid light client opts");
+        let config = Config::new([1; 32], light_client_opts, None, None);
+        SealedStore::open(path, AeadSealer::from_secret(b"secret"), config).expect("open")
+    }
+
+    #[tokio::test]
+    async fn keys_are_restored_after_reopening() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let path = dir.path().join("store.sealed");
+
+        let key_manager = SealedKeyManager::open(open_store(&path))
+            .await
+            .expect("open");
+        let pk: Vec<u8> = key_manager.pub_key().await.into();
+        drop(key_manager);
+
+        let mut key_manager = SealedKeyManager::open(open_store(&path))
+            .await
+            .expect("reopen");
+        assert_eq!(Vec::<u8>::from(key_manager.pub_key().await), pk);
+
+        assert_eq!(key_manager.rotate().await.expect("rotate"), 1);
+        let rotated_pk: Vec<u8> = key_manager.pub_key().await.into();
+        drop(key_manager);
+
+        let key_manager = SealedKeyManager::open(open_store(&path))
+            .await
+            .expect("reopen");
+        assert_eq!(key_manager.epoch().await, 1);
+        assert_eq!(Vec::<u8>::from(key_manager.pub_key().await), rotated_pk);
+        assert_eq!(
+            key_manager.prev_pub_key().await.map(Vec::<u8>::from),
+            Some(pk)
+        );
+    }
+}
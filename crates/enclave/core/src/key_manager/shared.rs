@@ -1,8 +1,9 @@
 use std::sync::Arc;
 
+use k256::ecdsa::SigningKey;
 use tokio::sync::{RwLock, RwLockReadGuard};
 
-use crate::key_manager::KeyManager;
+use crate::key_manager::{HandoverKeyManager, KeyManager, RotatingKeyManager};
 
 #[derive(Clone, Debug)]
 pub struct SharedKeyManager<K> {
@@ -29,3 +30,31 @@ impl<K: KeyManager> KeyManager for SharedKeyManager<K> {
         self.inner.read().await.pub_key().await
     }
 }
+
+#[async_trait::async_trait]
+impl<K: RotatingKeyManager> RotatingKeyManager for SharedKeyManager<K> {
+    type Error = K::Error;
+
+    async fn epoch(&self) -> u64 {
+        self.inner.read().await.epoch().await
+    }
+
+    async fn prev_pub_key(&self) -> Option<Self::PubKey> {
+        self.inner.read().await.prev_pub_key().await
+    }
+
+    async fn rotate(&mut self) -> Result<u64, Self::Error> {
+        self.inner.write().await.rotate().await
+    }
+}
+
+#[async_trait::async_trait]
+impl<K: HandoverKeyManager> HandoverKeyManager for SharedKeyManager<K> {
+    async fn sk(&self) -> SigningKey {
+        self.inner.read().await.sk().await
+    }
+
+    async fn take_over(&mut self, prev_sk: SigningKey, epoch: u64) -> Result<(), Self::Error> {
+        self.inner.write().await.take_over(prev_sk, epoch).await
+    }
+}
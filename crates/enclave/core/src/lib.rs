@@ -17,8 +17,14 @@ use quartz_contract_core::state::Config;
 
 use crate::{
     attestor::{Attestor, DefaultAttestor},
-    key_manager::{default::DefaultKeyManager, shared::SharedKeyManager, KeyManager},
-    store::{default::DefaultStore, Store},
+    key_manager::{
+        default::DefaultKeyManager, sealed::SealedKeyManager, shared::SharedKeyManager, KeyManager,
+    },
+    store::{
+        default::DefaultStore,
+        sealed::{SealedStore, SealedStoreError, Sealer},
+        Store,
+    },
 };
 
 pub mod attestor;
@@ -32,8 +38,8 @@ pub mod proof_of_publication;
 pub mod store;
 pub mod types;
 
-pub type DefaultSharedEnclave<C, K = DefaultKeyManager> =
-    DefaultEnclave<C, DefaultAttestor, SharedKeyManager<K>, DefaultStore>;
+pub type DefaultSharedEnclave<C, K = DefaultKeyManager, S = DefaultStore> =
+    DefaultEnclave<C, DefaultAttestor, SharedKeyManager<K>, S>;
 
 #[async_trait::async_trait]
 pub trait Enclave: Send + Sync + 'static {
@@ -75,6 +81,36 @@ impl<C: Send + Sync + 'static> DefaultSharedEnclave<C> {
             ctx: self.ctx,
         }
     }
+
+    /// Use `with_sealed_store` for a `SealedStore`, so that the session keys are persisted along
+    /// with the session.
+    pub fn with_store<S: Store>(
+        self,
+        store: S,
+    ) -> DefaultEnclave<C, <Self as Enclave>::Attestor, <Self as Enclave>::KeyManager, S> {
+        DefaultEnclave {
+            attestor: self.attestor,
+            key_manager: self.key_manager,
+            store,
+            ctx: self.ctx,
+        }
+    }
+
+    /// Uses `store` for the enclave's state and restores the session keys persisted in it (or
+    /// persists new ones), so that a restarted enclave can carry on with its session.
+    pub async fn with_sealed_store<T: Sealer>(
+        self,
+        store: SealedStore<T>,
+    ) -> Result<DefaultSharedEnclave<C, SealedKeyManager<T>, SealedStore<T>>, SealedStoreError>
+    {
+        let key_manager = SealedKeyManager::open(store.clone()).await?;
+        Ok(DefaultEnclave {
+            attestor: self.attestor,
+            key_manager: SharedKeyManager::wrapping(key_manager),
+            store,
+            ctx: self.ctx,
+        })
+    }
 }
 
 #[async_trait::async_trait]
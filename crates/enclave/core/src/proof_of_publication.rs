@@ -1,82 +1,684 @@
-use std::time::Duration;
+use std::{collections::BTreeMap, iter, time::Duration};
 
 use cosmrs::AccountId;
+use displaydoc::Display;
 use quartz_contract_core::state::LightClientOpts;
-use quartz_cw_proof::proof::{
-    cw::{CwProof, RawCwProof},
-    key::CwAbciKey,
-    Proof,
+use quartz_cw_proof::{
+    error::ProofError,
+    proof::{
+        cw::{CwNonExistenceProof, CwProof, RawCwNonExistenceProof, RawCwProof},
+        key::{CwAbciKey, StorageKey},
+        Proof,
+    },
 };
-use quartz_tm_stateless_verifier::make_provider;
+use quartz_tm_stateless_verifier::{make_provider, Error as StatelessVerifierError};
 use serde::{Deserialize, Serialize};
+use tendermint::{block::Height, Hash, Time};
 use tendermint_light_client::{
     light_client::Options,
     types::{LightBlock, TrustThreshold},
 };
 
+use crate::store::Store;
+
+#[derive(Debug, Display)]
+pub enum ProofOfPublicationError {
+    /// empty light client proof
+    EmptyLightClientProof,
+    /// invalid light client options: {0}
+    InvalidLightClientOpts(String),
+    /// light client verification failed: {0}
+    LightClientVerification(StatelessVerifierError),
+    /// merkle proof key mismatch
+    MerkleProofKeyMismatch,
+    /// missing merkle proof for storage key: {0}
+    MissingMerkleProof(String),
+    /// merkle proof verification failed: {0}
+    MerkleProofVerification(ProofError),
+    /// stale proof: target header time ({target}) is older than the latest verified header time ({latest}) by more than {max_block_lag}s
+    StaleProof {
+        target: Time,
+        latest: Time,
+        max_block_lag: u64,
+    },
+    /// store error: {0}
+    Store(String),
+}
+
+/// A proof of publication of a contract state item, where `P` is either a proof of its existence
+/// (`RawCwProof`) or of its absence (`RawCwNonExistenceProof`).
 #[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct ProofOfPublication<M> {
+pub struct ProofOfPublication<M, P = RawCwProof> {
     light_client_proof: Vec<LightBlock>,
-    merkle_proof: RawCwProof,
+    merkle_proof: P,
     msg: M,
 }
 
 impl<M> ProofOfPublication<M> {
-    pub fn verify(
+    /// Verifies the light client proof and the merkle proof of the contract's state against it.
+    ///
+    /// The light client proof is verified starting from a light block recently verified by the
+    /// enclave (as tracked in the `store`) if the proof starts at one, or from the trusted height
+    /// and hash in the config otherwise. The target header must not be older than the latest
+    /// verified header by more than `max_block_lag` seconds. On success, the target light block
+    /// becomes the latest verified light block if it is newer.
+    pub async fn verify<S: Store>(
         self,
+        store: &S,
         light_client_opts: &LightClientOpts,
         contract_address: AccountId,
         storage_key: String,
         storage_namespace: Option<String>,
-    ) -> Result<(Vec<u8>, M), String> {
-        let config_trust_threshold = light_client_opts.trust_threshold();
-        let trust_threshold =
-            TrustThreshold::new(config_trust_threshold.0, config_trust_threshold.1).unwrap();
-
-        let config_trusting_period = light_client_opts.trusting_period();
-        let trusting_period = Duration::from_secs(config_trusting_period);
-
-        let config_clock_drift = light_client_opts.max_clock_drift();
-        let clock_drift = Duration::from_secs(config_clock_drift);
-        let options = Options {
-            trust_threshold,
-            trusting_period,
-            clock_drift,
-        };
+    ) -> Result<(Vec<u8>, M), ProofOfPublicationError> {
+        let primary_block =
+            verify_light_client_proof(store, light_client_opts, self.light_client_proof).await?;
 
-        let target_height = self.light_client_proof.last().unwrap().height();
-
-        let primary_block = make_provider(
-            light_client_opts.chain_id(),
-            light_client_opts.trusted_height().try_into().unwrap(),
-            light_client_opts
-                .trusted_hash()
-                .to_vec()
-                .try_into()
-                .unwrap(),
-            self.light_client_proof,
-            options,
-        )
-        .and_then(|mut primary| primary.verify_to_height(target_height))
-        .map_err(|e| e.to_string())?;
+        let key = CwAbciKey::new(contract_address, storage_key, storage_namespace);
+        let value = verify_merkle_proof(self.merkle_proof, key, &primary_block)?;
+
+        advance_latest_verified_block(store, primary_block).await?;
+
+        Ok((value, self.msg))
+    }
+}
+
+impl<M> ProofOfPublication<M, RawCwNonExistenceProof> {
+    /// Verifies the light client proof and the merkle proof that there is no value in the
+    /// contract's state for the given key.
+    ///
+    /// See [`ProofOfPublication::verify`] for how the light client proof is verified.
+    pub async fn verify<S: Store>(
+        self,
+        store: &S,
+        light_client_opts: &LightClientOpts,
+        contract_address: AccountId,
+        storage_key: String,
+        storage_namespace: Option<String>,
+    ) -> Result<M, ProofOfPublicationError> {
+        let primary_block =
+            verify_light_client_proof(store, light_client_opts, self.light_client_proof).await?;
 
         let key = CwAbciKey::new(contract_address, storage_key, storage_namespace);
         if key.into_vec() != self.merkle_proof.key() {
-            return Err("Merkle proof key mismatch".to_string());
+            return Err(ProofOfPublicationError::MerkleProofKeyMismatch);
+        }
+
+        CwNonExistenceProof::from(self.merkle_proof)
+            .verify(app_hash(&primary_block))
+            .map_err(ProofOfPublicationError::MerkleProofVerification)?;
+
+        advance_latest_verified_block(store, primary_block).await?;
+
+        Ok(self.msg)
+    }
+}
+
+/// A proof of publication of several contract state items at the same height, i.e. multiple
+/// merkle proofs under a single light client proof.
+///
+/// Its serialized form is compatible with the `ProofOutput` produced by the tm-prover when it is
+/// given `additional_storage_keys`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MultiProofOfPublication<M> {
+    light_client_proof: Vec<LightBlock>,
+    merkle_proof: RawCwProof,
+    #[serde(default)]
+    additional_merkle_proofs: Vec<RawCwProof>,
+    msg: M,
+}
+
+impl<M> MultiProofOfPublication<M> {
+    /// Verifies the light client proof and a merkle proof for each of the `storage_keys` against
+    /// it, returning the proven values by key.
+    ///
+    /// Every requested key must be proven, whereas proofs for keys that weren't requested are
+    /// ignored. See [`ProofOfPublication::verify`] for how the light client proof is verified.
+    pub async fn verify<S: Store>(
+        self,
+        store: &S,
+        light_client_opts: &LightClientOpts,
+        contract_address: AccountId,
+        storage_keys: Vec<StorageKey>,
+    ) -> Result<(BTreeMap<StorageKey, Vec<u8>>, M), ProofOfPublicationError> {
+        let mut merkle_proofs: Vec<_> = iter::once(self.merkle_proof)
+            .chain(self.additional_merkle_proofs)
+            .map(Some)
+            .collect();
+
+        // pick the proof for each requested key first, so that a missing one is reported as such
+        let mut proofs_by_key = Vec::with_capacity(storage_keys.len());
+        for storage_key in storage_keys {
+            let key = CwAbciKey::from_storage_key(contract_address.clone(), storage_key.clone());
+            let key_bytes = key.clone().into_vec();
+            let merkle_proof = merkle_proofs
+                .iter_mut()
+                .find(|p| p.as_ref().is_some_and(|p| p.key() == key_bytes))
+                .and_then(Option::take)
+                .ok_or_else(|| {
+                    ProofOfPublicationError::MissingMerkleProof(storage_key.to_string())
+                })?;
+            proofs_by_key.push((storage_key, key, merkle_proof));
         }
 
-        let proof = CwProof::from(self.merkle_proof);
-        proof
-            .verify(
-                primary_block
-                    .signed_header
-                    .header
-                    .app_hash
-                    .as_bytes()
-                    .to_vec(),
-            )
-            .map_err(|e| e.to_string())?;
-
-        Ok((proof.value, self.msg))
+        let primary_block =
+            verify_light_client_proof(store, light_client_opts, self.light_client_proof).await?;
+
+        let mut values = BTreeMap::new();
+        for (storage_key, key, merkle_proof) in proofs_by_key {
+            let value = verify_merkle_proof(merkle_proof, key, &primary_block)?;
+            values.insert(storage_key, value);
+        }
+
+        advance_latest_verified_block(store, primary_block).await?;
+
+        Ok((values, self.msg))
+    }
+}
+
+/// Verifies the `light_client_proof` up to its last block, which is returned.
+async fn verify_light_client_proof<S: Store>(
+    store: &S,
+    light_client_opts: &LightClientOpts,
+    light_client_proof: Vec<LightBlock>,
+) -> Result<LightBlock, ProofOfPublicationError> {
+    let config_trust_threshold = light_client_opts.trust_threshold();
+    let trust_threshold =
+        TrustThreshold::new(config_trust_threshold.0, config_trust_threshold.1)
+            .map_err(|e| ProofOfPublicationError::InvalidLightClientOpts(e.to_string()))?;
+
+    let config_trusting_period = light_client_opts.trusting_period();
+    let trusting_period = Duration::from_secs(config_trusting_period);
+
+    let config_clock_drift = light_client_opts.max_clock_drift();
+    let clock_drift = Duration::from_secs(config_clock_drift);
+    let options = Options {
+        trust_threshold,
+        trusting_period,
+        clock_drift,
+    };
+
+    let target_height = light_client_proof
+        .last()
+        .ok_or(ProofOfPublicationError::EmptyLightClientProof)?
+        .height();
+
+    let (trusted_height, trusted_hash) =
+        trust_anchor(store, &light_client_proof, light_client_opts).await?;
+
+    let primary_block = make_provider(
+        light_client_opts.chain_id(),
+        trusted_height,
+        trusted_hash,
+        light_client_proof,
+        options,
+    )
+    .and_then(|mut primary| primary.verify_to_height(target_height))
+    .map_err(ProofOfPublicationError::LightClientVerification)?;
+
+    let latest_verified_block = store
+        .get_latest_verified_block()
+        .await
+        .map_err(|e| ProofOfPublicationError::Store(e.to_string()))?;
+    if let Some(latest) = &latest_verified_block {
+        ensure_fresh(
+            primary_block.signed_header.header.time,
+            latest.signed_header.header.time,
+            light_client_opts.max_block_lag(),
+        )?;
+    }
+
+    Ok(primary_block)
+}
+
+/// Verifies the `merkle_proof` for `key` against the app hash of the `primary_block` and returns
+/// the proven value.
+fn verify_merkle_proof(
+    merkle_proof: RawCwProof,
+    key: CwAbciKey,
+    primary_block: &LightBlock,
+) -> Result<Vec<u8>, ProofOfPublicationError> {
+    if key.into_vec() != merkle_proof.key() {
+        return Err(ProofOfPublicationError::MerkleProofKeyMismatch);
+    }
+
+    let proof = CwProof::from(merkle_proof);
+    proof
+        .verify(app_hash(primary_block))
+        .map_err(ProofOfPublicationError::MerkleProofVerification)?;
+
+    Ok(proof.value)
+}
+
+fn app_hash(light_block: &LightBlock) -> Vec<u8> {
+    light_block
+        .signed_header
+        .header
+        .app_hash
+        .as_bytes()
+        .to_vec()
+}
+
+async fn advance_latest_verified_block<S: Store>(
+    store: &S,
+    primary_block: LightBlock,
+) -> Result<(), ProofOfPublicationError> {
+    store
+        .advance_latest_verified_block(primary_block)
+        .await
+        .map(|_| ())
+        .map_err(|e| ProofOfPublicationError::Store(e.to_string()))
+}
+
+/// Returns the height and hash of the light block the `light_client_proof` should be verified
+/// from.
+///
+/// Proofs that start at one of the light blocks the enclave verified recently are verified from
+/// it, so that traces stay short and the trust anchor doesn't expire. This includes proofs that
+/// were generated before a newer light block was verified (e.g. by a host that generates proofs
+/// for several events concurrently). Any other proof is verified from the configured trusted
+/// height (e.g. during the handshake).
+pub(crate) async fn trust_anchor<S: Store>(
+    store: &S,
+    light_client_proof: &[LightBlock],
+    light_client_opts: &LightClientOpts,
+) -> Result<(Height, Hash), ProofOfPublicationError> {
+    let first_height = light_client_proof
+        .first()
+        .ok_or(ProofOfPublicationError::EmptyLightClientProof)?
+        .height();
+
+    let verified_hash = store
+        .get_verified_block_hash(first_height)
+        .await
+        .map_err(|e| ProofOfPublicationError::Store(e.to_string()))?;
+    if let Some(verified_hash) = verified_hash {
+        return Ok((first_height, verified_hash));
+    }
+
+    let trusted_height =
+        light_client_opts
+            .trusted_height()
+            .try_into()
+            .map_err(|e: tendermint::Error| {
+                ProofOfPublicationError::InvalidLightClientOpts(e.to_string())
+            })?;
+    let trusted_hash = light_client_opts
+        .trusted_hash()
+        .to_vec()
+        .try_into()
+        .map_err(|e: tendermint::Error| {
+            ProofOfPublicationError::InvalidLightClientOpts(e.to_string())
+        })?;
+    Ok((trusted_height, trusted_hash))
+}
+
+/// Ensures the `target` header time isn't older than the `latest` verified header time by more
+/// than `max_block_lag` seconds.
+fn ensure_fresh(
+    target: Time,
+    latest: Time,
+    max_block_lag: u64,
+) -> Result<(), ProofOfPublicationError> {
+    match latest.duration_since(target) {
+        Ok(lag) if lag > Duration::from_secs(max_block_lag) => {
+            Err(ProofOfPublicationError::StaleProof {
+                target,
+                latest,
+                max_block_lag,
+            })
+        }
+        // `target` is either newer than `latest` or within the allowed lag
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use ics23::{
+        commitment_proof, CommitmentProof, ExistenceProof, HashOp, InnerOp, LeafOp, LengthOp,
+    };
+    use prost::Message;
+    use tendermint::{
+        account,
+        block::{self, parts, Commit, Round},
+        chain,
+        merkle::proof::{ProofOp, ProofOps},
+        node,
+        validator::Set as ValidatorSet,
+        AppHash,
+    };
+    use tendermint_light_client::types::SignedHeader;
+
+    use super::*;
+    use crate::store::default::DefaultStore;
+
+    /// An (unsigned) light block at `height`, with one block per second.
+    pub(crate) fn light_block(height: u64) -> LightBlock {
+        let time = Time::from_unix_timestamp(height as i64, 0).expect("valid time");
+        light_block_at(height, time, Hash::None, AppHash::default())
+    }
+
+    /// An (unsigned) light block at `height` and `time`, with an empty validator set whose hash
+    /// is `validators_hash`.
+    fn light_block_at(
+        height: u64,
+        time: Time,
+        validators_hash: Hash,
+        app_hash: AppHash,
+    ) -> LightBlock {
+        let height = Height::try_from(height).expect("valid height");
+        let header = block::Header {
+            version: block::header::Version { block: 11, app: 0 },
+            chain_id: chain::Id::try_from("testing").expect("valid chain id"),
+            height,
+            time,
+            last_block_id: None,
+            last_commit_hash: None,
+            data_hash: None,
+            validators_hash,
+            next_validators_hash: validators_hash,
+            consensus_hash: Hash::None,
+            app_hash,
+            last_results_hash: None,
+            evidence_hash: None,
+            proposer_address: account::Id::new([0; 20]),
+        };
+        let commit = Commit {
+            height,
+            round: Round::default(),
+            block_id: block::Id {
+                hash: header.hash(),
+                part_set_header: parts::Header::default(),
+            },
+            signatures: vec![],
+        };
+        LightBlock::new(
+            SignedHeader::new(header, commit).expect("valid signed header"),
+            ValidatorSet::without_proposer(vec![]),
+            ValidatorSet::without_proposer(vec![]),
+            node::Id::new([0; 20]),
+        )
+    }
+
+    pub(crate) fn light_client_opts() -> LightClientOpts {
+        let trusted_hash = light_block(1)
+            .signed_header
+            .header
+            .hash()
+            .as_bytes()
+            .try_into()
+            .expect("sha256 hash");
+        LightClientOpts::new(
+            "testing".to_string(),
+            1,
+            trusted_hash,
+            (1, 3),
+            1_209_600,
+            5,
+            20,
+        )
+        .expect("valid light client opts")
+    }
+
+    fn anchor(height: u64) -> (Height, Hash) {
+        let light_block = light_block(height);
+        (
+            light_block.height(),
+            light_block.signed_header.header.hash(),
+        )
+    }
+
+    #[tokio::test]
+    async fn trust_anchor_is_a_recently_verified_block_or_the_configured_one() {
+        let store = DefaultStore::default();
+        let opts = light_client_opts();
+        let trace = |from: u64| vec![light_block(from), light_block(from + 1)];
+
+        // nothing was verified yet, so only the configured trusted block can be used
+        assert_eq!(
+            trust_anchor(&store, &trace(1), &opts)
+                .await
+                .expect("anchor"),
+            anchor(1)
+        );
+        assert_eq!(
+            trust_anchor(&store, &trace(5), &opts)
+                .await
+                .expect("anchor"),
+            anchor(1)
+        );
+
+        store
+            .advance_latest_verified_block(light_block(5))
+            .await
+            .expect("infallible store");
+        store
+            .advance_latest_verified_block(light_block(8))
+            .await
+            .expect("infallible store");
+
+        // proofs from the latest and from previously verified blocks are accepted
+        assert_eq!(
+            trust_anchor(&store, &trace(8), &opts)
+                .await
+                .expect("anchor"),
+            anchor(8)
+        );
+        assert_eq!(
+            trust_anchor(&store, &trace(5), &opts)
+                .await
+                .expect("anchor"),
+            anchor(5)
+        );
+        assert_eq!(
+            trust_anchor(&store, &trace(6), &opts)
+                .await
+                .expect("anchor"),
+            anchor(1)
+        );
+        assert!(matches!(
+            trust_anchor(&store, &[], &opts).await,
+            Err(ProofOfPublicationError::EmptyLightClientProof)
+        ));
+    }
+
+    /// A light block with `app_hash` that the light client accepts as trusted, i.e. one that is
+    /// within the trusting period and whose validator sets match its header.
+    pub(crate) fn trusted_light_block(app_hash: AppHash) -> LightBlock {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .expect("time after epoch");
+        let time = Time::from_unix_timestamp(now.as_secs() as i64 - 60, 0).expect("valid time");
+        light_block_at(
+            1,
+            time,
+            ValidatorSet::without_proposer(vec![]).hash(),
+            app_hash,
+        )
+    }
+
+    /// Light client options whose trusted block is `light_block`.
+    pub(crate) fn trusting_light_client_opts(light_block: &LightBlock) -> LightClientOpts {
+        let trusted_hash = light_block
+            .signed_header
+            .header
+            .hash()
+            .as_bytes()
+            .try_into()
+            .expect("sha256 hash");
+        LightClientOpts::new(
+            "testing".to_string(),
+            light_block.height().value(),
+            trusted_hash,
+            (1, 3),
+            1_209_600,
+            5,
+            20,
+        )
+        .expect("valid light client opts")
+    }
+
+    /// Existence proofs for two items of a contract's state, whose store is an IAVL tree with
+    /// just these two leaves. The tree's root is the only (wasm) store in the multistore. Returns
+    /// the app hash along with the proofs.
+    pub(crate) fn cw_proofs(items: [(CwAbciKey, Vec<u8>); 2]) -> (AppHash, Vec<RawCwProof>) {
+        let leaf_op = |prefix| LeafOp {
+            hash: HashOp::Sha256.into(),
+            prehash_key: HashOp::NoHash.into(),
+            prehash_value: HashOp::Sha256.into(),
+            length: LengthOp::VarProto.into(),
+            prefix,
+        };
+        let root = |proof: &ExistenceProof| {
+            ics23::calculate_existence_root::<ics23::HostFunctionsManager>(proof)
+                .expect("valid proof")
+        };
+
+        // IAVL leaves (height 0, size 1, version 1) under a single inner node (height 1, size 2)
+        let [mut left, mut right] = items.map(|(key, value)| ExistenceProof {
+            key: key.into_vec(),
+            value,
+            leaf: Some(leaf_op(vec![0, 2, 2])),
+            path: vec![],
+        });
+        let (left_hash, right_hash) = (root(&left), root(&right));
+        left.path.push(InnerOp {
+            hash: HashOp::Sha256.into(),
+            prefix: vec![2, 4, 2, 32],
+            suffix: [vec![32], right_hash].concat(),
+        });
+        right.path.push(InnerOp {
+            hash: HashOp::Sha256.into(),
+            prefix: [vec![2, 4, 2, 32], left_hash, vec![32]].concat(),
+            suffix: vec![],
+        });
+
+        let multistore = ExistenceProof {
+            key: b"wasm".to_vec(),
+            value: root(&left),
+            leaf: Some(leaf_op(vec![0])),
+            path: vec![],
+        };
+        let app_hash = AppHash::try_from(root(&multistore)).expect("valid app hash");
+
+        let proof_op = |proof: ExistenceProof| ProofOp {
+            field_type: "ics23".to_string(),
+            key: vec![],
+            data: CommitmentProof {
+                proof: Some(commitment_proof::Proof::Exist(proof)),
+            }
+            .encode_to_vec(),
+        };
+        let proofs = [left, right]
+            .into_iter()
+            .map(|proof| {
+                serde_json::from_value(serde_json::json!({
+                    "key": hex::encode(&proof.key),
+                    "value": hex::encode(&proof.value),
+                    "proof": ProofOps {
+                        ops: vec![proof_op(proof), proof_op(multistore.clone())],
+                    },
+                }))
+                .expect("valid raw proof")
+            })
+            .collect();
+
+        (app_hash, proofs)
+    }
+
+    fn raw_cw_proof(key: Vec<u8>) -> RawCwProof {
+        serde_json::from_value(serde_json::json!({
+            "key": hex::encode(key),
+            "value": hex::encode(b"value"),
+            "proof": { "ops": [] },
+        }))
+        .expect("valid raw proof")
+    }
+
+    #[tokio::test]
+    async fn multi_proof_must_prove_every_requested_key() {
+        let store = DefaultStore::default();
+        let light_block = trusted_light_block(AppHash::default());
+        let opts = trusting_light_client_opts(&light_block);
+
+        let contract = AccountId::new("wasm", &[1; 20]).expect("valid contract address");
+        let other_contract = AccountId::new("wasm", &[2; 20]).expect("valid contract address");
+        let requests = StorageKey::new("requests", None);
+        let seq_num = StorageKey::new("seq_num", None);
+        let abci_key = |contract: &AccountId, storage_key: &StorageKey| {
+            CwAbciKey::from_storage_key(contract.clone(), storage_key.clone()).into_vec()
+        };
+        let multi_proof = |merkle_proof, additional_merkle_proofs| MultiProofOfPublication {
+            light_client_proof: vec![light_block.clone()],
+            merkle_proof,
+            additional_merkle_proofs,
+            msg: (),
+        };
+
+        // missing proof for one of the requested keys
+        let proof = multi_proof(raw_cw_proof(abci_key(&contract, &requests)), vec![]);
+        assert!(matches!(
+            proof
+                .verify(&store, &opts, contract.clone(), vec![requests.clone(), seq_num.clone()])
+                .await,
+            Err(ProofOfPublicationError::MissingMerkleProof(key)) if key == "seq_num"
+        ));
+
+        // proofs for the same storage key but another namespace or contract don't match
+        let proof = multi_proof(
+            raw_cw_proof(abci_key(&contract, &requests)),
+            vec![
+                raw_cw_proof(abci_key(
+                    &contract,
+                    &StorageKey::new("seq_num", Some("requests".to_string())),
+                )),
+                raw_cw_proof(abci_key(&other_contract, &seq_num)),
+            ],
+        );
+        assert!(matches!(
+            proof
+                .verify(&store, &opts, contract.clone(), vec![requests.clone(), seq_num.clone()])
+                .await,
+            Err(ProofOfPublicationError::MissingMerkleProof(key)) if key == "seq_num"
+        ));
+
+        // a proof for a matching key is still verified against the app hash
+        let proof = multi_proof(
+            raw_cw_proof(abci_key(&contract, &requests)),
+            vec![raw_cw_proof(abci_key(&contract, &seq_num))],
+        );
+        assert!(matches!(
+            proof
+                .verify(&store, &opts, contract.clone(), vec![requests, seq_num])
+                .await,
+            Err(ProofOfPublicationError::MerkleProofVerification(_))
+        ));
+
+        // nothing was verified
+        assert!(store
+            .get_latest_verified_block()
+            .await
+            .expect("infallible store")
+            .is_none());
+    }
+
+    #[test]
+    fn proofs_must_not_lag_behind_the_latest_verified_block() {
+        let time = |secs| Time::from_unix_timestamp(secs, 0).expect("valid time");
+
+        // within lag
+        assert!(ensure_fresh(time(95), time(100), 10).is_ok());
+        // exactly at lag
+        assert!(ensure_fresh(time(90), time(100), 10).is_ok());
+        // beyond lag
+        assert!(matches!(
+            ensure_fresh(time(89), time(100), 10),
+            Err(ProofOfPublicationError::StaleProof {
+                max_block_lag: 10,
+                ..
+            })
+        ));
+        // target newer than latest
+        assert!(ensure_fresh(time(110), time(100), 10).is_ok());
     }
 }
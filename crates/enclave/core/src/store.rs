@@ -1,6 +1,16 @@
+use std::collections::VecDeque;
+
 use quartz_contract_core::state::{Config, Nonce};
+use serde::{Deserialize, Serialize};
+use tendermint::{block::Height, Hash};
+use tendermint_light_client::types::LightBlock;
 
 pub mod default;
+pub mod sealed;
+
+/// Number of verified light blocks whose heights and hashes are retained, so that proofs that
+/// were generated from one of them are still accepted after newer light blocks were verified.
+pub const RECENT_VERIFIED_BLOCKS: usize = 128;
 
 #[async_trait::async_trait]
 pub trait Store: Send + Sync + 'static {
@@ -18,4 +28,90 @@ pub trait Store: Send + Sync + 'static {
     async fn set_nonce(&self, nonce: Nonce) -> Result<Option<Nonce>, Self::Error>;
     async fn get_seq_num(&self) -> Result<u64, Self::Error>;
     async fn inc_seq_num(&self, count: usize) -> Result<u64, Self::Error>;
+    async fn get_latest_verified_block(&self) -> Result<Option<LightBlock>, Self::Error>;
+    /// Returns the hash of the light block at `height` if it is one of the
+    /// `RECENT_VERIFIED_BLOCKS` most recently verified light blocks.
+    async fn get_verified_block_hash(&self, height: Height) -> Result<Option<Hash>, Self::Error>;
+    /// Replaces the latest verified light block only if `light_block` is at a greater height, so
+    /// that it never moves backwards. Returns the previous latest verified light block.
+    async fn advance_latest_verified_block(
+        &self,
+        light_block: LightBlock,
+    ) -> Result<Option<LightBlock>, Self::Error>;
+}
+
+/// The latest light block verified by the enclave, along with the heights and hashes of the
+/// `RECENT_VERIFIED_BLOCKS` most recently verified light blocks (including the latest).
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct VerifiedBlocks {
+    latest: Option<LightBlock>,
+    #[serde(default)]
+    recent: VecDeque<(Height, Hash)>,
+}
+
+impl VerifiedBlocks {
+    pub fn latest(&self) -> Option<&LightBlock> {
+        self.latest.as_ref()
+    }
+
+    pub fn hash_at(&self, height: Height) -> Option<Hash> {
+        self.recent
+            .iter()
+            .find(|(h, _)| *h == height)
+            .map(|(_, hash)| *hash)
+    }
+
+    /// Replaces the latest light block only if `light_block` is at a greater height. Returns the
+    /// previous latest light block.
+    pub fn advance(&mut self, light_block: LightBlock) -> Option<LightBlock> {
+        let prev_latest = self.latest.clone();
+        if prev_latest
+            .as_ref()
+            .map_or(true, |l| light_block.height() > l.height())
+        {
+            self.recent.push_back((
+                light_block.height(),
+                light_block.signed_header.header.hash(),
+            ));
+            if self.recent.len() > RECENT_VERIFIED_BLOCKS {
+                self.recent.pop_front();
+            }
+            self.latest = Some(light_block);
+        }
+        prev_latest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proof_of_publication::tests::light_block;
+
+    #[test]
+    fn verified_blocks_retain_recent_hashes() {
+        let mut verified_blocks = VerifiedBlocks::default();
+        assert!(verified_blocks.advance(light_block(2)).is_none());
+        assert_eq!(
+            verified_blocks.advance(light_block(3)).map(|l| l.height()),
+            Some(2u32.into())
+        );
+
+        // the latest verified block never moves backwards
+        verified_blocks.advance(light_block(1));
+        assert_eq!(
+            verified_blocks.latest().map(|l| l.height()),
+            Some(3u32.into())
+        );
+        assert_eq!(verified_blocks.hash_at(1u32.into()), None);
+        assert_eq!(
+            verified_blocks.hash_at(2u32.into()),
+            Some(light_block(2).signed_header.header.hash())
+        );
+
+        for height in 4..(4 + RECENT_VERIFIED_BLOCKS as u64) {
+            verified_blocks.advance(light_block(height));
+        }
+        assert_eq!(verified_blocks.hash_at(3u32.into()), None);
+        assert!(verified_blocks.hash_at(4u32.into()).is_some());
+    }
 }
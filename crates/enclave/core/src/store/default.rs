@@ -3,9 +3,11 @@ use std::sync::Arc;
 use cosmrs::AccountId;
 use displaydoc::Display;
 use quartz_contract_core::state::{Config, Nonce};
+use tendermint::{block::Height, Hash};
+use tendermint_light_client::types::LightBlock;
 use tokio::sync::RwLock;
 
-use crate::store::Store;
+use crate::store::{Store, VerifiedBlocks};
 
 #[derive(Clone, Debug, Default)]
 pub struct DefaultStore {
@@ -13,6 +15,7 @@ pub struct DefaultStore {
     contract: Arc<RwLock<Option<AccountId>>>,
     nonce: Arc<RwLock<Option<Nonce>>>,
     seq_num: Arc<RwLock<u64>>,
+    verified_blocks: Arc<RwLock<VerifiedBlocks>>,
 }
 
 impl DefaultStore {
@@ -22,6 +25,7 @@ impl DefaultStore {
             contract: Default::default(),
             nonce: Default::default(),
             seq_num: Default::default(),
+            verified_blocks: Default::default(),
         }
     }
 }
@@ -71,4 +75,19 @@ impl Store for DefaultStore {
         *seq_num += count as u64;
         Ok(prev_seq_num)
     }
+
+    async fn get_latest_verified_block(&self) -> Result<Option<LightBlock>, Self::Error> {
+        Ok(self.verified_blocks.read().await.latest().cloned())
+    }
+
+    async fn get_verified_block_hash(&self, height: Height) -> Result<Option<Hash>, Self::Error> {
+        Ok(self.verified_blocks.read().await.hash_at(height))
+    }
+
+    async fn advance_latest_verified_block(
+        &self,
+        light_block: LightBlock,
+    ) -> Result<Option<LightBlock>, Self::Error> {
+        Ok(self.verified_blocks.write().await.advance(light_block))
+    }
 }
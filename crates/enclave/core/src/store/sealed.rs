@@ -0,0 +1,574 @@
+use std::{
+    fs::{self, File},
+    io::{Error as IoError, ErrorKind, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    XChaCha20Poly1305, XNonce,
+};
+use cosmrs::AccountId;
+use displaydoc::Display;
+use k256::ecdsa::SigningKey;
+use quartz_contract_core::state::{Config, Nonce, RawConfig};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tendermint::{block::Height, Hash};
+use tendermint_light_client::types::LightBlock;
+use tokio::{sync::RwLock, task::spawn_blocking};
+
+use crate::{
+    key_manager::default::DefaultKeyManager,
+    store::{Store, VerifiedBlocks},
+};
+
+#[cfg(not(feature = "mock-sgx"))]
+pub type DefaultSealer = ProtectedFileSealer;
+
+#[cfg(feature = "mock-sgx")]
+pub type DefaultSealer = AeadSealer;
+
+/// Magic bytes at the start of every sealed store file.
+const MAGIC: &[u8; 4] = b"QZST";
+
+/// Current version of the sealed store file format.
+const VERSION: u16 = 1;
+
+const HEADER_LEN: usize = MAGIC.len() + 2;
+
+/// The trait defines how the serialized store state is protected before it is written to disk.
+///
+/// The file header (magic and version) is passed as associated data so that implementations which
+/// authenticate their output also bind it to the format version.
+pub trait Sealer: Send + Sync + 'static {
+    fn seal(&self, header: &[u8], plaintext: Vec<u8>) -> Result<Vec<u8>, SealedStoreError>;
+
+    fn unseal(&self, header: &[u8], sealed: Vec<u8>) -> Result<Vec<u8>, SealedStoreError>;
+}
+
+/// A `Sealer` for Gramine protected (i.e. encrypted) files.
+///
+/// Gramine transparently encrypts and integrity-protects everything written under an
+/// `encrypted` mount, so this sealer passes data through unchanged. The store path must be
+/// located on such a mount, e.g. -
+/// `{ type = "encrypted", path = "/sealed", uri = "file:sealed", key_name = "_sgx_mrenclave" }`
+#[derive(Clone, Debug, Default)]
+pub struct ProtectedFileSealer;
+
+impl Sealer for ProtectedFileSealer {
+    fn seal(&self, _header: &[u8], plaintext: Vec<u8>) -> Result<Vec<u8>, SealedStoreError> {
+        Ok(plaintext)
+    }
+
+    fn unseal(&self, _header: &[u8], sealed: Vec<u8>) -> Result<Vec<u8>, SealedStoreError> {
+        Ok(sealed)
+    }
+}
+
+/// A `Sealer` that encrypts data with XChaCha20-Poly1305 under a key derived from a secret.
+///
+/// Meant for mock-sgx mode where there is no Gramine runtime to protect files for us.
+#[derive(Clone)]
+pub struct AeadSealer {
+    cipher: XChaCha20Poly1305,
+}
+
+impl AeadSealer {
+    const KDF_DOMAIN: &'static [u8] = b"quartz-sealed-store-v1";
+
+    pub fn from_secret(secret: &[u8]) -> Self {
+        let key = Sha256::new()
+            .chain_update(Self::KDF_DOMAIN)
+            .chain_update(secret)
+            .finalize();
+        Self {
+            cipher: XChaCha20Poly1305::new(&key),
+        }
+    }
+}
+
+impl Sealer for AeadSealer {
+    fn seal(&self, header: &[u8], plaintext: Vec<u8>) -> Result<Vec<u8>, SealedStoreError> {
+        let mut nonce = XNonce::default();
+        rand::thread_rng().fill_bytes(&mut nonce);
+
+        let ciphertext = self
+            .cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: &plaintext,
+                    aad: header,
+                },
+            )
+            .map_err(|_| SealedStoreError::Seal)?;
+
+        Ok([nonce.as_slice(), &ciphertext].concat())
+    }
+
+    fn unseal(&self, header: &[u8], sealed: Vec<u8>) -> Result<Vec<u8>, SealedStoreError> {
+        let nonce_len = XNonce::default().len();
+        if sealed.len() < nonce_len {
+            return Err(SealedStoreError::Unseal);
+        }
+
+        let (nonce, ciphertext) = sealed.split_at(nonce_len);
+        self.cipher
+            .decrypt(
+                XNonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: header,
+                },
+            )
+            .map_err(|_| SealedStoreError::Unseal)
+    }
+}
+
+#[derive(Debug, Display)]
+pub enum SealedStoreError {
+    /// I/O error: {0}
+    Io(IoError),
+    /// failed to (de)serialize store state: {0}
+    Serde(serde_json::Error),
+    /// invalid sealed store file header
+    InvalidHeader,
+    /// unsupported sealed store file version (expected {expected}, found {found})
+    UnsupportedVersion { expected: u16, found: u16 },
+    /// failed to seal store state
+    Seal,
+    /// failed to unseal store state (wrong key or tampered file)
+    Unseal,
+    /// invalid store state: {0}
+    InvalidState(String),
+}
+
+impl From<IoError> for SealedStoreError {
+    fn from(e: IoError) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for SealedStoreError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Serde(e)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+struct State {
+    config: Option<Config>,
+    contract: Option<AccountId>,
+    nonce: Option<Nonce>,
+    seq_num: u64,
+    verified_blocks: VerifiedBlocks,
+    key_manager: Option<DefaultKeyManager>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct RawState {
+    config: Option<RawConfig>,
+    contract: Option<String>,
+    nonce: Option<String>,
+    seq_num: u64,
+    #[serde(default)]
+    verified_blocks: VerifiedBlocks,
+    #[serde(default)]
+    key_manager: Option<RawKeyManager>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RawKeyManager {
+    sk: String,
+    prev_sk: Option<String>,
+    epoch: u64,
+}
+
+impl TryFrom<RawKeyManager> for DefaultKeyManager {
+    type Error = SealedStoreError;
+
+    fn try_from(value: RawKeyManager) -> Result<Self, Self::Error> {
+        let signing_key = |sk: String| {
+            let sk = hex::decode(sk).map_err(|e| SealedStoreError::InvalidState(e.to_string()))?;
+            SigningKey::from_slice(&sk).map_err(|e| SealedStoreError::InvalidState(e.to_string()))
+        };
+
+        Ok(Self {
+            sk: signing_key(value.sk)?,
+            prev_sk: value.prev_sk.map(signing_key).transpose()?,
+            epoch: value.epoch,
+        })
+    }
+}
+
+impl From<DefaultKeyManager> for RawKeyManager {
+    fn from(value: DefaultKeyManager) -> Self {
+        Self {
+            sk: hex::encode(value.sk.to_bytes()),
+            prev_sk: value.prev_sk.map(|sk| hex::encode(sk.to_bytes())),
+            epoch: value.epoch,
+        }
+    }
+}
+
+impl TryFrom<RawState> for State {
+    type Error = SealedStoreError;
+
+    fn try_from(value: RawState) -> Result<Self, Self::Error> {
+        let config = value
+            .config
+            .map(Config::try_from)
+            .transpose()
+            .map_err(|e| SealedStoreError::InvalidState(e.to_string()))?;
+        let contract = value
+            .contract
+            .map(|c| c.parse())
+            .transpose()
+            .map_err(|e: cosmrs::ErrorReport| SealedStoreError::InvalidState(e.to_string()))?;
+        let nonce = value
+            .nonce
+            .map(|n| {
+                hex::decode(n)
+                    .map_err(|e| e.to_string())?
+                    .try_into()
+                    .map_err(|_| "invalid nonce length".to_string())
+            })
+            .transpose()
+            .map_err(SealedStoreError::InvalidState)?;
+
+        let key_manager = value.key_manager.map(TryInto::try_into).transpose()?;
+
+        Ok(Self {
+            config,
+            contract,
+            nonce,
+            seq_num: value.seq_num,
+            verified_blocks: value.verified_blocks,
+            key_manager,
+        })
+    }
+}
+
+impl From<State> for RawState {
+    fn from(value: State) -> Self {
+        Self {
+            config: value.config.map(Into::into),
+            contract: value.contract.map(|c| c.to_string()),
+            nonce: value.nonce.map(hex::encode),
+            seq_num: value.seq_num,
+            verified_blocks: value.verified_blocks,
+            key_manager: value.key_manager.map(Into::into),
+        }
+    }
+}
+
+/// A `Store` that persists its state to a sealed file so that it survives enclave restarts.
+///
+/// Every mutation rewrites the whole file atomically (write to a temporary file, sync, rename,
+/// then sync the directory) while holding the state lock, so the file always reflects a
+/// consistent snapshot. The file is written on tokio's blocking thread pool so that it doesn't
+/// stall the async runtime.
+///
+/// The store also holds the enclave's session keys (see `SealedKeyManager`), so that a restored
+/// session can still be used with the key that was published on-chain.
+pub struct SealedStore<S = DefaultSealer> {
+    state: Arc<RwLock<State>>,
+    path: Arc<PathBuf>,
+    sealer: Arc<S>,
+}
+
+impl<S> Clone for SealedStore<S> {
+    fn clone(&self) -> Self {
+        Self {
+            state: self.state.clone(),
+            path: self.path.clone(),
+            sealer: self.sealer.clone(),
+        }
+    }
+}
+
+impl<S: Sealer> SealedStore<S> {
+    /// Opens the sealed store at `path`, restoring any previously persisted state.
+    ///
+    /// The given `config` is only used (and persisted) if the file doesn't already hold one.
+    pub fn open(
+        path: impl Into<PathBuf>,
+        sealer: S,
+        config: Config,
+    ) -> Result<Self, SealedStoreError> {
+        let path = path.into();
+        let mut state = match read_sealed(&path, &sealer) {
+            Ok(state) => state,
+            Err(SealedStoreError::Io(e)) if e.kind() == ErrorKind::NotFound => State::default(),
+            Err(e) => return Err(e),
+        };
+
+        if state.config.is_none() {
+            state.config = Some(config);
+            write_sealed(&path, &sealer, &state)?;
+        }
+
+        Ok(Self {
+            state: Arc::new(RwLock::new(state)),
+            path: Arc::new(path),
+            sealer: Arc::new(sealer),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub async fn get_key_manager(&self) -> Option<DefaultKeyManager> {
+        self.state.read().await.key_manager.clone()
+    }
+
+    pub async fn set_key_manager(
+        &self,
+        key_manager: DefaultKeyManager,
+    ) -> Result<Option<DefaultKeyManager>, SealedStoreError> {
+        self.update(|state| state.key_manager.replace(key_manager))
+            .await
+    }
+
+    async fn update<T>(&self, f: impl FnOnce(&mut State) -> T) -> Result<T, SealedStoreError> {
+        let mut state = self.state.write().await;
+        let mut next = state.clone();
+        let ret = f(&mut next);
+
+        let path = self.path.clone();
+        let sealer = self.sealer.clone();
+        *state = spawn_blocking(move || write_sealed(&path, sealer.as_ref(), &next).map(|_| next))
+            .await
+            .map_err(|e| SealedStoreError::Io(IoError::other(e)))??;
+        Ok(ret)
+    }
+}
+
+fn header() -> [u8; HEADER_LEN] {
+    let mut header = [0u8; HEADER_LEN];
+    header[..MAGIC.len()].copy_from_slice(MAGIC);
+    header[MAGIC.len()..].copy_from_slice(&VERSION.to_be_bytes());
+    header
+}
+
+fn read_sealed(path: &Path, sealer: &impl Sealer) -> Result<State, SealedStoreError> {
+    let mut contents = fs::read(path)?;
+    if contents.len() < HEADER_LEN || &contents[..MAGIC.len()] != MAGIC {
+        return Err(SealedStoreError::InvalidHeader);
+    }
+
+    let found = u16::from_be_bytes([contents[MAGIC.len()], contents[MAGIC.len() + 1]]);
+    if found != VERSION {
+        return Err(SealedStoreError::UnsupportedVersion {
+            expected: VERSION,
+            found,
+        });
+    }
+
+    let sealed = contents.split_off(HEADER_LEN);
+    let plaintext = sealer.unseal(&contents, sealed)?;
+    let raw_state: RawState = serde_json::from_slice(&plaintext)?;
+    raw_state.try_into()
+}
+
+fn write_sealed(path: &Path, sealer: &impl Sealer, state: &State) -> Result<(), SealedStoreError> {
+    let header = header();
+    let plaintext = serde_json::to_vec(&RawState::from(state.clone()))?;
+    let sealed = sealer.seal(&header, plaintext)?;
+
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_path);
+
+    let mut file = File::create(&tmp_path)?;
+    file.write_all(&header)?;
+    file.write_all(&sealed)?;
+    file.sync_all()?;
+    fs::rename(&tmp_path, path)?;
+
+    // the rename is only durable once the directory entry is
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    File::open(dir)?.sync_all()?;
+
+    Ok(())
+}
+
+#[async_trait::async_trait]
+impl<S: Sealer> Store for SealedStore<S> {
+    type Contract = AccountId;
+    type Error = SealedStoreError;
+
+    async fn get_config(&self) -> Result<Option<Config>, Self::Error> {
+        Ok(self.state.read().await.config.clone())
+    }
+
+    async fn set_config(&self, config: Config) -> Result<Option<Config>, Self::Error> {
+        self.update(|state| state.config.replace(config)).await
+    }
+
+    async fn get_contract(&self) -> Result<Option<Self::Contract>, Self::Error> {
+        Ok(self.state.read().await.contract.clone())
+    }
+
+    async fn set_contract(
+        &self,
+        contract: Self::Contract,
+    ) -> Result<Option<Self::Contract>, Self::Error> {
+        self.update(|state| state.contract.replace(contract)).await
+    }
+
+    async fn get_nonce(&self) -> Result<Option<Nonce>, Self::Error> {
+        Ok(self.state.read().await.nonce)
+    }
+
+    async fn set_nonce(&self, nonce: Nonce) -> Result<Option<Nonce>, Self::Error> {
+        self.update(|state| state.nonce.replace(nonce)).await
+    }
+
+    async fn get_seq_num(&self) -> Result<u64, Self::Error> {
+        Ok(self.state.read().await.seq_num)
+    }
+
+    async fn inc_seq_num(&self, count: usize) -> Result<u64, Self::Error> {
+        self.update(|state| {
+            let prev_seq_num = state.seq_num;
+            state.seq_num += count as u64;
+            prev_seq_num
+        })
+        .await
+    }
+
+    async fn get_latest_verified_block(&self) -> Result<Option<LightBlock>, Self::Error> {
+        Ok(self.state.read().await.verified_blocks.latest().cloned())
+    }
+
+    async fn get_verified_block_hash(&self, height: Height) -> Result<Option<Hash>, Self::Error> {
+        Ok(self.state.read().await.verified_blocks.hash_at(height))
+    }
+
+    async fn advance_latest_verified_block(
+        &self,
+        light_block: LightBlock,
+    ) -> Result<Option<LightBlock>, Self::Error> {
+        self.update(|state| state.verified_blocks.advance(light_block))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quartz_contract_core::state::LightClientOpts;
+
+    use super::*;
+
+    const NONCE: Nonce = [7; 32];
+
+    fn config() -> Config {
+        let light_client_opts =
+            LightClientOpts::new("testing".to_string(), 1, [0; 32], (1, 3), 1_209_600, 5, 20)
+                .expect("valid light client opts");
+        Config::new([1; 32], light_client_opts, None, None)
+    }
+
+    fn sealer() -> AeadSealer {
+        AeadSealer::from_secret(b"secret")
+    }
+
+    #[tokio::test]
+    async fn state_roundtrips_and_is_restored_after_reopening() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let path = dir.path().join("store.sealed");
+
+        let store = SealedStore::open(&path, sealer(), config()).expect("open");
+        assert_eq!(store.get_config().await.expect("config"), Some(config()));
+        store.set_nonce(NONCE).await.expect("set nonce");
+        assert_eq!(store.inc_seq_num(3).await.expect("inc seq num"), 0);
+        assert_eq!(store.get_nonce().await.expect("nonce"), Some(NONCE));
+        assert_eq!(store.get_seq_num().await.expect("seq num"), 3);
+        drop(store);
+
+        let store = SealedStore::open(&path, sealer(), config()).expect("reopen");
+        assert_eq!(store.get_config().await.expect("config"), Some(config()));
+        assert_eq!(store.get_nonce().await.expect("nonce"), Some(NONCE));
+        assert_eq!(store.get_seq_num().await.expect("seq num"), 3);
+    }
+
+    #[tokio::test]
+    async fn file_is_sealed_against_tampering_and_other_keys() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let path = dir.path().join("store.sealed");
+        let store = SealedStore::open(&path, sealer(), config()).expect("open");
+        store.set_nonce(NONCE).await.expect("set nonce");
+
+        let contents = fs::read(&path).expect("read");
+        assert!(!contents
+            .windows(NONCE.len() * 2)
+            .any(|w| w == hex::encode(NONCE).as_bytes()));
+
+        assert!(matches!(
+            SealedStore::open(&path, AeadSealer::from_secret(b"other secret"), config()),
+            Err(SealedStoreError::Unseal)
+        ));
+
+        let mut tampered = contents.clone();
+        let last = tampered.len() - 1;
+        tampered[last] ^= 1;
+        fs::write(&path, tampered).expect("write");
+        assert!(matches!(
+            SealedStore::open(&path, sealer(), config()),
+            Err(SealedStoreError::Unseal)
+        ));
+    }
+
+    #[tokio::test]
+    async fn file_with_other_version_or_magic_is_rejected() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let path = dir.path().join("store.sealed");
+        SealedStore::open(&path, sealer(), config()).expect("open");
+        let contents = fs::read(&path).expect("read");
+
+        let mut other_version = contents.clone();
+        other_version[MAGIC.len()..HEADER_LEN].copy_from_slice(&(VERSION + 1).to_be_bytes());
+        fs::write(&path, other_version).expect("write");
+        assert!(matches!(
+            SealedStore::open(&path, sealer(), config()),
+            Err(SealedStoreError::UnsupportedVersion { expected: VERSION, found }) if found == VERSION + 1
+        ));
+
+        let mut other_magic = contents;
+        other_magic[0] ^= 1;
+        fs::write(&path, other_magic).expect("write");
+        assert!(matches!(
+            SealedStore::open(&path, sealer(), config()),
+            Err(SealedStoreError::InvalidHeader)
+        ));
+    }
+
+    #[tokio::test]
+    async fn leftover_temporary_file_is_ignored_and_replaced() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let path = dir.path().join("store.sealed");
+        let tmp_path = dir.path().join("store.sealed.tmp");
+
+        let store = SealedStore::open(&path, sealer(), config()).expect("open");
+        store.inc_seq_num(1).await.expect("inc seq num");
+        drop(store);
+
+        // a write that crashed before the rename leaves a partial temporary file behind
+        fs::write(&tmp_path, b"QZST partial").expect("write");
+        let store = SealedStore::open(&path, sealer(), config()).expect("reopen");
+        assert_eq!(store.get_seq_num().await.expect("seq num"), 1);
+
+        store.inc_seq_num(1).await.expect("inc seq num");
+        assert!(!tmp_path.exists());
+        let store = SealedStore::open(&path, sealer(), config()).expect("reopen");
+        assert_eq!(store.get_seq_num().await.expect("seq num"), 2);
+    }
+}
@@ -9,6 +9,9 @@ use quartz_contract_core::msg::{
     execute::{
         attested::{Attested, RawAttested},
         session_create::{RawSessionCreate, SessionCreate},
+        session_handover_complete::{RawSessionHandoverComplete, SessionHandoverComplete},
+        session_handover_request::{RawSessionHandoverRequest, SessionHandoverRequest},
+        session_rotate_pub_key::{RawSessionRotatePubKey, SessionRotatePubKey},
         session_set_pub_key::{RawSessionSetPubKey, SessionSetPubKey},
     },
     instantiate::{CoreInstantiate, RawCoreInstantiate},
@@ -17,6 +20,9 @@ use quartz_contract_core::msg::{
 use quartz_proto::quartz::{
     InstantiateResponse as RawInstantiateResponse,
     SessionCreateResponse as RawSessionCreateResponse,
+    SessionHandoverCompleteResponse as RawSessionHandoverCompleteResponse,
+    SessionHandoverRequestResponse as RawSessionHandoverRequestResponse,
+    SessionRotatePubKeyResponse as RawSessionRotatePubKeyResponse,
     SessionSetPubKeyResponse as RawSessionSetPubKeyResponse,
 };
 use serde::{Deserialize, Serialize};
@@ -113,6 +119,112 @@ where
     }
 }
 
+#[derive(Clone, Debug, PartialEq)]
+pub struct SessionRotatePubKeyResponse<A, RA> {
+    message: Attested<SessionRotatePubKey, A>,
+    _phantom: PhantomData<RA>,
+}
+
+impl<A, RA> SessionRotatePubKeyResponse<A, RA> {
+    pub fn new(message: Attested<SessionRotatePubKey, A>) -> Self {
+        Self {
+            message,
+            _phantom: Default::default(),
+        }
+    }
+
+    pub fn into_message(self) -> Attested<SessionRotatePubKey, A> {
+        self.message
+    }
+}
+
+impl<A, RA> From<SessionRotatePubKeyResponse<A, RA>> for RawSessionRotatePubKeyResponse
+where
+    RA: HasDomainType<DomainType = A> + Serialize,
+{
+    fn from(value: SessionRotatePubKeyResponse<A, RA>) -> Self {
+        let raw_message: RawAttested<RawSessionRotatePubKey, RA> = value.message.into();
+        Self {
+            message: serde_json::to_string(&raw_message).expect("infallible serializer"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SessionHandoverRequestResponse<A, RA> {
+    message: Attested<SessionHandoverRequest, A>,
+    _phantom: PhantomData<RA>,
+}
+
+impl<A, RA> SessionHandoverRequestResponse<A, RA> {
+    pub fn new(message: Attested<SessionHandoverRequest, A>) -> Self {
+        Self {
+            message,
+            _phantom: Default::default(),
+        }
+    }
+
+    pub fn into_message(self) -> Attested<SessionHandoverRequest, A> {
+        self.message
+    }
+}
+
+impl<A, RA> From<SessionHandoverRequestResponse<A, RA>> for RawSessionHandoverRequestResponse
+where
+    RA: HasDomainType<DomainType = A> + Serialize,
+{
+    fn from(value: SessionHandoverRequestResponse<A, RA>) -> Self {
+        let raw_message: RawAttested<RawSessionHandoverRequest, RA> = value.message.into();
+        Self {
+            message: serde_json::to_string(&raw_message).expect("infallible serializer"),
+        }
+    }
+}
+
+/// The attested `SessionHandoverComplete` msg along with the session state, encrypted to the pub
+/// key of the enclave taking over the session.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SessionHandoverCompleteResponse<A, RA> {
+    message: Attested<SessionHandoverComplete, A>,
+    state: Vec<u8>,
+    _phantom: PhantomData<RA>,
+}
+
+impl<A, RA> SessionHandoverCompleteResponse<A, RA> {
+    pub fn new(message: Attested<SessionHandoverComplete, A>, state: Vec<u8>) -> Self {
+        Self {
+            message,
+            state,
+            _phantom: Default::default(),
+        }
+    }
+
+    pub fn into_message(self) -> Attested<SessionHandoverComplete, A> {
+        self.message
+    }
+}
+
+impl<A, RA> From<SessionHandoverCompleteResponse<A, RA>> for RawSessionHandoverCompleteResponse
+where
+    RA: HasDomainType<DomainType = A> + Serialize,
+{
+    fn from(value: SessionHandoverCompleteResponse<A, RA>) -> Self {
+        let raw_message: RawAttested<RawSessionHandoverComplete, RA> = value.message.into();
+        Self {
+            message: serde_json::to_string(&raw_message).expect("infallible serializer"),
+            state: value.state,
+        }
+    }
+}
+
+/// The session state handed over to the enclave taking over the session.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SessionHandoverState {
+    /// The hex-encoded session key of the enclave that held the session.
+    pub sk: String,
+    pub seq_num: u64,
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Fmspc(pub [u8; 6]);
 
@@ -1,3 +1,5 @@
+use alloc::string::String;
+
 use displaydoc::Display;
 
 #[derive(Clone, Debug, Display)]
@@ -13,3 +15,13 @@ pub enum ProofError {
     /// proof verification failed
     VerificationFailure,
 }
+
+#[derive(Clone, Debug, Display, PartialEq, Eq)]
+pub enum StorageKeyError {
+    /// empty storage key or namespace
+    Empty,
+    /// ambiguous storage key `{0}` (expected `key` or `namespace:key`, neither containing `:`)
+    Ambiguous(String),
+}
+
+impl core::error::Error for StorageKeyError {}
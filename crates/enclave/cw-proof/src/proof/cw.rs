@@ -15,7 +15,7 @@ use crate::{
         prefix::PrefixWasm,
         Proof,
     },
-    verifier::cw::CwVerifier,
+    verifier::cw::{CwNonExistenceVerifier, CwVerifier},
 };
 
 #[derive(Clone, Debug)]
@@ -26,18 +26,32 @@ pub struct CwProof<K = Vec<u8>, V = Vec<u8>> {
     pub value: V,
 }
 
-/// ABCI query response doesn't contain proof
 #[derive(Clone, Debug, Display)]
-pub struct ErrorWithoutProof;
+pub enum AbciQueryProofError {
+    /// ABCI query response doesn't contain proof
+    MissingProof,
+    /// ABCI query response doesn't contain a value (i.e. the key doesn't exist)
+    MissingValue,
+    /// ABCI query response contains a value (i.e. the key exists)
+    UnexpectedValue,
+}
 
 impl TryFrom<AbciQuery> for CwProof {
-    type Error = ErrorWithoutProof;
+    type Error = AbciQueryProofError;
 
     fn try_from(query: AbciQuery) -> Result<Self, Self::Error> {
         RawCwProof::try_from(query).map(Into::into)
     }
 }
 
+fn into_array_of_size_2<T: Debug>(v: Vec<T>) -> Result<[T; 2], ProofError> {
+    let boxed_slice = v.into_boxed_slice();
+    let boxed_array: Box<[T; 2]> = boxed_slice
+        .try_into()
+        .map_err(|_| ProofError::InvalidMerkleProof)?;
+    Ok(*boxed_array)
+}
+
 impl<K, V> Proof for CwProof<K, V>
 where
     K: Clone + Into<Vec<u8>>,
@@ -48,12 +62,6 @@ where
     type ProofOps = ProofOps;
 
     fn verify(&self, root: Vec<u8>) -> Result<(), ProofError> {
-        fn into_array_of_size_2<T: Debug>(v: Vec<T>) -> Result<[T; 2], ProofError> {
-            let boxed_slice = v.into_boxed_slice();
-            let boxed_array: Box<[T; 2]> = boxed_slice.try_into().expect("todo");
-            Ok(*boxed_array)
-        }
-
         let Self { proof, key, value } = self;
         let proofs = convert_tm_to_ics_merkle_proof(proof)?;
 
@@ -106,16 +114,133 @@ impl From<CwProof> for RawCwProof {
 }
 
 impl TryFrom<AbciQuery> for RawCwProof {
-    type Error = ErrorWithoutProof;
+    type Error = AbciQueryProofError;
 
     fn try_from(query: AbciQuery) -> Result<Self, Self::Error> {
         let AbciQuery {
             key, value, proof, ..
         } = query;
         let Some(proof) = proof else {
-            return Err(ErrorWithoutProof);
+            return Err(AbciQueryProofError::MissingProof);
         };
+        if value.is_empty() {
+            return Err(AbciQueryProofError::MissingValue);
+        }
 
         Ok(Self { proof, key, value })
     }
 }
+
+/// A proof that a key is absent from a contract's state.
+#[derive(Clone, Debug)]
+pub struct CwNonExistenceProof<K = Vec<u8>> {
+    proof: ProofOps,
+    pub key: PrefixedKey<PrefixWasm, K>,
+}
+
+impl TryFrom<AbciQuery> for CwNonExistenceProof {
+    type Error = AbciQueryProofError;
+
+    fn try_from(query: AbciQuery) -> Result<Self, Self::Error> {
+        RawCwNonExistenceProof::try_from(query).map(Into::into)
+    }
+}
+
+impl<K> Proof for CwNonExistenceProof<K>
+where
+    K: Clone + Into<Vec<u8>>,
+{
+    type Key = K;
+    type Value = ();
+    type ProofOps = ProofOps;
+
+    fn verify(&self, root: Vec<u8>) -> Result<(), ProofError> {
+        let Self { proof, key } = self;
+        let proofs = convert_tm_to_ics_merkle_proof(proof)?;
+
+        let verifier = CwNonExistenceVerifier::default();
+        verifier.verify(
+            &into_array_of_size_2(proofs)?,
+            &root,
+            &into_array_of_size_2(key.clone().into_keys())?,
+        )
+    }
+}
+
+#[serde_as]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RawCwNonExistenceProof {
+    #[serde_as(as = "Hex")]
+    key: Vec<u8>,
+    proof: ProofOps,
+}
+
+impl RawCwNonExistenceProof {
+    pub fn key(&self) -> &[u8] {
+        self.key.as_ref()
+    }
+}
+
+impl From<RawCwNonExistenceProof> for CwNonExistenceProof {
+    fn from(RawCwNonExistenceProof { key, proof }: RawCwNonExistenceProof) -> Self {
+        Self {
+            proof,
+            key: PrefixedKey::new(key),
+        }
+    }
+}
+
+impl From<CwNonExistenceProof> for RawCwNonExistenceProof {
+    fn from(CwNonExistenceProof { proof, key }: CwNonExistenceProof) -> Self {
+        Self {
+            key: key.into_keys().pop().expect("empty key"),
+            proof,
+        }
+    }
+}
+
+impl TryFrom<AbciQuery> for RawCwNonExistenceProof {
+    type Error = AbciQueryProofError;
+
+    fn try_from(query: AbciQuery) -> Result<Self, Self::Error> {
+        let AbciQuery {
+            key, value, proof, ..
+        } = query;
+        let Some(proof) = proof else {
+            return Err(AbciQueryProofError::MissingProof);
+        };
+        if !value.is_empty() {
+            return Err(AbciQueryProofError::UnexpectedValue);
+        }
+
+        Ok(Self { proof, key })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use tendermint::merkle::proof::ProofOp;
+
+    use super::*;
+
+    #[test]
+    fn proof_without_two_ops_is_rejected() {
+        let op = ProofOp {
+            field_type: "ics23:iavl".into(),
+            key: b"key".to_vec(),
+            data: vec![],
+        };
+        for ops in [vec![], vec![op.clone()], vec![op.clone(), op.clone(), op]] {
+            let raw = RawCwNonExistenceProof {
+                key: b"key".to_vec(),
+                proof: ProofOps { ops },
+            };
+            assert!(matches!(
+                CwNonExistenceProof::from(raw).verify(vec![1; 32]),
+                Err(ProofError::InvalidMerkleProof)
+            ));
+        }
+    }
+}
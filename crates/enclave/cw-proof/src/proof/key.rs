@@ -3,11 +3,12 @@ use alloc::{
     vec,
     vec::Vec,
 };
-use core::marker::PhantomData;
+use core::{fmt, marker::PhantomData, str::FromStr};
 
 use cosmrs::AccountId;
+use serde::{Deserialize, Serialize};
 
-use crate::proof::prefix::ConstPrefix;
+use crate::{error::StorageKeyError, proof::prefix::ConstPrefix};
 
 const CONTRACT_STORE_PREFIX: u8 = 0x03;
 
@@ -49,6 +50,55 @@ where
     }
 }
 
+/// A contract storage item, i.e. an `Item`'s key or a `Map`'s namespace and key.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct StorageKey {
+    pub key: String,
+    pub namespace: Option<String>,
+}
+
+impl StorageKey {
+    pub fn new(key: impl Into<String>, namespace: Option<String>) -> Self {
+        Self {
+            key: key.into(),
+            namespace,
+        }
+    }
+}
+
+/// Parses either `key` (for an `Item`) or `namespace:key` (for a `Map` entry).
+///
+/// Neither the namespace nor the key may contain a `:`, as it would be ambiguous where one ends
+/// and the other starts. Such storage keys must be constructed with [`StorageKey::new`] instead.
+impl FromStr for StorageKey {
+    type Err = StorageKeyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let storage_key = match s.split_once(':') {
+            Some((_, key)) if key.contains(':') => {
+                return Err(StorageKeyError::Ambiguous(s.to_string()))
+            }
+            Some((namespace, key)) => Self::new(key, Some(namespace.to_string())),
+            None => Self::new(s, None),
+        };
+
+        if storage_key.key.is_empty() || storage_key.namespace.as_deref() == Some("") {
+            return Err(StorageKeyError::Empty);
+        }
+
+        Ok(storage_key)
+    }
+}
+
+impl fmt::Display for StorageKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.namespace {
+            Some(namespace) => write!(f, "{}:{}", namespace, self.key),
+            None => write!(f, "{}", self.key),
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub enum CwAbciKey {
     Item {
@@ -82,6 +132,10 @@ impl CwAbciKey {
         }
     }
 
+    pub fn from_storage_key(contract_address: AccountId, storage_key: StorageKey) -> Self {
+        Self::new(contract_address, storage_key.key, storage_key.namespace)
+    }
+
     pub fn into_vec(self) -> Vec<u8> {
         self.into()
     }
@@ -127,3 +181,35 @@ impl From<CwAbciKey> for Vec<u8> {
         data
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn storage_key_is_parsed_from_key_or_namespace_and_key() {
+        assert_eq!("config".parse(), Ok(StorageKey::new("config", None)));
+        assert_eq!(
+            "requests:alice".parse(),
+            Ok(StorageKey::new("alice", Some("requests".to_string())))
+        );
+        assert_eq!(
+            StorageKey::new("alice", Some("requests".to_string())).to_string(),
+            "requests:alice"
+        );
+
+        // a `:` in either part can't be told apart from the separator
+        assert_eq!(
+            "balances:alice:uatom".parse::<StorageKey>(),
+            Err(StorageKeyError::Ambiguous(
+                "balances:alice:uatom".to_string()
+            ))
+        );
+        assert_eq!("".parse::<StorageKey>(), Err(StorageKeyError::Empty));
+        assert_eq!(":alice".parse::<StorageKey>(), Err(StorageKeyError::Empty));
+        assert_eq!(
+            "requests:".parse::<StorageKey>(),
+            Err(StorageKeyError::Empty)
+        );
+    }
+}
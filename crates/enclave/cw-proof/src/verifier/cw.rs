@@ -4,7 +4,11 @@ use ics23::CommitmentProof;
 
 use crate::{
     error::ProofError,
-    verifier::{ics23::Ics23MembershipVerifier, multi::MultiVerifier, Verifier},
+    verifier::{
+        ics23::{Ics23MembershipVerifier, Ics23NonMembershipVerifier},
+        multi::MultiVerifier,
+        Verifier,
+    },
 };
 
 type Key = Vec<u8>;
@@ -41,3 +45,108 @@ impl Default for CwVerifier<'_> {
         Self(mv)
     }
 }
+
+/// Verifies that a key is absent from a contract's state, i.e. a non-existence proof for the key in
+/// the wasm store and an existence proof for the wasm store's root in the app hash.
+#[derive(Clone, Debug)]
+pub struct CwNonExistenceVerifier<'a> {
+    store_verifier: Ics23NonMembershipVerifier<Key>,
+    root_verifier: Ics23MembershipVerifier<Key, Value<'a>>,
+}
+
+impl CwNonExistenceVerifier<'_> {
+    pub fn verify(
+        &self,
+        proofs: &[CommitmentProof; 2],
+        #[allow(clippy::ptr_arg)] root: &Vec<u8>,
+        keys: &[Vec<u8>; 2],
+    ) -> Result<(), ProofError> {
+        if root.is_empty() {
+            return Err(ProofError::EmptyMerkleRoot);
+        }
+
+        // Like `MultiVerifier`, proofs are ordered from the innermost to the outermost store
+        // whereas keys are ordered the other way around.
+        let store_root = self.store_verifier.verify(&proofs[0], &keys[1], &())?;
+        self.root_verifier
+            .verify_against_root(&proofs[1], &keys[0], &Cow::Owned(store_root), root)?
+            .then_some(())
+            .ok_or(ProofError::VerificationFailure)
+    }
+}
+
+impl Default for CwNonExistenceVerifier<'_> {
+    fn default() -> Self {
+        Self {
+            store_verifier: Ics23NonMembershipVerifier::new(ics23::iavl_spec()),
+            root_verifier: Ics23MembershipVerifier::new(ics23::tendermint_spec()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+    use crate::verifier::ics23::tests::{iavl_root, multistore_proof, non_existence_proof};
+
+    fn proofs(key: &[u8]) -> [CommitmentProof; 2] {
+        [
+            non_existence_proof(key),
+            multistore_proof(b"wasm", &iavl_root()),
+        ]
+    }
+
+    fn app_hash() -> Vec<u8> {
+        let [_, multistore_proof] = proofs(b"c");
+        let ics23::commitment_proof::Proof::Exist(store) = multistore_proof.proof.expect("proof")
+        else {
+            unreachable!("existence proof");
+        };
+        ics23::calculate_existence_root::<ics23::HostFunctionsManager>(&store).expect("valid proof")
+    }
+
+    #[test]
+    fn absence_proof_is_verified_against_app_hash() {
+        let verifier = CwNonExistenceVerifier::default();
+        let keys = [b"wasm".to_vec(), b"c".to_vec()];
+
+        verifier
+            .verify(&proofs(b"c"), &app_hash(), &keys)
+            .expect("valid absence proof");
+    }
+
+    #[test]
+    fn absence_proof_for_existing_key_is_rejected() {
+        let verifier = CwNonExistenceVerifier::default();
+        let keys = [b"wasm".to_vec(), b"b".to_vec()];
+
+        assert!(matches!(
+            verifier.verify(&proofs(b"b"), &app_hash(), &keys),
+            Err(ProofError::VerificationFailure)
+        ));
+    }
+
+    #[test]
+    fn absence_proof_against_wrong_root_is_rejected() {
+        let verifier = CwNonExistenceVerifier::default();
+        let keys = [b"wasm".to_vec(), b"c".to_vec()];
+
+        assert!(matches!(
+            verifier.verify(&proofs(b"c"), &vec![1; 32], &keys),
+            Err(ProofError::VerificationFailure)
+        ));
+        assert!(matches!(
+            verifier.verify(&proofs(b"c"), &vec![], &keys),
+            Err(ProofError::EmptyMerkleRoot)
+        ));
+
+        // the store must be the wasm store
+        let keys = [b"bank".to_vec(), b"c".to_vec()];
+        assert!(matches!(
+            verifier.verify(&proofs(b"c"), &app_hash(), &keys),
+            Err(ProofError::VerificationFailure)
+        ));
+    }
+}
@@ -2,8 +2,8 @@ use alloc::vec::Vec;
 use core::marker::PhantomData;
 
 use ics23::{
-    calculate_existence_root, commitment_proof::Proof, verify_membership, CommitmentProof,
-    ProofSpec,
+    calculate_existence_root, commitment_proof::Proof, verify_membership, verify_non_membership,
+    CommitmentProof, ProofSpec,
 };
 
 use crate::{error::ProofError, verifier::Verifier};
@@ -64,3 +64,183 @@ where
         Ok(root)
     }
 }
+
+#[derive(Clone, Debug)]
+pub struct Ics23NonMembershipVerifier<K> {
+    spec: ProofSpec,
+    _phantom: PhantomData<K>,
+}
+
+impl<K> Ics23NonMembershipVerifier<K> {
+    pub fn new(spec: ProofSpec) -> Self {
+        Self {
+            spec,
+            _phantom: Default::default(),
+        }
+    }
+}
+
+impl<K> Verifier for Ics23NonMembershipVerifier<K>
+where
+    K: AsRef<[u8]>,
+{
+    type Proof = CommitmentProof;
+    type Root = Vec<u8>;
+    type Key = K;
+    type Value = ();
+    type Error = ProofError;
+
+    fn verify(
+        &self,
+        commitment_proof: &Self::Proof,
+        key: &Self::Key,
+        _value: &Self::Value,
+    ) -> Result<Self::Root, Self::Error> {
+        let Some(Proof::Nonexist(non_existence_proof)) = &commitment_proof.proof else {
+            return Err(ProofError::InvalidMerkleProof);
+        };
+
+        // The root is calculated from either neighbour, `verify_non_membership()` then checks
+        // that both neighbours (if present) are adjacent and hash to the same root.
+        let neighbour = non_existence_proof
+            .left
+            .as_ref()
+            .or(non_existence_proof.right.as_ref())
+            .ok_or(ProofError::InvalidMerkleProof)?;
+        let root = calculate_existence_root::<ics23::HostFunctionsManager>(neighbour)
+            .map_err(|_| ProofError::InvalidMerkleProof)?;
+
+        if !verify_non_membership::<ics23::HostFunctionsManager>(
+            commitment_proof,
+            &self.spec,
+            &root,
+            key.as_ref(),
+        ) {
+            return Err(ProofError::VerificationFailure);
+        }
+
+        Ok(root)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use alloc::{vec, vec::Vec};
+
+    use ics23::{
+        commitment_proof::Proof, ExistenceProof, HashOp, InnerOp, LeafOp, LengthOp,
+        NonExistenceProof,
+    };
+
+    use super::*;
+
+    /// IAVL node prefix for `height`, `size` and `version` (i.e. zigzag varints)
+    fn iavl_prefix(height: u8, size: u8) -> Vec<u8> {
+        vec![height * 2, size * 2, 2]
+    }
+
+    fn leaf_op(prefix: Vec<u8>) -> LeafOp {
+        LeafOp {
+            hash: HashOp::Sha256.into(),
+            prehash_key: HashOp::NoHash.into(),
+            prehash_value: HashOp::Sha256.into(),
+            length: LengthOp::VarProto.into(),
+            prefix,
+        }
+    }
+
+    fn leaf(key: &[u8], value: &[u8], prefix: Vec<u8>) -> ExistenceProof {
+        ExistenceProof {
+            key: key.to_vec(),
+            value: value.to_vec(),
+            leaf: Some(leaf_op(prefix)),
+            path: vec![],
+        }
+    }
+
+    fn leaf_hash(leaf: &ExistenceProof) -> Vec<u8> {
+        calculate_existence_root::<ics23::HostFunctionsManager>(leaf).expect("valid leaf")
+    }
+
+    /// An IAVL tree with two leaves (`b` and `d`) under a single inner node. Returns the existence
+    /// proofs of both leaves.
+    pub(crate) fn iavl_tree() -> (ExistenceProof, ExistenceProof) {
+        let mut left = leaf(b"b", b"left", iavl_prefix(0, 1));
+        let mut right = leaf(b"d", b"right", iavl_prefix(0, 1));
+        let (left_hash, right_hash) = (leaf_hash(&left), leaf_hash(&right));
+
+        left.path.push(InnerOp {
+            hash: HashOp::Sha256.into(),
+            prefix: [iavl_prefix(1, 2), vec![32]].concat(),
+            suffix: [vec![32], right_hash].concat(),
+        });
+        right.path.push(InnerOp {
+            hash: HashOp::Sha256.into(),
+            prefix: [iavl_prefix(1, 2), vec![32], left_hash, vec![32]].concat(),
+            suffix: vec![],
+        });
+        (left, right)
+    }
+
+    pub(crate) fn non_existence_proof(key: &[u8]) -> CommitmentProof {
+        let (left, right) = iavl_tree();
+        CommitmentProof {
+            proof: Some(Proof::Nonexist(NonExistenceProof {
+                key: key.to_vec(),
+                left: Some(left),
+                right: Some(right),
+            })),
+        }
+    }
+
+    /// A multistore (i.e. tendermint spec) tree with a single store `key` with the given `root`.
+    pub(crate) fn multistore_proof(key: &[u8], root: &[u8]) -> CommitmentProof {
+        CommitmentProof {
+            proof: Some(Proof::Exist(leaf(key, root, vec![0]))),
+        }
+    }
+
+    pub(crate) fn iavl_root() -> Vec<u8> {
+        calculate_existence_root::<ics23::HostFunctionsManager>(&iavl_tree().0)
+            .expect("valid proof")
+    }
+
+    #[test]
+    fn absence_of_key_between_neighbours_is_verified() {
+        let verifier = Ics23NonMembershipVerifier::new(ics23::iavl_spec());
+        let key = b"c".to_vec();
+
+        let root = verifier
+            .verify(&non_existence_proof(&key), &key, &())
+            .expect("valid absence proof");
+        assert_eq!(root, iavl_root());
+    }
+
+    #[test]
+    fn absence_of_existing_or_other_keys_is_rejected() {
+        let verifier = Ics23NonMembershipVerifier::new(ics23::iavl_spec());
+
+        // the neighbours themselves exist
+        for key in [b"b".to_vec(), b"d".to_vec()] {
+            assert!(matches!(
+                verifier.verify(&non_existence_proof(&key), &key, &()),
+                Err(ProofError::VerificationFailure)
+            ));
+        }
+        // a key outside the neighbours' range
+        let key = b"e".to_vec();
+        assert!(matches!(
+            verifier.verify(&non_existence_proof(&key), &key, &()),
+            Err(ProofError::VerificationFailure)
+        ));
+        // an existence proof isn't an absence proof
+        let (left, _) = iavl_tree();
+        let existence_proof = CommitmentProof {
+            proof: Some(Proof::Exist(left)),
+        };
+        assert!(matches!(
+            verifier.verify(&existence_proof, &b"c".to_vec(), &()),
+            Err(ProofError::InvalidMerkleProof)
+        ));
+    }
+}
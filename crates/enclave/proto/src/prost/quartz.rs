@@ -26,6 +26,48 @@ pub struct SessionSetPubKeyResponse {
     #[prost(string, tag = "1")]
     pub message: ::prost::alloc::string::String,
 }
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SessionRotatePubKeyRequest {
+    #[prost(string, tag = "1")]
+    pub message: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SessionRotatePubKeyResponse {
+    #[prost(string, tag = "1")]
+    pub message: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SessionHandoverRequestRequest {
+    #[prost(string, tag = "1")]
+    pub message: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SessionHandoverRequestResponse {
+    #[prost(string, tag = "1")]
+    pub message: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SessionHandoverCompleteRequest {
+    #[prost(string, tag = "1")]
+    pub message: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SessionHandoverCompleteResponse {
+    #[prost(string, tag = "1")]
+    pub message: ::prost::alloc::string::String,
+    /// the session state, encrypted to the pub key of the enclave taking over the session
+    #[prost(bytes = "vec", tag = "2")]
+    pub state: ::prost::alloc::vec::Vec<u8>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SessionHandoverAcceptRequest {
+    #[prost(string, tag = "1")]
+    pub message: ::prost::alloc::string::String,
+    #[prost(bytes = "vec", tag = "2")]
+    pub state: ::prost::alloc::vec::Vec<u8>,
+}
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct SessionHandoverAcceptResponse {}
 /// Generated client implementations.
 pub mod core_client {
     #![allow(
@@ -185,6 +227,102 @@ pub mod core_client {
                 .insert(GrpcMethod::new("quartz.Core", "SessionSetPubKey"));
             self.inner.unary(req, path, codec).await
         }
+        pub async fn session_rotate_pub_key(
+            &mut self,
+            request: impl tonic::IntoRequest<super::SessionRotatePubKeyRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::SessionRotatePubKeyResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/quartz.Core/SessionRotatePubKey",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("quartz.Core", "SessionRotatePubKey"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn session_handover_request(
+            &mut self,
+            request: impl tonic::IntoRequest<super::SessionHandoverRequestRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::SessionHandoverRequestResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/quartz.Core/SessionHandoverRequest",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("quartz.Core", "SessionHandoverRequest"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn session_handover_complete(
+            &mut self,
+            request: impl tonic::IntoRequest<super::SessionHandoverCompleteRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::SessionHandoverCompleteResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/quartz.Core/SessionHandoverComplete",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("quartz.Core", "SessionHandoverComplete"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn session_handover_accept(
+            &mut self,
+            request: impl tonic::IntoRequest<super::SessionHandoverAcceptRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::SessionHandoverAcceptResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::unknown(
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/quartz.Core/SessionHandoverAccept",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("quartz.Core", "SessionHandoverAccept"));
+            self.inner.unary(req, path, codec).await
+        }
     }
 }
 /// Generated server implementations.
@@ -221,6 +359,34 @@ pub mod core_server {
             tonic::Response<super::SessionSetPubKeyResponse>,
             tonic::Status,
         >;
+        async fn session_rotate_pub_key(
+            &self,
+            request: tonic::Request<super::SessionRotatePubKeyRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::SessionRotatePubKeyResponse>,
+            tonic::Status,
+        >;
+        async fn session_handover_request(
+            &self,
+            request: tonic::Request<super::SessionHandoverRequestRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::SessionHandoverRequestResponse>,
+            tonic::Status,
+        >;
+        async fn session_handover_complete(
+            &self,
+            request: tonic::Request<super::SessionHandoverCompleteRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::SessionHandoverCompleteResponse>,
+            tonic::Status,
+        >;
+        async fn session_handover_accept(
+            &self,
+            request: tonic::Request<super::SessionHandoverAcceptRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::SessionHandoverAcceptResponse>,
+            tonic::Status,
+        >;
     }
     #[derive(Debug)]
     pub struct CoreServer<T> {
@@ -431,6 +597,189 @@ pub mod core_server {
                     };
                     Box::pin(fut)
                 }
+                "/quartz.Core/SessionRotatePubKey" => {
+                    #[allow(non_camel_case_types)]
+                    struct SessionRotatePubKeySvc<T: Core>(pub Arc<T>);
+                    impl<
+                        T: Core,
+                    > tonic::server::UnaryService<super::SessionRotatePubKeyRequest>
+                    for SessionRotatePubKeySvc<T> {
+                        type Response = super::SessionRotatePubKeyResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::SessionRotatePubKeyRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Core>::session_rotate_pub_key(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = SessionRotatePubKeySvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/quartz.Core/SessionHandoverRequest" => {
+                    #[allow(non_camel_case_types)]
+                    struct SessionHandoverRequestSvc<T: Core>(pub Arc<T>);
+                    impl<
+                        T: Core,
+                    > tonic::server::UnaryService<super::SessionHandoverRequestRequest>
+                    for SessionHandoverRequestSvc<T> {
+                        type Response = super::SessionHandoverRequestResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::SessionHandoverRequestRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Core>::session_handover_request(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = SessionHandoverRequestSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/quartz.Core/SessionHandoverComplete" => {
+                    #[allow(non_camel_case_types)]
+                    struct SessionHandoverCompleteSvc<T: Core>(pub Arc<T>);
+                    impl<
+                        T: Core,
+                    > tonic::server::UnaryService<super::SessionHandoverCompleteRequest>
+                    for SessionHandoverCompleteSvc<T> {
+                        type Response = super::SessionHandoverCompleteResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<
+                                super::SessionHandoverCompleteRequest,
+                            >,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Core>::session_handover_complete(&inner, request)
+                                    .await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = SessionHandoverCompleteSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/quartz.Core/SessionHandoverAccept" => {
+                    #[allow(non_camel_case_types)]
+                    struct SessionHandoverAcceptSvc<T: Core>(pub Arc<T>);
+                    impl<
+                        T: Core,
+                    > tonic::server::UnaryService<super::SessionHandoverAcceptRequest>
+                    for SessionHandoverAcceptSvc<T> {
+                        type Response = super::SessionHandoverAcceptResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::SessionHandoverAcceptRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Core>::session_handover_accept(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let method = SessionHandoverAcceptSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
                 _ => {
                     Box::pin(async move {
                         let mut response = http::Response::new(empty_body());
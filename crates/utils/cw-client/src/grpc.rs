@@ -1,4 +1,4 @@
-use std::error::Error;
+use std::{error::Error, fmt, future::Future, time::Duration};
 
 use anyhow::anyhow;
 use cosmos_sdk_proto::{
@@ -7,8 +7,10 @@ use cosmos_sdk_proto::{
             query_client::QueryClient as AuthQueryClient, BaseAccount as RawBaseAccount,
             QueryAccountRequest,
         },
+        base::abci::v1beta1::TxResponse,
         tx::v1beta1::{
             service_client::ServiceClient, BroadcastMode, BroadcastTxRequest, BroadcastTxResponse,
+            GetTxRequest,
         },
     },
     cosmwasm::wasm::v1::{
@@ -22,25 +24,310 @@ use cosmrs::{
     auth::BaseAccount,
     cosmwasm::MsgExecuteContract,
     crypto::{secp256k1::SigningKey, PublicKey},
-    tendermint::chain::Id as TmChainId,
+    tendermint::{abci::Event, chain::Id as TmChainId},
     tx,
     tx::{Fee, Msg, SignDoc, SignerInfo},
     AccountId, Coin,
 };
+use log::warn;
 use reqwest::Url;
 use serde::de::DeserializeOwned;
+use tokio::{sync::Mutex, time::sleep};
 
 use crate::CwClient;
 
+/// The SDK's error code for an account sequence mismatch (`ErrWrongSequence`).
+const ACCOUNT_SEQUENCE_MISMATCH: u32 = 32;
+/// The SDK's error code for a tx that is already in the mempool (`ErrTxInMempoolCache`).
+const TX_IN_MEMPOOL_CACHE: u32 = 19;
+
 pub struct GrpcClient {
     sk: SigningKey,
     url: Url,
+    /// The signer's account number and the sequence of its next tx, if known.
+    account: Mutex<Option<(u64, u64)>>,
+    tx_config: TxSubmitConfig,
 }
 
 impl GrpcClient {
     pub fn new(sk: SigningKey, url: Url) -> Self {
-        Self { sk, url }
+        Self {
+            sk,
+            url,
+            account: Mutex::new(None),
+            tx_config: TxSubmitConfig::default(),
+        }
     }
+
+    pub fn with_tx_config(mut self, tx_config: TxSubmitConfig) -> Self {
+        self.tx_config = tx_config;
+        self
+    }
+
+    /// Executes `msg` on the `contract` and waits for the tx to be included in a block.
+    ///
+    /// The account sequence is cached (and incremented) locally, so that back-to-back txs don't
+    /// have to wait for each other to be committed. The sequence is resynced on a mismatch, in
+    /// which case the tx is retried (as are failed broadcasts). Note that a tx that is included
+    /// but fails is not an error, see `TxResult::code`.
+    pub async fn submit_tx(
+        &self,
+        contract: &AccountId,
+        chain_id: &TmChainId,
+        gas: u64,
+        msg: impl ToString,
+    ) -> Result<TxResult, anyhow::Error> {
+        let tm_pubkey = self.sk.public_key();
+        let sender = tm_pubkey
+            .account_id("neutron")
+            .map_err(|e| anyhow!("failed to create AccountId from pubkey: {}", e))?;
+
+        let msgs = vec![MsgExecuteContract {
+            sender: sender.clone(),
+            contract: contract.clone(),
+            msg: msg.to_string().into_bytes(),
+            funds: vec![],
+        }
+        .to_any()
+        .map_err(|e| anyhow!("failed to encode msg: {}", e))?];
+
+        let txhash = retry_broadcast(&self.tx_config, || {
+            self.broadcast(&sender, tm_pubkey, msgs.clone(), gas, chain_id)
+        })
+        .await?;
+
+        wait_for_inclusion(&self.tx_config, &txhash, || {
+            get_tx(self.url.to_string(), &txhash)
+        })
+        .await
+    }
+
+    /// Signs the tx with the cached account sequence and broadcasts it (in sync mode, i.e. it is
+    /// only checked, not executed).
+    async fn broadcast(
+        &self,
+        sender: &AccountId,
+        tm_pubkey: PublicKey,
+        msgs: Vec<Any>,
+        gas: u64,
+        chain_id: &TmChainId,
+    ) -> Result<String, BroadcastError> {
+        // Hold the lock until the tx is in the mempool, so that concurrent txs get consecutive
+        // sequence numbers.
+        let mut account = self.account.lock().await;
+        let (account_number, sequence) = match *account {
+            Some(account) => account,
+            None => {
+                let info = account_info(self.url.to_string(), sender.to_string())
+                    .await
+                    .map_err(|e| {
+                        BroadcastError::Retryable(anyhow!("error querying account info: {}", e))
+                    })?;
+                (info.account_number, info.sequence)
+            }
+        };
+
+        let amount = Coin {
+            amount: 11000u128,
+            denom: "untrn".parse().expect("hardcoded denom"),
+        };
+        let tx_bytes = tx_bytes(
+            &self.sk,
+            amount,
+            gas,
+            tm_pubkey,
+            msgs,
+            sequence,
+            account_number,
+            chain_id,
+        )
+        .map_err(|e| BroadcastError::Rejected(anyhow!("failed to create msg/tx: {}", e)))?;
+
+        // The tx may or may not have made it into the mempool, so resync the sequence next time.
+        *account = None;
+        let tx_response = send_tx(self.url.to_string(), tx_bytes)
+            .await
+            .map_err(|e| BroadcastError::Retryable(anyhow!("failed to send tx: {}", e)))?
+            .tx_response
+            .ok_or_else(|| BroadcastError::Retryable(anyhow!("missing tx response")))?;
+
+        let (next_account, result) = broadcast_outcome(account_number, sequence, tx_response);
+        *account = next_account;
+        result
+    }
+}
+
+/// Calls `broadcast` until it succeeds, retrying retryable errors up to `max_retries` times with
+/// exponential backoff.
+async fn retry_broadcast<F, Fut>(
+    tx_config: &TxSubmitConfig,
+    mut broadcast: F,
+) -> Result<String, anyhow::Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<String, BroadcastError>>,
+{
+    let mut backoff = tx_config.retry_backoff;
+    let mut retries = 0;
+    loop {
+        match broadcast().await {
+            Ok(txhash) => return Ok(txhash),
+            Err(BroadcastError::Retryable(e)) if retries < tx_config.max_retries => {
+                retries += 1;
+                warn!("Retrying tx ({retries}/{}): {e}", tx_config.max_retries);
+                sleep(backoff).await;
+                backoff *= 2;
+            }
+            Err(BroadcastError::Retryable(e) | BroadcastError::Rejected(e)) => return Err(e),
+        }
+    }
+}
+
+/// Polls `get_tx` until the tx is included in a block or the inclusion timeout has passed.
+async fn wait_for_inclusion<F, Fut>(
+    tx_config: &TxSubmitConfig,
+    txhash: &str,
+    mut get_tx: F,
+) -> Result<TxResult, anyhow::Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<Option<TxResponse>, Box<dyn Error>>>,
+{
+    let mut waited = Duration::ZERO;
+    loop {
+        if let Ok(Some(tx_response)) = get_tx().await {
+            return TxResult::try_from(tx_response);
+        }
+        if waited >= tx_config.inclusion_timeout {
+            return Err(anyhow!("tx {} was not included in time", txhash));
+        }
+        sleep(tx_config.poll_interval).await;
+        waited += tx_config.poll_interval;
+    }
+}
+
+/// The outcome of broadcasting a tx with `sequence`, along with the account (number and sequence)
+/// to cache for the next tx, if it's known.
+fn broadcast_outcome(
+    account_number: u64,
+    sequence: u64,
+    tx_response: TxResponse,
+) -> (Option<(u64, u64)>, Result<String, BroadcastError>) {
+    match tx_response.code {
+        0 => (Some((account_number, sequence + 1)), Ok(tx_response.txhash)),
+        // e.g. a retry of a tx whose broadcast timed out after it was accepted
+        TX_IN_MEMPOOL_CACHE if tx_response.codespace == "sdk" => {
+            (Some((account_number, sequence + 1)), Ok(tx_response.txhash))
+        }
+        ACCOUNT_SEQUENCE_MISMATCH if tx_response.codespace == "sdk" => (
+            expected_sequence(&tx_response.raw_log).map(|sequence| (account_number, sequence)),
+            Err(BroadcastError::Retryable(anyhow!(
+                "account sequence mismatch: {}",
+                tx_response.raw_log
+            ))),
+        ),
+        code => (
+            Some((account_number, sequence)),
+            Err(BroadcastError::Rejected(anyhow!(
+                "tx rejected with code {}: {}",
+                code,
+                tx_response.raw_log
+            ))),
+        ),
+    }
+}
+
+/// Retry and timeout settings for `GrpcClient::submit_tx`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TxSubmitConfig {
+    /// How often a tx is retried after a failed broadcast or an account sequence mismatch.
+    pub max_retries: u32,
+    /// The delay before the first retry, which doubles with every further retry.
+    pub retry_backoff: Duration,
+    /// How long to wait for a broadcast tx to be included in a block.
+    pub inclusion_timeout: Duration,
+    pub poll_interval: Duration,
+}
+
+impl Default for TxSubmitConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 5,
+            retry_backoff: Duration::from_millis(500),
+            inclusion_timeout: Duration::from_secs(60),
+            poll_interval: Duration::from_secs(1),
+        }
+    }
+}
+
+/// The result of a tx that was included in a block.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TxResult {
+    pub hash: String,
+    pub height: u64,
+    pub gas_wanted: u64,
+    pub gas_used: u64,
+    /// The result code of the tx's execution (zero if the tx succeeded).
+    pub code: u32,
+    pub codespace: String,
+    pub raw_log: String,
+    pub events: Vec<Event>,
+}
+
+impl TxResult {
+    pub fn is_ok(&self) -> bool {
+        self.code == 0
+    }
+}
+
+impl TryFrom<TxResponse> for TxResult {
+    type Error = anyhow::Error;
+
+    fn try_from(tx_response: TxResponse) -> Result<Self, Self::Error> {
+        Ok(Self {
+            hash: tx_response.txhash,
+            height: tx_response.height.try_into()?,
+            gas_wanted: tx_response.gas_wanted.try_into()?,
+            gas_used: tx_response.gas_used.try_into()?,
+            code: tx_response.code,
+            codespace: tx_response.codespace,
+            raw_log: tx_response.raw_log,
+            events: tx_response
+                .events
+                .into_iter()
+                .map(Event::try_from)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+impl fmt::Display for TxResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} (height: {}, code: {}, gas used: {}/{})",
+            self.hash, self.height, self.code, self.gas_used, self.gas_wanted
+        )?;
+        if !self.is_ok() {
+            write!(f, ": {}", self.raw_log)?;
+        }
+        Ok(())
+    }
+}
+
+enum BroadcastError {
+    Retryable(anyhow::Error),
+    Rejected(anyhow::Error),
+}
+
+/// Extracts the expected sequence from an account sequence mismatch error, e.g.
+/// `account sequence mismatch, expected 5, got 4: incorrect account sequence`.
+fn expected_sequence(raw_log: &str) -> Option<u64> {
+    let (_, expected) = raw_log.split_once("expected ")?;
+    let digits = expected
+        .find(|c: char| !c.is_ascii_digit())
+        .map_or(expected, |end| &expected[..end]);
+    digits.parse().ok()
 }
 
 #[async_trait::async_trait]
@@ -102,47 +389,9 @@ impl CwClient for GrpcClient {
         msg: M,
         _pay_amount: &str,
     ) -> Result<String, Self::Error> {
-        let tm_pubkey = self.sk.public_key();
-        let sender = tm_pubkey
-            .account_id("neutron")
-            .map_err(|e| anyhow!("failed to create AccountId from pubkey: {}", e))?;
-
-        let msgs = vec![MsgExecuteContract {
-            sender: sender.clone(),
-            contract: contract.clone(),
-            msg: msg.to_string().into_bytes(),
-            funds: vec![],
-        }
-        .to_any()
-        .unwrap()];
-
-        let account = account_info(self.url.to_string(), sender.to_string())
+        self.submit_tx(contract, chain_id, gas, msg)
             .await
-            .map_err(|e| anyhow!("error querying account info: {}", e))?;
-        let amount = Coin {
-            amount: 11000u128,
-            denom: "untrn".parse().expect("hardcoded denom"),
-        };
-        let tx_bytes = tx_bytes(
-            &self.sk,
-            amount,
-            gas,
-            tm_pubkey,
-            msgs,
-            account.sequence,
-            account.account_number,
-            chain_id,
-        )
-        .map_err(|e| anyhow!("failed to create msg/tx: {}", e))?;
-
-        let response = send_tx(self.url.to_string(), tx_bytes)
-            .await
-            .map_err(|e| anyhow!("failed to send tx: {}", e))?;
-        println!("{response:?}");
-        Ok(response
-            .tx_response
-            .map(|tx_response| tx_response.txhash)
-            .unwrap_or_default())
+            .map(|tx_result| tx_result.hash)
     }
 
     fn deploy<M: ToString>(
@@ -215,3 +464,167 @@ pub async fn send_tx(
     let tx_response = client.broadcast_tx(request).await?;
     Ok(tx_response.into_inner())
 }
+
+/// Returns the response of the tx with the given hash, or `None` if it isn't (yet) included in a
+/// block.
+pub async fn get_tx(
+    node: impl ToString,
+    txhash: &str,
+) -> Result<Option<TxResponse>, Box<dyn Error>> {
+    let mut client = ServiceClient::connect(node.to_string()).await?;
+    let request = tonic::Request::new(GetTxRequest {
+        hash: txhash.to_string(),
+    });
+    match client.get_tx(request).await {
+        Ok(response) => Ok(response.into_inner().tx_response),
+        Err(status) if status.code() == tonic::Code::NotFound => Ok(None),
+        Err(status) => Err(status.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, future::ready};
+
+    use super::*;
+
+    fn tx_config() -> TxSubmitConfig {
+        TxSubmitConfig {
+            max_retries: 2,
+            retry_backoff: Duration::from_millis(1),
+            inclusion_timeout: Duration::from_millis(5),
+            poll_interval: Duration::from_millis(1),
+        }
+    }
+
+    fn tx_response(code: u32, codespace: &str, raw_log: &str) -> TxResponse {
+        TxResponse {
+            txhash: "ABCD".to_string(),
+            height: 7,
+            code,
+            codespace: codespace.to_string(),
+            raw_log: raw_log.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn sequence_is_advanced_or_resynced_after_broadcast() {
+        // accepted into the mempool
+        let (account, result) = broadcast_outcome(1, 4, tx_response(0, "", ""));
+        assert_eq!(account, Some((1, 5)));
+        assert!(matches!(result, Ok(txhash) if txhash == "ABCD"));
+
+        // already in the mempool, i.e. the sequence is used up as well
+        let in_mempool = "tx already exists in cache";
+        let (account, result) = broadcast_outcome(1, 4, tx_response(19, "sdk", in_mempool));
+        assert_eq!(account, Some((1, 5)));
+        assert!(matches!(result, Ok(txhash) if txhash == "ABCD"));
+        let (account, result) = broadcast_outcome(1, 4, tx_response(19, "wasm", in_mempool));
+        assert_eq!(account, Some((1, 4)));
+        assert!(matches!(result, Err(BroadcastError::Rejected(_))));
+
+        // the expected sequence is taken from the error, if possible
+        let mismatch = "account sequence mismatch, expected 7, got 4: incorrect account sequence";
+        let (account, result) = broadcast_outcome(1, 4, tx_response(32, "sdk", mismatch));
+        assert_eq!(account, Some((1, 7)));
+        assert!(matches!(result, Err(BroadcastError::Retryable(_))));
+        let (account, result) = broadcast_outcome(1, 4, tx_response(32, "sdk", "mismatch"));
+        assert_eq!(account, None);
+        assert!(matches!(result, Err(BroadcastError::Retryable(_))));
+
+        // a rejected tx doesn't use up the sequence
+        let (account, result) = broadcast_outcome(1, 4, tx_response(32, "wasm", mismatch));
+        assert_eq!(account, Some((1, 4)));
+        assert!(matches!(result, Err(BroadcastError::Rejected(_))));
+        let (account, result) = broadcast_outcome(1, 4, tx_response(13, "sdk", "out of gas"));
+        assert_eq!(account, Some((1, 4)));
+        assert!(matches!(result, Err(BroadcastError::Rejected(_))));
+    }
+
+    #[tokio::test]
+    async fn retryable_broadcasts_are_retried_up_to_max_retries() {
+        let attempts = Cell::new(0);
+        let broadcast = |succeed_at| {
+            let attempts = &attempts;
+            move || {
+                attempts.set(attempts.get() + 1);
+                ready(if attempts.get() == succeed_at {
+                    Ok("ABCD".to_string())
+                } else {
+                    Err(BroadcastError::Retryable(anyhow!("failed to send tx")))
+                })
+            }
+        };
+
+        let txhash = retry_broadcast(&tx_config(), broadcast(3))
+            .await
+            .expect("succeeds on the last retry");
+        assert_eq!(txhash, "ABCD");
+        assert_eq!(attempts.get(), 3);
+
+        attempts.set(0);
+        retry_broadcast(&tx_config(), broadcast(4))
+            .await
+            .expect_err("retries are exhausted");
+        assert_eq!(attempts.get(), 3);
+
+        // rejected txs aren't retried
+        attempts.set(0);
+        let rejected = || {
+            attempts.set(attempts.get() + 1);
+            ready(Err(BroadcastError::Rejected(anyhow!("tx rejected"))))
+        };
+        retry_broadcast(&tx_config(), rejected)
+            .await
+            .expect_err("rejected tx");
+        assert_eq!(attempts.get(), 1);
+    }
+
+    #[tokio::test]
+    async fn inclusion_is_polled_until_timeout() {
+        let polls = Cell::new(0);
+        let get_tx = |included_at| {
+            let polls = &polls;
+            move || {
+                polls.set(polls.get() + 1);
+                let tx_response = (polls.get() == included_at).then(|| tx_response(0, "", ""));
+                ready(Ok(tx_response))
+            }
+        };
+
+        let tx_result = wait_for_inclusion(&tx_config(), "ABCD", get_tx(3))
+            .await
+            .expect("tx is included");
+        assert_eq!(tx_result.height, 7);
+        assert!(tx_result.is_ok());
+        assert_eq!(polls.get(), 3);
+
+        // i.e. polled right away and after each of the 5 poll intervals
+        polls.set(0);
+        let err = wait_for_inclusion(&tx_config(), "ABCD", get_tx(0))
+            .await
+            .expect_err("tx isn't included in time");
+        assert!(err.to_string().contains("not included in time"), "{err}");
+        assert_eq!(polls.get(), 6);
+
+        // a tx that is included but failed is still a result
+        let failed = || ready(Ok(Some(tx_response(5, "sdk", "insufficient funds"))));
+        let tx_result = wait_for_inclusion(&tx_config(), "ABCD", failed)
+            .await
+            .expect("tx is included");
+        assert!(!tx_result.is_ok());
+    }
+
+    #[test]
+    fn expected_sequence_is_parsed_from_mismatch_log() {
+        assert_eq!(
+            expected_sequence(
+                "account sequence mismatch, expected 5, got 4: incorrect account sequence"
+            ),
+            Some(5)
+        );
+        assert_eq!(expected_sequence("expected 12"), Some(12));
+        assert_eq!(expected_sequence("out of gas"), None);
+    }
+}
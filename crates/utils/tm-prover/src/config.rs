@@ -3,7 +3,7 @@ use std::{num::ParseIntError, str::FromStr};
 use clap::Parser;
 use color_eyre::eyre::{eyre, Result};
 use cosmrs::AccountId;
-use quartz_cw_proof::proof::cw::RawCwProof;
+use quartz_cw_proof::proof::{cw::RawCwProof, key::StorageKey};
 use serde::{Deserialize, Serialize};
 use tendermint_light_client::types::{Hash, Height, LightBlock, TrustThreshold};
 use tendermint_rpc::HttpClientUrl;
@@ -63,10 +63,15 @@ impl FromStr for Verbosity {
     }
 }
 
+/// The output of the prover, where `P` is either an existence proof (`RawCwProof`) or a
+/// non-existence proof (`RawCwNonExistenceProof`).
 #[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct ProofOutput {
+pub struct ProofOutput<P = RawCwProof> {
     pub light_client_proof: Vec<LightBlock>,
-    pub merkle_proof: RawCwProof,
+    pub merkle_proof: P,
+    /// Merkle proofs for the `additional_storage_keys`, at the same height as `merkle_proof`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub additional_merkle_proofs: Vec<P>,
 }
 
 // TODO: Investigate if it's possible to derive default using Clap's default values, or otherwise find better default values
@@ -88,6 +93,7 @@ impl Default for Config {
                 .unwrap(),
             storage_key: String::default(),
             storage_namespace: None,
+            additional_storage_keys: vec![],
         }
     }
 }
@@ -107,7 +113,7 @@ pub struct Config {
     #[clap(long)]
     pub witnesses: List<HttpClientUrl>,
 
-    /// Height of trusted header
+    /// Height of trusted header (e.g. the latest height verified by the enclave)
     #[clap(long)]
     pub trusted_height: Height,
 
@@ -147,4 +153,9 @@ pub struct Config {
     /// (only makes sense when dealing with maps)
     #[clap(long)]
     pub storage_namespace: Option<String>,
+
+    /// Additional state items for which proofs must be retrieved at the same height, each
+    /// specified as `key` or `namespace:key`, neither containing `:` (can be repeated)
+    #[clap(long = "additional-storage-key")]
+    pub additional_storage_keys: Vec<StorageKey>,
 }
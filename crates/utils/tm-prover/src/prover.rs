@@ -16,9 +16,13 @@ use color_eyre::{
 use futures::future::join_all;
 use quartz_cw_proof::{
     error::ProofError,
-    proof::{cw::CwProof, key::CwAbciKey, Proof},
+    proof::{
+        cw::{CwNonExistenceProof, CwProof, RawCwNonExistenceProof, RawCwProof},
+        key::CwAbciKey,
+        Proof,
+    },
 };
-use tendermint::{crypto::default::Sha256, evidence::Evidence, Hash};
+use tendermint::{crypto::default::Sha256, evidence::Evidence, AppHash, Hash};
 use tendermint_light_client::{
     builder::LightClientBuilder,
     light_client::Options,
@@ -26,14 +30,33 @@ use tendermint_light_client::{
     types::{Height, LightBlock},
 };
 use tendermint_light_client_detector::{detect_divergence, Error, Provider, Trace};
-use tendermint_rpc::{client::HttpClient, Client, HttpClientUrl};
+use tendermint_rpc::{client::HttpClient, endpoint::abci_query::AbciQuery, Client, HttpClientUrl};
+use tokio::time::{sleep, Instant};
 use tracing::{error, info};
 
 const WASM_STORE_KEY: &str = "/store/wasm/key";
 
+/// How long to wait for the chain to move past the trusted height, and how often to check.
+const NEXT_BLOCK_TIMEOUT: Duration = Duration::from_secs(60);
+const NEXT_BLOCK_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
 use crate::config::{Config as TmProverConfig, ProofOutput};
 
-pub async fn prove(
+/// Generates a light client proof up to the latest height along with merkle proofs for the
+/// existence of the configured storage keys at that height.
+pub async fn prove(config: TmProverConfig) -> Result<ProofOutput> {
+    prove_with(config).await
+}
+
+/// Like [`prove`], but the merkle proofs are for the absence (i.e. non-existence) of the
+/// configured storage keys.
+pub async fn prove_non_existence(
+    config: TmProverConfig,
+) -> Result<ProofOutput<RawCwNonExistenceProof>> {
+    prove_with(config).await
+}
+
+async fn prove_with<P: QueriedProof>(
     TmProverConfig {
         chain_id,
         primary,
@@ -48,8 +71,9 @@ pub async fn prove(
         contract_address,
         storage_key,
         storage_namespace,
+        additional_storage_keys,
     }: TmProverConfig,
-) -> Result<ProofOutput> {
+) -> Result<ProofOutput<P>> {
     let options = Options {
         trust_threshold,
         trusting_period: Duration::from_secs(trusting_period),
@@ -71,10 +95,10 @@ pub async fn prove(
         .latest_trusted()
         .ok_or_else(|| eyre!("No trusted state found for primary"))?;
 
-    info!("Getting status of node");
-    let status = client.status().await?;
-    let latest_height = status.sync_info.latest_block_height;
-    let latest_app_hash = status.sync_info.latest_app_hash;
+    // The trace is generated starting from the trusted block (e.g. the latest block verified by
+    // the enclave), which may well be the latest block on the chain.
+    let (latest_height, latest_app_hash) =
+        wait_for_block_after(&client, trusted_block.height()).await?;
 
     // `proof_height` is the height at which we want to query the blockchain's state
     // This is one less than than the `latest_height` because we want to verify the merkle-proof for
@@ -115,19 +139,20 @@ pub async fn prove(
     )
     .await?;
 
-    let path = WASM_STORE_KEY.to_owned();
-    let data = CwAbciKey::new(contract_address, storage_key, storage_namespace);
-    let result = client
-        .abci_query(Some(path), data, Some(proof_height), true)
-        .await?;
+    let merkle_proof = query_proof(
+        &client,
+        CwAbciKey::new(contract_address.clone(), storage_key, storage_namespace),
+        proof_height,
+        &latest_app_hash,
+    )
+    .await?;
 
-    let proof: CwProof = result
-        .clone()
-        .try_into()
-        .expect("result should contain proof");
-    proof
-        .verify(latest_app_hash.clone().into())
-        .map_err(|e: ProofError| eyre!(e))?;
+    let mut additional_merkle_proofs = Vec::with_capacity(additional_storage_keys.len());
+    for storage_key in additional_storage_keys {
+        let key = CwAbciKey::from_storage_key(contract_address.clone(), storage_key);
+        additional_merkle_proofs
+            .push(query_proof(&client, key, proof_height, &latest_app_hash).await?);
+    }
 
     // Replace the last block in the trace (i.e., the (latest - 1) block) with the latest block
     // We don't actually verify the latest block because it will be verified on the other side
@@ -137,12 +162,81 @@ pub async fn prove(
 
     let output = ProofOutput {
         light_client_proof: primary_trace,
-        merkle_proof: proof.into(),
+        merkle_proof,
+        additional_merkle_proofs,
     };
 
     Ok(output)
 }
 
+/// A merkle proof of contract state that can be obtained from an ABCI query response.
+trait QueriedProof: Sized {
+    /// Extracts the proof from the ABCI `query` response and verifies it against the `app_hash`.
+    fn from_abci_query(query: AbciQuery, app_hash: &AppHash) -> Result<Self>;
+}
+
+impl QueriedProof for RawCwProof {
+    fn from_abci_query(query: AbciQuery, app_hash: &AppHash) -> Result<Self> {
+        let proof = CwProof::try_from(query).map_err(|e| eyre!(e))?;
+        proof
+            .verify(app_hash.clone().into())
+            .map_err(|e: ProofError| eyre!(e))?;
+
+        Ok(proof.into())
+    }
+}
+
+impl QueriedProof for RawCwNonExistenceProof {
+    fn from_abci_query(query: AbciQuery, app_hash: &AppHash) -> Result<Self> {
+        let proof = CwNonExistenceProof::try_from(query).map_err(|e| eyre!(e))?;
+        proof
+            .verify(app_hash.clone().into())
+            .map_err(|e: ProofError| eyre!(e))?;
+
+        Ok(proof.into())
+    }
+}
+
+/// Waits until the latest block on the primary is above `height` and returns its height and app
+/// hash.
+async fn wait_for_block_after(client: &HttpClient, height: Height) -> Result<(Height, AppHash)> {
+    let deadline = Instant::now() + NEXT_BLOCK_TIMEOUT;
+    loop {
+        info!("Getting status of node");
+        let status = client.status().await?;
+        let latest_height = status.sync_info.latest_block_height;
+        if latest_height > height {
+            return Ok((latest_height, status.sync_info.latest_app_hash));
+        }
+
+        if Instant::now() >= deadline {
+            return Err(eyre!(
+                "latest height {} on primary didn't move past trusted height {}",
+                latest_height,
+                height
+            ));
+        }
+        info!("Waiting for a block after trusted height {}", height);
+        sleep(NEXT_BLOCK_POLL_INTERVAL).await;
+    }
+}
+
+/// Queries the contract state at `key` (along with its merkle proof) at `proof_height` and
+/// verifies the proof against the `app_hash` (i.e. the one committed to in the next block).
+async fn query_proof<P: QueriedProof>(
+    client: &HttpClient,
+    key: CwAbciKey,
+    proof_height: Height,
+    app_hash: &AppHash,
+) -> Result<P> {
+    let path = WASM_STORE_KEY.to_owned();
+    let result = client
+        .abci_query(Some(path), key, Some(proof_height), true)
+        .await?;
+
+    P::from_abci_query(result, app_hash)
+}
+
 async fn run_detector(
     primary: &mut Provider,
     witnesses: &mut [Provider],
@@ -2,7 +2,7 @@ use cosmwasm_std::{
     entry_point, to_json_binary, Binary, Deps, DepsMut, Env, Event, MessageInfo, Response,
     StdResult,
 };
-use quartz_contract_core::handler::RawHandler;
+use quartz_contract_core::handler::{QueryHandler, RawHandler};
 
 use crate::{
     error::ContractError,
@@ -90,8 +90,9 @@ pub mod execute {
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
+        QueryMsg::Quartz(msg) => msg.handle_query(deps, &env),
         QueryMsg::GetAllMessages {} => to_json_binary(&query::get_all_messages(deps)?),
     }
 }
@@ -43,5 +43,7 @@ pub mod execute {
 
 #[cw_serde]
 pub enum QueryMsg {
+    // Quartz state
+    Quartz(QuartzQueryMsg),
     GetAllMessages {},
 }
@@ -1,4 +1,4 @@
-use std::{env, net::SocketAddr};
+use std::{env, net::SocketAddr, path::PathBuf};
 
 use clap::Parser;
 use color_eyre::eyre::{eyre, Result};
@@ -33,6 +33,23 @@ pub struct Cli {
     #[clap(long)]
     pub fmspc: Option<Fmspc>,
 
+    /// Base URL of the PCCS (Provisioning Certificate Caching Service)
+    #[clap(long, default_value = "https://127.0.0.1:8081")]
+    pub pccs_url: Url,
+
+    /// PEM encoded root certificate to verify the PCCS' TLS certificate against (instead of the
+    /// built-in roots), e.g. for a local PCCS with a self-signed certificate
+    #[clap(long)]
+    pub pccs_ca: Option<PathBuf>,
+
+    /// Don't verify the PCCS' TLS certificate (for development only)
+    #[clap(long, conflicts_with = "pccs_ca")]
+    pub pccs_insecure: bool,
+
+    /// How long fetched PCCS collateral is reused, in seconds
+    #[clap(long, default_value = "3600")]
+    pub pccs_cache_ttl: u64,
+
     /// TcbInfo contract address
     #[clap(long)]
     pub tcbinfo_contract: Option<AccountId>,
@@ -76,6 +93,11 @@ pub struct Cli {
 
     #[clap(long, default_value = "admin")]
     pub tx_sender: String,
+
+    /// File in which the host keeps track of the last processed block, so that it can catch up
+    /// on missed events after a restart
+    #[clap(long)]
+    pub checkpoint_file: Option<PathBuf>,
 }
 
 fn default_rpc_addr() -> SocketAddr {
@@ -82,12 +82,14 @@ impl Handler<DefaultSharedEnclave<()>> for PingRequest {
             .ok_or_else(|| Status::not_found("config not found"))?;
         let (proof_value, ping) = proof
             .verify(
+                ctx.store().await,
                 config.light_client_opts(),
                 contract,
                 PINGS_KEY.to_string(),
                 None,
             )
-            .map_err(Status::failed_precondition)?;
+            .await
+            .map_err(|e| Status::failed_precondition(e.to_string()))?;
 
         let proof_value_matches_msg =
             serde_json::to_string(&ping.message).is_ok_and(|s| s.as_bytes() == proof_value);
@@ -98,9 +100,15 @@ impl Handler<DefaultSharedEnclave<()>> for PingRequest {
         // Perform enclave logic
         // Decrypt the ciphertext using enclave private key
         let decrypted_message: String = {
-            let sk = ctx.key_manager().await.read_lock().await.sk.clone();
-
-            let msg_bytes = decrypt(&sk.to_bytes(), &ping.message)
+            // Fall back to the previous key in case the ping was sent before a key rotation
+            let key_manager = ctx.key_manager().await;
+            let key_manager = key_manager.read_lock().await;
+
+            let msg_bytes = decrypt(&key_manager.sk.to_bytes(), &ping.message)
+                .or_else(|e| match &key_manager.prev_sk {
+                    Some(prev_sk) => decrypt(&prev_sk.to_bytes(), &ping.message),
+                    None => Err(e),
+                })
                 .map_err(|_| Status::invalid_argument("decryption failed"))?;
 
             String::from_utf8(msg_bytes)
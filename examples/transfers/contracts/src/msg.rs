@@ -18,6 +18,8 @@ pub struct InstantiateMsg<RA = RawDefaultAttestation> {
 
 #[cw_serde]
 pub enum QueryMsg {
+    // Quartz state
+    Quartz(QuartzQueryMsg),
     GetBalance { address: String },
     GetRequests {},
     GetState {},
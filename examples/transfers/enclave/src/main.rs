@@ -26,7 +26,7 @@ use quartz_common::{
     enclave::{
         attestor::{self, Attestor},
         chain_client::default::{DefaultChainClient, DefaultTxConfig},
-        host::{DefaultHost, Host},
+        host::{checkpoint::FileCheckpointStore, DefaultHost, EventFilter, Host},
         DefaultSharedEnclave,
     },
     proto::core_server::CoreServer,
@@ -68,9 +68,19 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     )?;
 
     #[cfg(not(feature = "mock-sgx"))]
-    let attestor = attestor::DcapAttestor {
-        fmspc: args.fmspc.expect("FMSPC is required for DCAP"),
-    };
+    let attestor = attestor::DcapAttestor::with_pccs_config(
+        args.fmspc.expect("FMSPC is required for DCAP"),
+        attestor::pccs::PccsConfig {
+            url: args.pccs_url,
+            tls: match (args.pccs_ca, args.pccs_insecure) {
+                (Some(ca), _) => attestor::pccs::PccsTls::PinnedRoot(std::fs::read(ca)?),
+                (None, true) => attestor::pccs::PccsTls::AcceptInvalidCerts,
+                (None, false) => attestor::pccs::PccsTls::BuiltInRoots,
+            },
+            cache_ttl: std::time::Duration::from_secs(args.pccs_cache_ttl),
+            ..Default::default()
+        },
+    );
 
     #[cfg(feature = "mock-sgx")]
     let attestor = attestor::MockAttestor::default();
@@ -92,11 +102,18 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     );
 
     let enclave = DefaultSharedEnclave::shared(attestor, config, ());
-    let host = DefaultHost::<EnclaveRequest, EnclaveEvent, _, _>::new(
+    let mut host = DefaultHost::<EnclaveRequest, EnclaveEvent, _, _>::new(
         enclave.clone(),
         chain_client,
         gas_fn,
-    );
+    )
+    .with_event_filters([
+        EventFilter::EventType("transfer".to_string()),
+        EventFilter::EventType("query_balance".to_string()),
+    ]);
+    if let Some(checkpoint_file) = args.checkpoint_file {
+        host = host.with_checkpoint_store(FileCheckpointStore::new(checkpoint_file));
+    }
 
     tokio::spawn(async move {
         Server::builder()
@@ -1,11 +1,12 @@
 use cosmwasm_std::HexBinary;
 use ecies::{decrypt, encrypt};
-use k256::ecdsa::{SigningKey, VerifyingKey};
+use k256::ecdsa::VerifyingKey;
 use quartz_common::{
     contract::msg::execute::attested::{HasUserData, RawNoop},
     enclave::{
         attestor::{Attestor, DefaultAttestor},
         handler::Handler,
+        key_manager::default::DefaultKeyManager,
         DefaultSharedEnclave, Enclave,
     },
 };
@@ -64,20 +65,32 @@ impl Handler<DefaultSharedEnclave<()>> for EnclaveRequest {
     }
 }
 
+/// Decrypts with the enclave's current key, falling back to the previous key in case the
+/// ciphertext was encrypted before a key rotation.
+fn decrypt_with_key_manager(
+    key_manager: &DefaultKeyManager,
+    ciphertext: &[u8],
+) -> Result<Vec<u8>, Status> {
+    decrypt(&key_manager.sk.to_bytes(), ciphertext)
+        .or_else(|e| match &key_manager.prev_sk {
+            Some(prev_sk) => decrypt(&prev_sk.to_bytes(), ciphertext),
+            None => Err(e),
+        })
+        .map_err(|e| Status::invalid_argument(e.to_string()))
+}
+
 fn decrypt_transfer(
-    sk: &SigningKey,
+    key_manager: &DefaultKeyManager,
     ciphertext: &HexBinary,
 ) -> Result<ClearTextTransferRequestMsg, Status> {
-    let o =
-        decrypt(&sk.to_bytes(), ciphertext).map_err(|e| Status::invalid_argument(e.to_string()))?;
+    let o = decrypt_with_key_manager(key_manager, ciphertext)?;
 
     serde_json::from_slice(&o)
         .map_err(|e| Status::internal(format!("Could not deserialize transfer {}", e)))
 }
 
-fn decrypt_state(sk: &SigningKey, ciphertext: &[u8]) -> Result<State, Status> {
-    let o =
-        decrypt(&sk.to_bytes(), ciphertext).map_err(|e| Status::invalid_argument(e.to_string()))?;
+fn decrypt_state(key_manager: &DefaultKeyManager, ciphertext: &[u8]) -> Result<State, Status> {
+    let o = decrypt_with_key_manager(key_manager, ciphertext)?;
     serde_json::from_slice(&o).map_err(|e| Status::invalid_argument(e.to_string()))
 }
 
@@ -29,13 +29,10 @@ impl Handler<DefaultSharedEnclave<()>> for QueryRequest {
             serde_json::from_str(&message).map_err(|e| Status::invalid_argument(e.to_string()))?
         };
 
-        // Decrypt and deserialize the state
+        // Decrypt and deserialize the state (falling back to the previous key after a rotation)
         let state = match &message.state.to_vec()[..] {
             &[0] => State::default(),
-            state_bytes => {
-                let sk = ctx.key_manager.read_lock().await.sk.clone();
-                decrypt_state(&sk, state_bytes)?
-            }
+            state_bytes => decrypt_state(&*ctx.key_manager.read_lock().await, state_bytes)?,
         };
 
         let bal = match state.state.get(&message.address) {
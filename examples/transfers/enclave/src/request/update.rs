@@ -55,12 +55,14 @@ impl Handler<DefaultSharedEnclave<()>> for UpdateRequest {
             .ok_or_else(|| Status::not_found("config not found"))?;
         let (proof_value, message) = proof
             .verify(
+                ctx.store().await,
                 config.light_client_opts(),
                 contract,
                 REQUESTS_KEY.to_string(),
                 None,
             )
-            .map_err(Status::failed_precondition)?;
+            .await
+            .map_err(|e| Status::failed_precondition(e.to_string()))?;
 
         let proof_value_matches_msg =
             serde_json::to_string(&message.requests).is_ok_and(|s| s.as_bytes() == proof_value);
@@ -90,13 +92,11 @@ impl Handler<DefaultSharedEnclave<()>> for UpdateRequest {
                 .map_err(|_| Status::internal("store read error"))?;
         }
 
-        // Decrypt and deserialize the state
+        // Decrypt and deserialize the state (which may still be encrypted under the previous key
+        // after a key rotation, in which case it is re-encrypted under the current key below)
         let mut state = match &message.state.to_vec()[..] {
             &[0] => State::default(),
-            state_bytes => {
-                let sk = ctx.key_manager.read_lock().await.sk.clone();
-                decrypt_state(&sk, state_bytes)?
-            }
+            state_bytes => decrypt_state(&*ctx.key_manager.read_lock().await, state_bytes)?,
         };
 
         let requests_len = message.requests.len() as u32;
@@ -109,11 +109,8 @@ impl Handler<DefaultSharedEnclave<()>> for UpdateRequest {
             match req {
                 TransferRequest::Transfer(ciphertext) => {
                     // Decrypt transfer ciphertext into cleartext struct (acquires lock on enclave sk to do so)
-                    let transfer: ClearTextTransferRequestMsg = {
-                        let sk = ctx.key_manager.read_lock().await.sk.clone();
-
-                        decrypt_transfer(&sk, &ciphertext)?
-                    };
+                    let transfer: ClearTextTransferRequestMsg =
+                        decrypt_transfer(&*ctx.key_manager.read_lock().await, &ciphertext)?;
                     if let Entry::Occupied(mut entry) = state.state.entry(transfer.sender) {
                         let balance = entry.get();
                         if balance >= &transfer.amount {
@@ -144,7 +141,7 @@ impl Handler<DefaultSharedEnclave<()>> for UpdateRequest {
             }
         }
 
-        // Encrypt state
+        // Encrypt state (always under the current key)
         let state_enc = {
             let pk = ctx.key_manager().await.pub_key().await;
 